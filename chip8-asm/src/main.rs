@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use chip8_lib::{assembler::assemble_file, octo::compile_file};
+use clap::Parser;
+
+// Command line arguments of the assembler
+#[derive(Parser, Debug)]
+#[command(
+    name = "chip8-asm",
+    version,
+    about = "Assemble CHIP-8, SUPER-CHIP and XO-CHIP programs into ROMs"
+)]
+struct Args {
+    /// Path to the assembly source, .8o files are compiled as Octo
+    source: PathBuf,
+
+    /// Where to write the ROM, defaults to the source with a .ch8 extension
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Compile the source as Octo, whatever its extension
+    #[arg(long)]
+    octo: bool,
+
+    /// Write the symbol map of an Octo program to this file, for chip8-emu --symbols
+    #[arg(long, value_name = "FILE")]
+    symbols: Option<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let octo = args.octo || args.source.extension().is_some_and(|ext| ext == "8o");
+    let result = if octo {
+        compile_file(&args.source).map(|program| (program.rom, Some(program.symbols)))
+    } else {
+        assemble_file(&args.source).map(|rom| (rom, None))
+    };
+    let (rom, symbols) = match result {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("chip8-asm: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let output = args
+        .output
+        .unwrap_or_else(|| args.source.with_extension("ch8"));
+    if let Err(e) = fs::write(&output, &rom.data) {
+        eprintln!("chip8-asm: can't write {}: {}", output.display(), e);
+        process::exit(1);
+    }
+
+    if let Some(path) = args.symbols {
+        let symbols = match symbols {
+            Some(symbols) => symbols,
+            None => {
+                eprintln!("chip8-asm: symbol maps are only written for Octo programs");
+                process::exit(1);
+            }
+        };
+        if let Err(e) = fs::write(&path, symbols.to_text()) {
+            eprintln!("chip8-asm: can't write {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
+}
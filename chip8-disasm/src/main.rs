@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use chip8_lib::{
+    disassembler::{disassemble, listing, Syntax},
+    drivers::rom_driver::ROM,
+};
+use clap::Parser;
+
+// Command line arguments of the disassembler
+#[derive(Parser, Debug)]
+#[command(
+    name = "chip8-disasm",
+    version,
+    about = "Disassemble CHIP-8, SUPER-CHIP and XO-CHIP ROMs"
+)]
+struct Args {
+    /// Path to the ROM to disassemble
+    rom: PathBuf,
+
+    /// Write the listing to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Mnemonics to use: cowgod or octo
+    #[arg(short, long, default_value = "cowgod", value_parser = parse_syntax)]
+    syntax: Syntax,
+
+    /// Hex address the ROM is loaded at
+    #[arg(long, default_value = "200", value_parser = parse_address)]
+    origin: u16,
+
+    /// Hex address to start the listing at
+    #[arg(long, value_parser = parse_address)]
+    start: Option<u16>,
+
+    /// Hex address to end the listing before
+    #[arg(long, value_parser = parse_address)]
+    end: Option<u16>,
+}
+
+fn parse_syntax(name: &str) -> Result<Syntax, String> {
+    Syntax::from_name(name).ok_or_else(|| "expected cowgod or octo".to_string())
+}
+
+fn parse_address(hex: &str) -> Result<u16, String> {
+    let hex = hex.trim_start_matches("0x").trim_start_matches("0X");
+    u16::from_str_radix(hex, 16).map_err(|e| e.to_string())
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let rom = match ROM::from_file(&args.rom) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("chip8-disasm: can't load ROM {}: {}", args.rom.display(), e);
+            process::exit(1);
+        }
+    };
+
+    // Only decode the requested part of the ROM, keeping its addresses
+    let origin = args.origin as usize;
+    let rom_end = origin + rom.data.len();
+    let start = args
+        .start
+        .map_or(origin, |addr| addr as usize)
+        .clamp(origin, rom_end);
+    let end = args
+        .end
+        .map_or(rom_end, |addr| addr as usize)
+        .clamp(start, rom_end);
+    let lines = disassemble(&rom.data[start - origin..end - origin], start as u16);
+
+    let text = listing(&lines, args.syntax);
+    match args.output {
+        Some(path) => {
+            if let Err(e) = fs::write(&path, text) {
+                eprintln!("chip8-disasm: can't write {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", text),
+    }
+}
@@ -0,0 +1,205 @@
+use std::path::PathBuf;
+
+use chip8_lib::{
+    constants::*,
+    debugger::watchpoint::Watchpoint,
+    drivers::{
+        input_driver::{Keymap, KEYMAP_NAMES},
+        viewport::{ScaleMode, SCALE_MODE_NAMES},
+    },
+    palette,
+    quirks::{Quirks, PRESET_NAMES},
+    random::{RandomKind, RANDOM_NAMES},
+};
+use clap::Parser;
+
+// Command line arguments of the emulator
+#[derive(Parser, Debug)]
+#[command(
+    name = "chip8-emu",
+    version,
+    about = "A CHIP-8, SUPER-CHIP and XO-CHIP emulator"
+)]
+pub struct Args {
+    /// Path to the ROM to run
+    pub rom: PathBuf,
+
+    /// Instructions executed per second
+    #[arg(
+        short,
+        long,
+        default_value_t = INSTRUCTIONS_PER_FRAME * TIMER_HZ as u32,
+        conflicts_with = "play"
+    )]
+    pub clock: u32,
+
+    /// Quirks preset the ROM was written for: default, vip, chip48, schip or xochip
+    #[arg(
+        short,
+        long,
+        default_value = "default",
+        value_parser = parse_quirks,
+        conflicts_with = "play"
+    )]
+    pub quirks: Quirks,
+
+    /// Wrap memory addresses and keys around instead of stopping on an out of bounds access
+    #[arg(long, conflicts_with = "play")]
+    pub wrap_memory: bool,
+
+    /// Seed for the random number generator, a different one every run by default
+    #[arg(long, conflicts_with = "play")]
+    pub seed: Option<u64>,
+
+    /// Random number generator: xorshift, or vip for the COSMAC VIP's timing dependent one
+    #[arg(long, default_value = "xorshift", value_parser = parse_random, conflicts_with = "play")]
+    pub random: RandomKind,
+
+    /// Phosphor fade to stop flicker: the brightness a pixel that went dark keeps each frame,
+    /// from 0 (off) to below 1. Around 0.6 makes moving sprites look solid
+    #[arg(long, default_value_t = 0.0, value_parser = parse_decay)]
+    pub phosphor: f32,
+
+    /// Only draw the screen when a 60 Hz frame has finished
+    #[arg(long)]
+    pub vblank_draw: bool,
+
+    /// Window pixels per CHIP-8 pixel at startup, Ctrl and +/- change it.
+    /// Alt+Enter toggles fullscreen
+    #[arg(short, long, default_value_t = DEFAULT_SCREEN_SCALE, value_parser = parse_scale)]
+    pub scale: u32,
+
+    /// How the screen fills the window: integer for whole pixel multiples, aspect to fill
+    /// as much as possible. Both keep the aspect ratio and letterbox the rest
+    #[arg(long, default_value = "integer", value_parser = parse_scale_mode)]
+    pub scale_mode: ScaleMode,
+
+    /// Color palette: classic, amber, lcd, bw, high-contrast, one from the palette file,
+    /// or 2, 4 or 16 RRGGBB hex colors separated by commas. F9 cycles through them
+    #[arg(long, default_value = "classic")]
+    pub palette: String,
+
+    /// Palette file with more palettes, one "NAME = COLOR COLOR..." per line
+    #[arg(long, value_name = "FILE")]
+    pub palette_file: Option<PathBuf>,
+
+    /// Foreground color as RRGGBB hex, replaces the palette's
+    #[arg(long, value_parser = parse_color)]
+    pub fg: Option<u32>,
+
+    /// Background color as RRGGBB hex, replaces the palette's
+    #[arg(long, value_parser = parse_color)]
+    pub bg: Option<u32>,
+
+    /// Keyboard layout: qwerty, azerty or dvorak
+    #[arg(short, long, default_value = "qwerty", value_parser = parse_keymap)]
+    pub keymap: Keymap,
+
+    /// Keymap file binding keyboard keys and controller buttons, for every ROM or single ones.
+    /// Applied over the built in profiles for common games
+    #[arg(long, value_name = "FILE")]
+    pub keymap_file: Option<PathBuf>,
+
+    /// Disable sound
+    #[arg(short, long)]
+    pub mute: bool,
+
+    /// Start in fullscreen
+    #[arg(short, long)]
+    pub fullscreen: bool,
+
+    /// Start paused (Space resumes, F10 steps, F11 steps over, F12 steps out)
+    #[arg(short, long)]
+    pub paused: bool,
+
+    /// Snapshots kept for rewinding with Backspace
+    #[arg(long, default_value_t = REWIND_DEPTH)]
+    pub rewind_depth: usize,
+
+    /// Frames between two rewind snapshots
+    #[arg(long, default_value_t = REWIND_GRANULARITY)]
+    pub rewind_granularity: u32,
+
+    /// Start paused and print the machine state whenever the emulator stops
+    #[arg(short, long)]
+    pub debugger: bool,
+
+    /// Pause when PC reaches this hex address, can be repeated
+    #[arg(short, long = "break", value_name = "ADDR", value_parser = parse_address)]
+    pub breakpoints: Vec<u16>,
+
+    /// Pause when a watchpoint triggers, can be repeated.
+    /// e.g. "write 3A0+4", "read 300", "change [3A0]", "change v3 if v3 == 10" (numbers in hex)
+    #[arg(short, long = "watch", value_name = "WATCH", value_parser = parse_watchpoint)]
+    pub watchpoints: Vec<Watchpoint>,
+
+    /// Symbol map written by chip8-asm, to show label names and stop at its breakpoints
+    #[arg(long, value_name = "FILE")]
+    pub symbols: Option<PathBuf>,
+
+    /// Record the keys pressed into a movie file, written on exit
+    #[arg(long, value_name = "FILE", conflicts_with = "play")]
+    pub record: Option<PathBuf>,
+
+    /// Play a movie back, with the quirks, speed, random generator, seed and memory wrapping
+    /// it was recorded with, so those options can't be given with it.
+    /// Stops with an error if the screen differs from the recording.
+    #[arg(long, value_name = "FILE")]
+    pub play: Option<PathBuf>,
+}
+
+impl Args {
+    // Instructions per 60 Hz frame for the requested clock speed
+    pub fn instructions_per_frame(&self) -> u32 {
+        (self.clock / TIMER_HZ as u32).max(1)
+    }
+}
+
+fn parse_quirks(name: &str) -> Result<Quirks, String> {
+    Quirks::preset(name).ok_or_else(|| format!("expected one of {}", PRESET_NAMES.join(", ")))
+}
+
+fn parse_random(name: &str) -> Result<RandomKind, String> {
+    RandomKind::from_name(name)
+        .ok_or_else(|| format!("expected one of {}", RANDOM_NAMES.join(", ")))
+}
+
+fn parse_decay(text: &str) -> Result<f32, String> {
+    match text.parse() {
+        Ok(decay) if (0.0..1.0).contains(&decay) => Ok(decay),
+        _ => Err("expected a number from 0 to below 1".to_string()),
+    }
+}
+
+fn parse_scale(text: &str) -> Result<u32, String> {
+    match text.parse() {
+        Ok(scale) if (1..=MAX_SCREEN_SCALE).contains(&scale) => Ok(scale),
+        _ => Err(format!("expected a number from 1 to {}", MAX_SCREEN_SCALE)),
+    }
+}
+
+fn parse_scale_mode(name: &str) -> Result<ScaleMode, String> {
+    ScaleMode::from_name(name)
+        .ok_or_else(|| format!("expected one of {}", SCALE_MODE_NAMES.join(", ")))
+}
+
+fn parse_keymap(name: &str) -> Result<Keymap, String> {
+    Keymap::from_name(name).ok_or_else(|| format!("expected one of {}", KEYMAP_NAMES.join(", ")))
+}
+
+fn parse_address(hex: &str) -> Result<u16, String> {
+    let hex = hex.trim_start_matches("0x").trim_start_matches("0X");
+    u16::from_str_radix(hex, 16).map_err(|e| e.to_string())
+}
+
+fn parse_watchpoint(text: &str) -> Result<Watchpoint, String> {
+    Watchpoint::parse(text).ok_or_else(|| {
+        "expected read|write ADDR[+LEN] or change V0-VF|I|DT|ST|[ADDR[+LEN]], \
+         optionally followed by if VALUE OP NUMBER"
+            .to_string()
+    })
+}
+
+fn parse_color(hex: &str) -> Result<u32, String> {
+    palette::parse_color(hex).ok_or_else(|| "expected a RRGGBB hex color".to_string())
+}
@@ -1,94 +1,385 @@
-// Import SDL2
+mod cli;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Instant;
+
 use chip8_lib::{
-    cpu::Emulator,
-	constants::CLOCK_SPEED,
-    drivers::{rom_driver::ROM, screen_driver::Screen},
+    constants::FRAME_DURATION,
+    cpu::{Emulator, OutOfBounds},
+    debugger::{Debugger, StopReason},
+    drivers::{
+        audio_driver::BeeperConfig,
+        input_driver::Keyboard,
+        rom_driver::ROM,
+        screen_driver::{Screen, ScreenConfig},
+        speaker_driver::Speaker,
+    },
+    errors::Chip8Error,
+    filter::DisplayFilter,
+    keymap::KeymapConfig,
+    movie::{Movie, MovieMode, MovieSession},
+    palette::Palette,
+    platform::{Audio, Input, InputEvent, NullAudio},
+    random,
+    rewind::Rewind,
+    symbols::SymbolMap,
 };
-use sdl2::event::Event;
-use sdl2::{self, keyboard::Keycode};
+use clap::Parser;
+use cli::Args;
 
 fn main() {
-    // Initialize SDL2
-    let (mut screen, sdl_context) = Screen::new();
-    let rom = ROM::from_file("roms/INVADERS.ch8").unwrap();
+    let args = Args::parse();
 
-    // Initialize the emulator
-    let mut emulator = Emulator::new();
+    // A movie being played brings its own quirks, speed, memory wrapping and seed
+    let playing = args.play.as_ref().map(|path| {
+        Movie::from_file(path).unwrap_or_else(|e| {
+            eprintln!("chip8-emu: can't load movie {}: {}", path.display(), e);
+            process::exit(1);
+        })
+    });
 
-    // Load the ROM into the emulator
-    emulator.load_rom(rom);
+    // Load the ROM before opening any window
+    let seed = args.seed.unwrap_or_else(random::entropy_seed);
+    let mut emulator = match &playing {
+        Some(movie) => movie.emulator(),
+        None => {
+            let mut emulator = Emulator::new(args.quirks);
+            emulator.instructions_per_frame = args.instructions_per_frame();
+            emulator.rng = args.random.create(seed);
+            if args.wrap_memory {
+                emulator.out_of_bounds = OutOfBounds::Wrap;
+            }
+            emulator
+        }
+    };
+    let loaded = ROM::from_file(&args.rom).and_then(|rom| {
+        let name = rom.name.clone();
+        emulator.load_rom(rom).map(|()| name)
+    });
+    let rom_name = loaded.unwrap_or_else(|e| {
+        eprintln!("chip8-emu: can't load ROM {}: {}", args.rom.display(), e);
+        process::exit(1);
+    });
 
-    // Create an event pump
-    let mut event_pump = sdl_context.event_pump().unwrap();
+    // The built in profiles, then the user's keymap file, for this ROM
+    let mut keymap = args.keymap.clone();
+    KeymapConfig::builtin().apply(&mut keymap, &rom_name, emulator.rom_hash);
+    if let Some(path) = &args.keymap_file {
+        match KeymapConfig::from_file(path) {
+            Ok(config) => config.apply(&mut keymap, &rom_name, emulator.rom_hash),
+            Err(e) => {
+                eprintln!("chip8-emu: can't load keymap {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        }
+    }
+
+    // Keys go through the movie session while recording or playing
+    let mut session = match playing {
+        Some(movie) => {
+            if let Err(e) = movie.check_rom(&emulator) {
+                eprintln!("chip8-emu: {}", e);
+                process::exit(1);
+            }
+            Some(MovieSession::new(movie, MovieMode::Play))
+        }
+        None if args.record.is_some() => Some(MovieSession::new(
+            Movie::new(&emulator, args.random, seed),
+            MovieMode::Record,
+        )),
+        None => None,
+    };
+
+    // The palettes F9 cycles through: the built in ones, then the palette file's
+    let mut palettes = Palette::builtin();
+    if let Some(path) = &args.palette_file {
+        match Palette::from_file(path) {
+            Ok(loaded) => palettes.extend(loaded),
+            Err(e) => {
+                eprintln!("chip8-emu: can't load palettes {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        }
+    }
+    let palette = palettes
+        .iter()
+        .find(|palette| palette.name.eq_ignore_ascii_case(&args.palette))
+        .cloned()
+        .or_else(|| Palette::parse(&args.palette))
+        .unwrap_or_else(|| {
+            eprintln!("chip8-emu: unknown palette {}", args.palette);
+            process::exit(1);
+        })
+        .with_colors(args.bg, args.fg);
+    // A custom starting palette joins the cycle
+    let mut palette_index = palettes
+        .iter()
+        .position(|p| *p == palette)
+        .unwrap_or_else(|| {
+            palettes.insert(0, palette.clone());
+            0
+        });
+
+    // Initialize SDL2
+    let (mut screen, sdl_context) = Screen::new(ScreenConfig {
+        scale: args.scale,
+        scale_mode: args.scale_mode,
+        palette,
+        fullscreen: args.fullscreen,
+    });
+    let mut keyboard = Keyboard::new(&sdl_context, keymap);
+    // Fall back to silence when muted or there is no audio device
+    let mut audio: Box<dyn Audio> = if args.mute {
+        Box::new(NullAudio)
+    } else {
+        match Speaker::new(BeeperConfig::default()) {
+            Ok(speaker) => Box::new(speaker),
+            Err(e) => {
+                eprintln!("{}", e);
+                Box::new(NullAudio)
+            }
+        }
+    };
+
+    // Attach the debugger, every frontend action goes through it
+    let mut debugger = Debugger::new(&mut emulator);
+    if let Some(path) = &args.symbols {
+        match SymbolMap::from_file(path) {
+            Ok(symbols) => debugger.set_symbols(symbols),
+            Err(e) => eprintln!("Can't load symbols from {}: {}", path.display(), e),
+        }
+    }
+    for &addr in &args.breakpoints {
+        debugger.add_breakpoint(addr);
+    }
+    for &watchpoint in &args.watchpoints {
+        let id = debugger.add_watchpoint(watchpoint);
+        eprintln!("Watchpoint {}: {:?}", id, watchpoint);
+    }
+    if args.paused || args.debugger {
+        debugger.pause();
+    }
 
     // Main loop
+    let mut rewind = Rewind::new(args.rewind_depth, args.rewind_granularity);
+    let mut rewinding = false;
+    let mut filter = DisplayFilter::new(args.phosphor, args.vblank_draw);
+    let mut redraw = true; // The window needs drawing even if the emulator didn't change
+    let mut last_frame = Instant::now();
     'running: loop {
-        // Emulator cycle
-        emulator.tick().unwrap();
-        emulator.timer_tick();
-
         // Handle events
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
-                // Handle key presses
-                Event::KeyDown {
-                    keycode: Some(key), ..
-                } => {
-                    if let Some(key) = map_sdl_keys(key) {
-                        emulator.key_down(key);
+        for event in keyboard.poll() {
+            let stopped = match event {
+                InputEvent::Quit => break 'running,
+                InputEvent::KeyDown(_) | InputEvent::KeyUp(_) if session.is_some() => {
+                    if let Some(session) = &mut session {
+                        session.key(event);
+                    }
+                    Ok(None)
+                }
+                InputEvent::KeyDown(key) => {
+                    debugger.emulator.key_down(key);
+                    Ok(None)
+                }
+                InputEvent::KeyUp(key) => {
+                    debugger.emulator.key_up(key);
+                    Ok(None)
+                }
+                InputEvent::TogglePause if debugger.is_paused() => {
+                    debugger.resume();
+                    Ok(None)
+                }
+                InputEvent::TogglePause => {
+                    debugger.pause();
+                    Ok(Some(StopReason::Stepped))
+                }
+                // Stepping would run instructions outside of the movie's frames
+                InputEvent::Step | InputEvent::StepOver | InputEvent::StepOut
+                    if session.is_some() =>
+                {
+                    eprintln!("Can't step while a movie is running");
+                    Ok(None)
+                }
+                InputEvent::Step => debugger.step().map(Some),
+                InputEvent::StepOver => debugger.step_over(),
+                InputEvent::StepOut => debugger.step_out(),
+                InputEvent::SaveState(slot) => {
+                    let path = slot_path(&args.rom, slot);
+                    match fs::write(&path, debugger.emulator.save_state()) {
+                        Ok(()) => eprintln!("Saved state to {}", path.display()),
+                        Err(e) => eprintln!("Can't save state to {}: {}", path.display(), e),
                     }
+                    Ok(None)
                 }
-                // Handle key releases
-                Event::KeyUp {
-                    keycode: Some(key), ..
-                } => {
-                    if let Some(key) = map_sdl_keys(key) {
-                        emulator.key_up(key);
+                // Going back in time would break the movie
+                InputEvent::LoadState(_) | InputEvent::Rewind(true) if session.is_some() => {
+                    eprintln!("Can't load states or rewind while a movie is running");
+                    Ok(None)
+                }
+                InputEvent::LoadState(slot) => {
+                    let path = slot_path(&args.rom, slot);
+                    let quirks = debugger.emulator.quirks;
+                    let result = fs::read(&path).map_err(|e| e.to_string()).and_then(|data| {
+                        debugger
+                            .emulator
+                            .load_state(&data)
+                            .map_err(|e| e.to_string())
+                    });
+                    match result {
+                        // Redraw the restored screen, the rewind history is for another timeline
+                        Ok(()) => {
+                            if debugger.emulator.quirks != quirks {
+                                eprintln!(
+                                    "{} was saved with other quirks, using them",
+                                    path.display()
+                                );
+                            }
+                            debugger.emulator.draw_flag = true;
+                            rewind.clear();
+                        }
+                        Err(e) => eprintln!("Can't load state from {}: {}", path.display(), e),
                     }
+                    Ok(None)
+                }
+                InputEvent::Rewind(held) => {
+                    rewinding = held;
+                    Ok(None)
+                }
+                InputEvent::ToggleFullscreen => {
+                    screen.toggle_fullscreen();
+                    Ok(None)
+                }
+                InputEvent::ChangeScale(steps) => {
+                    screen.set_scale(screen.scale.saturating_add_signed(steps));
+                    Ok(None)
+                }
+                InputEvent::Redraw => {
+                    redraw = true;
+                    Ok(None)
                 }
-                _ => {}
+                InputEvent::NextPalette => {
+                    palette_index = (palette_index + 1) % palettes.len();
+                    screen.palette = palettes[palette_index].clone();
+                    redraw = true;
+                    eprintln!("Palette: {}", screen.palette.name);
+                    Ok(None)
+                }
+            };
+            report(&debugger, stopped, args.debugger);
+        }
+
+        // Run the emulator for the time that passed since the last loop, or go back a frame
+        let now = Instant::now();
+        if !debugger.is_paused() {
+            if rewinding {
+                rewind.step_back(debugger.emulator);
+            } else {
+                let frames = debugger.emulator.frames;
+                let stopped = match &mut session {
+                    Some(session) => session.run_for(&mut debugger, now - last_frame),
+                    None => debugger.run_for(now - last_frame),
+                };
+                if let Err(e @ Chip8Error::Desync { .. }) = stopped {
+                    eprintln!("chip8-emu: {}", e);
+                    process::exit(1);
+                }
+                // Save a recording that ends in a fault right away, it reproduces the crash
+                if stopped.is_err() {
+                    save_movie(&args, &session);
+                }
+                report(&debugger, stopped, args.debugger);
+                // Only frames that ran count, the loop can be faster or slower than 60 Hz
+                rewind.record(debugger.emulator, debugger.emulator.frames - frames);
             }
         }
+        last_frame = now;
 
-        // Draw the screen
-        if emulator.draw_flag {
-            screen.draw_screen(&emulator.screen);
-            emulator.draw_flag = false;
+        // Every recorded frame played back the same
+        if session
+            .as_ref()
+            .is_some_and(|session| session.finished(debugger.emulator))
+        {
+            eprintln!("Movie finished after {} frames", debugger.emulator.frames);
+            break 'running;
+        }
+
+        // The ROM ran the SUPER-CHIP exit instruction
+        if debugger.emulator.halted {
+            break 'running;
+        }
+
+        // Draw the screen through the display filter
+        if redraw || filter.should_draw(debugger.emulator) {
+            let colors = filter.apply(debugger.emulator, &screen.palette);
+            let frame = &debugger.emulator.screen;
+            screen.draw_colors(frame.width, frame.height, &colors);
+            debugger.emulator.draw_flag = false;
+            redraw = false;
         }
 
         // Handle audio
-        if emulator.st > 0 {
-            // Play Sound
+        let emulator = &debugger.emulator;
+        audio.set_pattern(emulator.buzzer_pattern(), emulator.pitch);
+        audio.set_playing(!debugger.is_paused() && emulator.st > 0);
+
+        // Sleep until the next frame is due
+        std::thread::sleep(FRAME_DURATION.saturating_sub(now.elapsed()));
+    }
+
+    save_movie(&args, &session);
+}
+
+// Write the movie being recorded, if there is one
+fn save_movie(args: &Args, session: &Option<MovieSession>) {
+    if let (Some(path), Some(session)) = (&args.record, session) {
+        match fs::write(path, session.movie.to_text()) {
+            Ok(()) => eprintln!("Saved movie to {}", path.display()),
+            Err(e) => eprintln!("Can't save movie to {}: {}", path.display(), e),
+        }
+    }
+}
+
+// Tell the user why the emulator stopped; errors leave the debugger paused on the fault
+// Single steps are only reported in debugger mode.
+fn report(debugger: &Debugger, stopped: Result<Option<StopReason>, Chip8Error>, verbose: bool) {
+    match stopped {
+        Ok(Some(StopReason::Stepped)) if !verbose => {}
+        Ok(Some(reason)) => {
+            eprintln!("{:?}", reason);
+            print_state(debugger);
+        }
+        Ok(_) => {}
+        Err(Chip8Error::Fault(fault)) => eprint!("{}", debugger.crash_report(&fault)),
+        Err(e) => {
+            eprintln!("chip8-emu: {}", e);
+            print_state(debugger);
         }
+    }
+}
 
-        // Sleep according to the clock speed
-		std::thread::sleep(std::time::Duration::from_millis(CLOCK_SPEED));
-	}
+// Quick save slots are stored next to the ROM: `roms/Pong.state1`
+fn slot_path(rom: &Path, slot: u8) -> PathBuf {
+    rom.with_extension(format!("state{}", slot))
 }
 
-fn map_sdl_keys(key: Keycode) -> Option<u8> {
-    match key {
-		Keycode::Num1 => Some(0x1),
-		Keycode::Num2 => Some(0x2),
-		Keycode::Num3 => Some(0x3),
-		Keycode::Num4 => Some(0xC),
-		Keycode::Q => Some(0x4),
-		Keycode::W => Some(0x5),
-		Keycode::E => Some(0x6),
-		Keycode::R => Some(0xD),
-		Keycode::A => Some(0x7),
-		Keycode::S => Some(0x8),
-		Keycode::D => Some(0x9),
-		Keycode::F => Some(0xE),
-		Keycode::Z => Some(0xA),
-		Keycode::X => Some(0x0),
-		Keycode::C => Some(0xB),
-		Keycode::V => Some(0xF),
-		_ => None,
-	}
+// Print the registers and the next instruction for the debugger
+fn print_state(debugger: &Debugger) {
+    let emulator = &*debugger.emulator;
+    let instruction = emulator
+        .peek()
+        .map_or("???".to_string(), |instruction| instruction.to_string());
+    eprintln!(
+        "PC={:04X} ({}) [{:04X} {}] I={:04X} SP={} DT={} ST={} V={:02X?}",
+        emulator.pc,
+        debugger.symbols.describe(emulator.pc),
+        emulator.read_word(emulator.pc),
+        instruction,
+        emulator.i,
+        emulator.sp,
+        emulator.dt,
+        emulator.st,
+        emulator.v
+    );
 }
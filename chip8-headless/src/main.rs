@@ -0,0 +1,200 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use chip8_lib::{
+    constants::*,
+    cpu::{Emulator, OutOfBounds},
+    debugger::Debugger,
+    drivers::rom_driver::ROM,
+    errors::Chip8Error,
+    headless::{InputScript, Limit, Outcome, Runner, ScreenFormat, SCREEN_FORMAT_NAMES},
+    quirks::{Quirks, PRESET_NAMES},
+    random::{RandomKind, RANDOM_NAMES},
+};
+use clap::Parser;
+
+// Command line arguments of the headless runner
+#[derive(Parser, Debug)]
+#[command(
+    name = "chip8-headless",
+    version,
+    about = "Run a CHIP-8 ROM without a display and dump its final state",
+    after_help = "Exit codes: 0 finished or halted, 1 bad arguments or files, \
+                  2 invalid instruction, 3 stack error, 4 timeout, 5 other emulator error, \
+                  6 memory or key out of bounds, 7 invalid register"
+)]
+struct Args {
+    /// Path to the ROM to run
+    rom: PathBuf,
+
+    /// Instructions to run
+    #[arg(long, conflicts_with = "frames")]
+    cycles: Option<u64>,
+
+    /// 60 Hz frames to run
+    #[arg(long, default_value_t = 600)]
+    frames: u64,
+
+    /// Instructions executed per second
+    #[arg(short, long, default_value_t = INSTRUCTIONS_PER_FRAME * TIMER_HZ as u32)]
+    clock: u32,
+
+    /// Quirks preset the ROM was written for: default, vip, chip48, schip or xochip
+    #[arg(short, long, default_value = "default", value_parser = parse_quirks)]
+    quirks: Quirks,
+
+    /// Wrap memory addresses and keys around instead of stopping on an out of bounds access
+    #[arg(long)]
+    wrap_memory: bool,
+
+    /// Seed for the random number generator
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Random number generator: xorshift, or vip for the COSMAC VIP's timing dependent one
+    #[arg(long, default_value = "xorshift", value_parser = parse_random)]
+    random: RandomKind,
+
+    /// Input script, one "FRAME down|up KEY" per line with KEY in hex
+    #[arg(short, long, value_name = "FILE")]
+    input: Option<PathBuf>,
+
+    /// Write the final screen to this file, "-" for stdout
+    #[arg(short, long, value_name = "FILE", default_value = "-")]
+    screen: PathBuf,
+
+    /// Screen format: ascii, pbm or png. Guessed from the screen file's extension by default
+    #[arg(short, long, value_parser = parse_format)]
+    format: Option<ScreenFormat>,
+
+    /// Write the registers and memory as JSON to this file, "-" for stdout
+    #[arg(long, value_name = "FILE")]
+    state: Option<PathBuf>,
+
+    /// Wall clock seconds the run may take
+    #[arg(short, long, value_name = "SECONDS")]
+    timeout: Option<f64>,
+}
+
+fn parse_quirks(name: &str) -> Result<Quirks, String> {
+    Quirks::preset(name).ok_or_else(|| format!("expected one of {}", PRESET_NAMES.join(", ")))
+}
+
+fn parse_random(name: &str) -> Result<RandomKind, String> {
+    RandomKind::from_name(name)
+        .ok_or_else(|| format!("expected one of {}", RANDOM_NAMES.join(", ")))
+}
+
+fn parse_format(name: &str) -> Result<ScreenFormat, String> {
+    ScreenFormat::from_name(name)
+        .ok_or_else(|| format!("expected one of {}", SCREEN_FORMAT_NAMES.join(", ")))
+}
+
+// Write to a file, or to stdout for "-"
+fn write_output(path: &Path, data: &[u8]) -> io::Result<()> {
+    if path == Path::new("-") {
+        io::stdout().write_all(data)
+    } else {
+        fs::write(path, data)
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let rom = match ROM::from_file(&args.rom) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!(
+                "chip8-headless: can't load ROM {}: {}",
+                args.rom.display(),
+                e
+            );
+            process::exit(1);
+        }
+    };
+
+    let script = match &args.input {
+        Some(path) => match fs::read_to_string(path).map(|text| InputScript::parse(&text)) {
+            Ok(Some(script)) => script,
+            Ok(None) => {
+                eprintln!("chip8-headless: {} is not an input script", path.display());
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("chip8-headless: can't read {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        },
+        None => InputScript::new(),
+    };
+
+    let format = args.format.unwrap_or_else(|| {
+        args.screen
+            .extension()
+            .and_then(|ext| ScreenFormat::from_name(&ext.to_string_lossy()))
+            .unwrap_or(ScreenFormat::Ascii)
+    });
+
+    let mut emulator = Emulator::new(args.quirks);
+    emulator.instructions_per_frame = (args.clock / TIMER_HZ as u32).max(1);
+    if args.wrap_memory {
+        emulator.out_of_bounds = OutOfBounds::Wrap;
+    }
+    emulator.rng = args.random.create(args.seed);
+    if let Err(e) = emulator.load_rom(rom) {
+        eprintln!(
+            "chip8-headless: can't load ROM {}: {}",
+            args.rom.display(),
+            e
+        );
+        process::exit(1);
+    }
+
+    let mut runner = Runner::new(emulator, script);
+    runner.timeout = args.timeout.map(Duration::from_secs_f64);
+    let limit = match args.cycles {
+        Some(cycles) => Limit::Cycles(cycles),
+        None => Limit::Frames(args.frames),
+    };
+    let outcome = runner.run(limit);
+
+    // Dump the state even when the run failed, it's what shows why
+    let summary = format!(
+        "after {} cycles, {} frames",
+        runner.emulator.cycles, runner.emulator.frames
+    );
+    match &outcome {
+        Outcome::Finished => eprintln!("Finished {}", summary),
+        Outcome::Halted => eprintln!("Halted {}", summary),
+        Outcome::Timeout => eprintln!("chip8-headless: timed out {}", summary),
+        Outcome::Error(Chip8Error::Fault(fault)) => {
+            eprint!(
+                "{}",
+                Debugger::new(&mut runner.emulator).crash_report(fault)
+            )
+        }
+        Outcome::Error(e) => eprintln!("chip8-headless: {} {}", e, summary),
+    }
+
+    let screen = format.encode(&runner.emulator.screen);
+    if let Err(e) = write_output(&args.screen, &screen) {
+        eprintln!(
+            "chip8-headless: can't write {}: {}",
+            args.screen.display(),
+            e
+        );
+        process::exit(1);
+    }
+    if let Some(path) = &args.state {
+        if let Err(e) = write_output(path, runner.state_json().as_bytes()) {
+            eprintln!("chip8-headless: can't write {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
+
+    process::exit(outcome.exit_code());
+}
@@ -0,0 +1,582 @@
+// Assembler: turns Cowgod style assembly, as listed by the disassembler, into ROMs
+//
+// Every line holds at most one statement, mnemonics and directives are case insensitive:
+//   label:              a label at the current address, can come before a statement
+//   NAME EQU value      a constant
+//   CLS, LD V0, 0x12    instructions, including the SUPER-CHIP and XO-CHIP ones
+//   DB 1, 0x02, "AB"    bytes
+//   DW 0x1234, label    16-bit big endian words
+//   INCLUDE "file.asm"  assemble another file in place, relative to the including file
+//   ; comment
+// Values are decimal, 0x or $ hex, or 0b binary numbers and symbols, added or subtracted.
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::constants::*;
+use crate::drivers::rom_driver::ROM;
+use crate::errors::AsmError;
+
+// How deep INCLUDE can nest, catches files including themselves
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+// Names that are part of the syntax and can't be symbols
+const RESERVED: [&str; 10] = ["I", "DT", "ST", "K", "F", "HF", "B", "R", "LONG", "EQU"];
+
+// A line of source, after includes were expanded
+struct SourceLine {
+    file: String,
+    line: usize,
+    text: String,
+}
+
+impl SourceLine {
+    fn error<S: Into<String>>(&self, column: usize, message: S) -> AsmError {
+        AsmError {
+            file: self.file.clone(),
+            line: self.line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+// A piece of a line and the column it starts at
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    column: usize,
+}
+
+// A statement with the label before it split off
+struct Statement<'a> {
+    label: Option<Token<'a>>,
+    name: Option<Token<'a>>, // Mnemonic, directive or constant name
+    rest: Option<Token<'a>>, // Everything after the name
+}
+
+// An instruction operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arg {
+    V(u16),
+    Range(u16, u16), // Vx - Vy
+    I,
+    IndirectI, // [I]
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    B,
+    R,
+    Long(i64), // LONG value
+    Value(i64),
+}
+
+// Assemble source text into a ROM, `name` is used for the ROM and in errors
+// The source has no file of its own, so its INCLUDE paths are relative to the working
+// directory. Files it includes still include relative to themselves.
+pub fn assemble(name: &str, source: &str) -> Result<ROM, AsmError> {
+    let mut lines = Vec::new();
+    expand(name, source, Path::new(""), 0, &mut lines)?;
+    let data = Assembler::new().run(&lines)?;
+    Ok(ROM::new(data, name.to_string()))
+}
+
+// Assemble a source file into a ROM named after it
+pub fn assemble_file<P: AsRef<Path>>(path: P) -> Result<ROM, AsmError> {
+    let path = path.as_ref();
+    let file = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|e| AsmError {
+        file: file.clone(),
+        line: 0,
+        column: 0,
+        message: e.to_string(),
+    })?;
+
+    let mut lines = Vec::new();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    expand(&file, &source, dir, 0, &mut lines)?;
+    let data = Assembler::new().run(&lines)?;
+
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or_default()
+        .to_string();
+    Ok(ROM::new(data, name))
+}
+
+// Split the source into lines, replacing INCLUDE statements with the included lines
+fn expand(
+    file: &str,
+    source: &str,
+    dir: &Path,
+    depth: usize,
+    out: &mut Vec<SourceLine>,
+) -> Result<(), AsmError> {
+    for (n, text) in source.lines().enumerate() {
+        let line = SourceLine {
+            file: file.to_string(),
+            line: n + 1,
+            text: text.to_string(),
+        };
+        let statement = split_statement(&line)?;
+        let include = match statement.name {
+            Some(name) if name.text.eq_ignore_ascii_case("INCLUDE") => statement.rest,
+            _ => {
+                out.push(line);
+                continue;
+            }
+        };
+
+        let column = statement.name.map_or(1, |name| name.column);
+        if let Some(label) = statement.label {
+            return Err(line.error(label.column, "INCLUDE can't have a label"));
+        }
+        let path = match include.and_then(|path| string_literal(path.text)) {
+            Some(path) => dir.join(path),
+            None => return Err(line.error(column, "expected INCLUDE \"file\"")),
+        };
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err(line.error(column, "INCLUDE nested too deeply"));
+        }
+        let included = fs::read_to_string(&path)
+            .map_err(|e| line.error(column, format!("can't include {}: {}", path.display(), e)))?;
+        let included_dir: PathBuf = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        expand(
+            &path.display().to_string(),
+            &included,
+            &included_dir,
+            depth + 1,
+            out,
+        )?;
+    }
+    Ok(())
+}
+
+// The text of a "quoted" string, without escapes
+fn string_literal(text: &str) -> Option<&str> {
+    text.trim().strip_prefix('"')?.strip_suffix('"')
+}
+
+// Skip whitespace from `pos`, returns the new position
+fn skip_space(text: &str, pos: usize) -> usize {
+    pos + (text.len() - pos - text[pos..].trim_start().len())
+}
+
+// Split a line into label, name and the rest, dropping the comment
+fn split_statement(line: &SourceLine) -> Result<Statement<'_>, AsmError> {
+    // The comment starts at the first ; outside of a string
+    let mut in_string = false;
+    let end = line
+        .text
+        .char_indices()
+        .find(|&(_, c)| {
+            if c == '"' {
+                in_string = !in_string;
+            }
+            c == ';' && !in_string
+        })
+        .map_or(line.text.len(), |(pos, _)| pos);
+    let text = line.text[..end].trim_end();
+
+    let word_end = |pos: usize| {
+        text[pos..]
+            .find(char::is_whitespace)
+            .map_or(text.len(), |len| pos + len)
+    };
+    let token = |start: usize, end: usize| Token {
+        text: &text[start..end],
+        column: start + 1,
+    };
+
+    let mut pos = skip_space(text, 0);
+    let mut label = None;
+    if pos < text.len() {
+        let end = word_end(pos);
+        if let Some(name) = text[pos..end].strip_suffix(':') {
+            check_symbol(line, name, pos + 1)?;
+            label = Some(token(pos, pos + name.len()));
+            pos = skip_space(text, end);
+        }
+    }
+
+    let mut name = None;
+    let mut rest = None;
+    if pos < text.len() {
+        let end = word_end(pos);
+        name = Some(token(pos, end));
+        let start = skip_space(text, end);
+        if start < text.len() {
+            rest = Some(token(start, text.len()));
+        }
+    }
+    Ok(Statement { label, name, rest })
+}
+
+// Symbols start with a letter, _ or . and can't be registers or keywords
+fn check_symbol(line: &SourceLine, name: &str, column: usize) -> Result<(), AsmError> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    let upper = name.to_ascii_uppercase();
+    if !valid || RESERVED.contains(&upper.as_str()) || parse_register(name).is_some() {
+        return Err(line.error(column, format!("invalid symbol name `{}`", name)));
+    }
+    Ok(())
+}
+
+// Split operands on commas outside of strings
+fn split_operands(rest: Option<Token>) -> Vec<Token> {
+    let rest = match rest {
+        Some(rest) => rest,
+        None => return Vec::new(),
+    };
+    let mut operands = Vec::new();
+    let mut push = |start: usize, end: usize| {
+        let raw = &rest.text[start..end];
+        operands.push(Token {
+            text: raw.trim(),
+            column: rest.column + start + raw.len() - raw.trim_start().len(),
+        });
+    };
+    let mut in_string = false;
+    let mut start = 0;
+    for (pos, c) in rest.text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                push(start, pos);
+                start = pos + 1;
+            }
+            _ => {}
+        }
+    }
+    push(start, rest.text.len());
+    operands
+}
+
+// V0 - VF
+fn parse_register(text: &str) -> Option<u16> {
+    let x = text.strip_prefix(['v', 'V'])?;
+    if x.len() != 1 {
+        return None;
+    }
+    u16::from_str_radix(x, 16).ok()
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let lower = text.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x").or_else(|| lower.strip_prefix('$')) {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+struct Assembler {
+    symbols: HashMap<String, i64>,
+}
+
+impl Assembler {
+    fn new() -> Self {
+        Self {
+            symbols: HashMap::new(),
+        }
+    }
+
+    // Two passes: find the address of every label, then encode
+    fn run(&mut self, lines: &[SourceLine]) -> Result<Vec<u8>, AsmError> {
+        let mut addr = ROM_START as usize;
+        let mut statements = Vec::new();
+        for line in lines {
+            let statement = split_statement(line)?;
+            if let Some(label) = statement.label {
+                self.define(line, label, addr as i64)?;
+            }
+            let name = match statement.name {
+                Some(name) => name,
+                None => continue,
+            };
+
+            // NAME EQU value
+            if let Some(rest) = statement.rest {
+                let (keyword, value) = rest.text.split_at(word_len(rest.text));
+                if keyword.eq_ignore_ascii_case("EQU") {
+                    check_symbol(line, name.text, name.column)?;
+                    let column =
+                        rest.column + keyword.len() + value.len() - value.trim_start().len();
+                    let value = self.eval(line, value.trim(), column)?;
+                    self.define(line, name, value)?;
+                    continue;
+                }
+            }
+
+            addr += self.size(line, name, statement.rest)?;
+            if addr > XO_MEMORY_SIZE {
+                return Err(line.error(name.column, "program doesn't fit in memory"));
+            }
+            statements.push((line, name, statement.rest));
+        }
+
+        let mut data = Vec::new();
+        for (line, name, rest) in statements {
+            self.encode(line, name, rest, &mut data)?;
+        }
+        Ok(data)
+    }
+
+    fn define(&mut self, line: &SourceLine, name: Token, value: i64) -> Result<(), AsmError> {
+        if self.symbols.insert(name.text.to_string(), value).is_some() {
+            return Err(line.error(name.column, format!("`{}` is already defined", name.text)));
+        }
+        Ok(())
+    }
+
+    // Bytes a statement assembles to
+    fn size(&self, line: &SourceLine, name: Token, rest: Option<Token>) -> Result<usize, AsmError> {
+        let operands = split_operands(rest);
+        match name.text.to_ascii_uppercase().as_str() {
+            "DB" => Ok(operands
+                .iter()
+                .map(|op| string_literal(op.text).map_or(1, str::len))
+                .sum()),
+            "DW" => Ok(operands.len() * 2),
+            // LD I, LONG addr is the only 4 byte instruction
+            "LD" if operands.get(1).is_some_and(|op| is_long(op.text)) => Ok(4),
+            mnemonic if MNEMONICS.contains(&mnemonic) => Ok(2),
+            _ => Err(line.error(name.column, format!("unknown instruction `{}`", name.text))),
+        }
+    }
+
+    // Evaluate a sum of numbers and symbols
+    fn eval(&self, line: &SourceLine, text: &str, column: usize) -> Result<i64, AsmError> {
+        let bytes = text.as_bytes();
+        let mut total = 0;
+        let mut sign = 1;
+        let mut term_start = None;
+        let mut pos = 0;
+        loop {
+            let c = bytes.get(pos).copied();
+            match (c, term_start) {
+                // A + or - after a term ends it
+                (None | Some(b'+') | Some(b'-'), Some(start)) => {
+                    let term = text[start..pos].trim_end();
+                    total += sign * self.term(line, term, column + start)?;
+                    term_start = None;
+                    sign = if c == Some(b'-') { -1 } else { 1 };
+                    if c.is_none() {
+                        return Ok(total);
+                    }
+                }
+                (None, None) => return Err(line.error(column + pos, "expected a value")),
+                (Some(b'-'), None) => sign = -sign,
+                (Some(b'+'), None) => {}
+                (Some(c), None) if !c.is_ascii_whitespace() => term_start = Some(pos),
+                _ => {}
+            }
+            pos += 1;
+        }
+    }
+
+    // A number or a symbol
+    fn term(&self, line: &SourceLine, text: &str, column: usize) -> Result<i64, AsmError> {
+        if text.starts_with(|c: char| c.is_ascii_digit() || c == '$') {
+            return parse_number(text)
+                .ok_or_else(|| line.error(column, format!("invalid number `{}`", text)));
+        }
+        self.symbols
+            .get(text)
+            .copied()
+            .ok_or_else(|| line.error(column, format!("unknown symbol `{}`", text)))
+    }
+
+    fn parse_arg(&self, line: &SourceLine, op: Token) -> Result<Arg, AsmError> {
+        let upper = op.text.to_ascii_uppercase();
+        let arg = match upper.as_str() {
+            "I" => Arg::I,
+            "[I]" => Arg::IndirectI,
+            "DT" => Arg::Dt,
+            "ST" => Arg::St,
+            "K" => Arg::K,
+            "F" => Arg::F,
+            "HF" => Arg::Hf,
+            "B" => Arg::B,
+            "R" => Arg::R,
+            _ => {
+                if let Some(x) = parse_register(op.text) {
+                    Arg::V(x)
+                } else if let Some((x, y)) = parse_range(op.text) {
+                    Arg::Range(x, y)
+                } else if is_long(op.text) {
+                    let value = &op.text[4..];
+                    let column = op.column + 4 + value.len() - value.trim_start().len();
+                    Arg::Long(self.eval(line, value.trim(), column)?)
+                } else {
+                    Arg::Value(self.eval(line, op.text, op.column)?)
+                }
+            }
+        };
+        Ok(arg)
+    }
+
+    fn encode(
+        &self,
+        line: &SourceLine,
+        name: Token,
+        rest: Option<Token>,
+        out: &mut Vec<u8>,
+    ) -> Result<(), AsmError> {
+        let operands = split_operands(rest);
+        let mnemonic = name.text.to_ascii_uppercase();
+
+        // Data directives
+        if mnemonic == "DB" || mnemonic == "DW" {
+            for op in operands {
+                match string_literal(op.text) {
+                    Some(text) if mnemonic == "DB" => out.extend_from_slice(text.as_bytes()),
+                    _ if mnemonic == "DB" => {
+                        let value = self.eval(line, op.text, op.column)?;
+                        out.push(check(line, value, 0xFF, "byte", op.column)? as u8);
+                    }
+                    _ => {
+                        let value = self.eval(line, op.text, op.column)?;
+                        out.extend_from_slice(
+                            &check(line, value, 0xFFFF, "word", op.column)?.to_be_bytes(),
+                        );
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        let args = operands
+            .iter()
+            .map(|&op| self.parse_arg(line, op))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Column of the nth operand, or of the mnemonic
+        let col = |n: usize| operands.get(n).map_or(name.column, |op| op.column);
+        let addr = |value: i64, n: usize| check(line, value, 0xFFF, "address", col(n));
+        let byte = |value: i64, n: usize| check(line, value, 0xFF, "byte", col(n));
+        let nibble = |value: i64, n: usize| check(line, value, 0xF, "nibble", col(n));
+        let xy = |x: u16, y: u16| x << 8 | y << 4;
+
+        use Arg::*;
+        let opcode = match (mnemonic.as_str(), args.as_slice()) {
+            ("SCD", &[Value(n)]) => 0x00C0 | nibble(n, 0)?,
+            ("SCU", &[Value(n)]) => 0x00D0 | nibble(n, 0)?,
+            ("CLS", &[]) => 0x00E0,
+            ("RET", &[]) => 0x00EE,
+            ("SCR", &[]) => 0x00FB,
+            ("SCL", &[]) => 0x00FC,
+            ("EXIT", &[]) => 0x00FD,
+            ("LOW", &[]) => 0x00FE,
+            ("HIGH", &[]) => 0x00FF,
+
+            ("JP", &[Value(a)]) => 0x1000 | addr(a, 0)?,
+            ("JP", &[V(0), Value(a)]) => 0xB000 | addr(a, 1)?,
+            ("CALL", &[Value(a)]) => 0x2000 | addr(a, 0)?,
+            ("SE", &[V(x), Value(b)]) => 0x3000 | x << 8 | byte(b, 1)?,
+            ("SNE", &[V(x), Value(b)]) => 0x4000 | x << 8 | byte(b, 1)?,
+            ("SE", &[V(x), V(y)]) => 0x5000 | xy(x, y),
+            ("SNE", &[V(x), V(y)]) => 0x9000 | xy(x, y),
+            ("LD", &[IndirectI, Range(x, y)]) => 0x5002 | xy(x, y),
+            ("LD", &[Range(x, y), IndirectI]) => 0x5003 | xy(x, y),
+            ("LD", &[V(x), Value(b)]) => 0x6000 | x << 8 | byte(b, 1)?,
+            ("ADD", &[V(x), Value(b)]) => 0x7000 | x << 8 | byte(b, 1)?,
+
+            ("LD", &[V(x), V(y)]) => 0x8000 | xy(x, y),
+            ("OR", &[V(x), V(y)]) => 0x8001 | xy(x, y),
+            ("AND", &[V(x), V(y)]) => 0x8002 | xy(x, y),
+            ("XOR", &[V(x), V(y)]) => 0x8003 | xy(x, y),
+            ("ADD", &[V(x), V(y)]) => 0x8004 | xy(x, y),
+            ("SUB", &[V(x), V(y)]) => 0x8005 | xy(x, y),
+            ("SHR", &[V(x)]) => 0x8006 | xy(x, x),
+            ("SHR", &[V(x), V(y)]) => 0x8006 | xy(x, y),
+            ("SUBN", &[V(x), V(y)]) => 0x8007 | xy(x, y),
+            ("SHL", &[V(x)]) => 0x800E | xy(x, x),
+            ("SHL", &[V(x), V(y)]) => 0x800E | xy(x, y),
+
+            ("LD", &[I, Value(a)]) => 0xA000 | addr(a, 1)?,
+            ("RND", &[V(x), Value(b)]) => 0xC000 | x << 8 | byte(b, 1)?,
+            ("DRW", &[V(x), V(y), Value(n)]) => 0xD000 | xy(x, y) | nibble(n, 2)?,
+            ("SKP", &[V(x)]) => 0xE09E | x << 8,
+            ("SKNP", &[V(x)]) => 0xE0A1 | x << 8,
+
+            ("LD", &[I, Long(a)]) => {
+                let a = check(line, a, 0xFFFF, "address", col(1))?;
+                out.extend_from_slice(&[0xF0, 0x00]);
+                a
+            }
+            ("PLANE", &[Value(n)]) => 0xF001 | nibble(n, 0)? << 8,
+            ("AUDIO", &[]) => 0xF002,
+            ("LD", &[V(x), Dt]) => 0xF007 | x << 8,
+            ("LD", &[V(x), K]) => 0xF00A | x << 8,
+            ("LD", &[Dt, V(x)]) => 0xF015 | x << 8,
+            ("LD", &[St, V(x)]) => 0xF018 | x << 8,
+            ("ADD", &[I, V(x)]) => 0xF01E | x << 8,
+            ("LD", &[F, V(x)]) => 0xF029 | x << 8,
+            ("LD", &[Hf, V(x)]) => 0xF030 | x << 8,
+            ("PITCH", &[V(x)]) => 0xF03A | x << 8,
+            ("LD", &[B, V(x)]) => 0xF033 | x << 8,
+            ("LD", &[IndirectI, V(x)]) => 0xF055 | x << 8,
+            ("LD", &[V(x), IndirectI]) => 0xF065 | x << 8,
+            ("LD", &[R, V(x)]) => 0xF075 | x << 8,
+            ("LD", &[V(x), R]) => 0xF085 | x << 8,
+
+            _ => {
+                return Err(line.error(name.column, format!("invalid operands for `{}`", name.text)))
+            }
+        };
+        out.extend_from_slice(&opcode.to_be_bytes());
+        Ok(())
+    }
+}
+
+// Every instruction mnemonic, DB and DW are handled separately
+const MNEMONICS: [&str; 29] = [
+    "SCD", "SCU", "CLS", "RET", "SCR", "SCL", "EXIT", "LOW", "HIGH", "JP", "CALL", "SE", "SNE",
+    "LD", "ADD", "OR", "AND", "XOR", "SUB", "SHR", "SUBN", "SHL", "RND", "DRW", "SKP", "SKNP",
+    "PLANE", "AUDIO", "PITCH",
+];
+
+// Length of the first word of the text
+fn word_len(text: &str) -> usize {
+    text.find(char::is_whitespace).unwrap_or(text.len())
+}
+
+// LONG value
+fn is_long(text: &str) -> bool {
+    text.len() > 4
+        && text[..4].eq_ignore_ascii_case("LONG")
+        && text[4..].starts_with(char::is_whitespace)
+}
+
+// Vx - Vy
+fn parse_range(text: &str) -> Option<(u16, u16)> {
+    let (x, y) = text.split_once('-')?;
+    Some((parse_register(x.trim())?, parse_register(y.trim())?))
+}
+
+// Make sure a value fits in an operand, bytes can also be negative
+fn check(
+    line: &SourceLine,
+    value: i64,
+    max: u16,
+    what: &str,
+    column: usize,
+) -> Result<u16, AsmError> {
+    let min = if max == 0xFF { -0x80 } else { 0 };
+    if value < min || value > max as i64 {
+        return Err(line.error(column, format!("{} out of range: {}", what, value)));
+    }
+    Ok(value as u16 & max)
+}
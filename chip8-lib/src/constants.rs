@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 // CHIP-8's default fontset
 pub const FONT_SET_SIZE: usize = 80;
 pub const FONT_SET: [u8; FONT_SET_SIZE] = [
@@ -19,16 +21,59 @@ pub const FONT_SET: [u8; FONT_SET_SIZE] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+// SUPER-CHIP's large 8x10 fontset, stored right after the small one
+pub const BIG_FONT_SET_START: u16 = FONT_SET_SIZE as u16;
+pub const BIG_FONT_SET_SIZE: usize = 160;
+pub const BIG_FONT_SET: [u8; BIG_FONT_SET_SIZE] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
 // CHIP-8's constants
 pub const STACK_SIZE: usize = 16;
 pub const ROM_START: u16 = 0x200;
 pub const MEMORY_SIZE: usize = 4096;
+pub const XO_MEMORY_SIZE: usize = 65536; // XO-CHIP's extended address space
 pub const NUM_REGISTERS: usize = 16;
 pub const NUM_KEYS: usize = 16;
-pub const CLOCK_SPEED: u64 = 4;
+pub const TIMER_HZ: u64 = 60; // The timers and the display run at 60 Hz
+pub const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / TIMER_HZ);
+pub const INSTRUCTIONS_PER_FRAME: u32 = 11; // About 660 instructions per second
+pub const MAX_INSTRUCTIONS_PER_FRAME: u32 = 100_000; // 6 MHz, far past any real interpreter
+pub const REWIND_DEPTH: usize = 600; // Snapshots kept for rewinding, 10 seconds at one per frame
+pub const REWIND_GRANULARITY: u32 = 1; // Frames between two rewind snapshots
+pub const CRASH_CONTEXT: u16 = 4; // Instructions shown before and after a crash
+pub const MOVIE_CHECK_INTERVAL: u64 = 30; // Frames between two screen checksums in a movie
+
+pub const SAMPLE_RATE: u32 = 44100;
+pub const BEEP_FREQUENCY: f32 = 440.0;
+pub const BEEP_VOLUME: f32 = 0.25;
+pub const BEEP_RAMP_SECONDS: f32 = 0.005; // Fade in / out time, long enough to avoid clicks
+pub const NUM_RPL_FLAGS: usize = 16; // XO-CHIP's "RPL user flags"
+pub const SCHIP_RPL_FLAGS: usize = 8; // SUPER-CHIP only has the HP-48's first 8
+pub const NUM_PLANES: usize = 4; // XO-CHIP bitplanes
+pub const AUDIO_PATTERN_SIZE: usize = 16; // XO-CHIP 128-bit audio pattern buffer
+pub const DEFAULT_PITCH: u8 = 64; // XO-CHIP pitch register value for 4000 Hz playback
 
 pub const SCREEN_WIDTH: u32 = 64;
 pub const SCREEN_HEIGHT: u32 = 32;
-pub const SCREEN_SCALE: u32 = 16;
+pub const HIRES_SCREEN_WIDTH: u32 = 128;
+pub const HIRES_SCREEN_HEIGHT: u32 = 64;
+pub const DEFAULT_SCREEN_SCALE: u32 = 16; // Window pixels per low-res pixel at startup
+pub const MAX_SCREEN_SCALE: u32 = 64;
 pub const BACK_COLOR: u32 = 0x0E0F12;
 pub const FORE_COLOR: u32 = 0x35D62F;
@@ -3,11 +3,15 @@
 // N or nibble 		- A 4-bit value, the lowest 4 bits of the instruction
 // X or X register 	- A 4-bit value, the lower 4 bits of the high byte of the instruction
 // Y or Y register 	- A 4-bit value, the upper 4 bits of the low byte of the instruction
-use rand::Rng;
+use std::fmt;
+use std::time::Duration;
 
 use crate::constants::*;
 use crate::drivers::rom_driver::ROM;
-use crate::errors::Chip8Error;
+use crate::errors::{Chip8Error, Fault, FaultKind, RomError};
+use crate::framebuffer::FrameBuffer;
+use crate::quirks::Quirks;
+use crate::random::{self, RandomSource, Xorshift};
 
 pub struct OpCode(u16);
 
@@ -52,28 +56,38 @@ impl From<u16> for OpCode {
 pub type Register = usize;
 pub type Address = u16; // original address value is 12 bits, but we have to use 16 bits to store it
 
-// All of the standart instructions in CHIP-8
+// All of the standart instructions in CHIP-8, plus the SUPER-CHIP 1.1 and XO-CHIP extensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
-    ClearDisplay, // 00E0 - CLS
-    Return,       // 00EE - RET
+    ScrollDown(u8), // 00CN - SCD nibble (SUPER-CHIP)
+    ScrollUp(u8),   // 00DN - SCU nibble (XO-CHIP)
+    ClearDisplay,   // 00E0 - CLS
+    Return,         // 00EE - RET
+    ScrollRight,    // 00FB - SCR (SUPER-CHIP)
+    ScrollLeft,     // 00FC - SCL (SUPER-CHIP)
+    Exit,           // 00FD - EXIT (SUPER-CHIP)
+    LowRes,         // 00FE - LOW (SUPER-CHIP)
+    HighRes,        // 00FF - HIGH (SUPER-CHIP)
 
     Jump(Address),                   // 1NNN - JP addr
     Call(Address),                   // 2NNN - CALL addr
     SkipEqual(Register, u8),         // 3XNN - SE Vx, byte
     SkipNotEqual(Register, u8),      // 4XNN - SNE Vx, byte
     SkipEqualXY(Register, Register), // 5XY0 - SE Vx, Vy
+    StoreRange(Register, Register),  // 5XY2 - LD [I], Vx - Vy (XO-CHIP)
+    LoadRange(Register, Register),   // 5XY3 - LD Vx - Vy, [I] (XO-CHIP)
     Load(Register, u8),              // 6XNN - LD Vx, byte
     Add(Register, u8),               // 7XNN - ADD Vx, byte
 
-    Move(Register, Register),  // 8XY0 - LD Vx, Vy
-    Or(Register, Register),    // 8XY1 - OR Vx, Vy
-    And(Register, Register),   // 8XY2 - AND Vx, Vy
-    Xor(Register, Register),   // 8XY3 - XOR Vx, Vy
-    AddXY(Register, Register), // 8XY4 - ADD Vx, Vy
-    SubXY(Register, Register), // 8XY5 - SUB Vx, Vy
-    ShiftRight(Register),      // 8XY6 - SHR Vx {, Vy}
-    SubYX(Register, Register), // 8XY7 - SUBN Vx, Vy
-    ShiftLeft(Register),       // 8XYE - SHL Vx {, Vy}
+    Move(Register, Register),       // 8XY0 - LD Vx, Vy
+    Or(Register, Register),         // 8XY1 - OR Vx, Vy
+    And(Register, Register),        // 8XY2 - AND Vx, Vy
+    Xor(Register, Register),        // 8XY3 - XOR Vx, Vy
+    AddXY(Register, Register),      // 8XY4 - ADD Vx, Vy
+    SubXY(Register, Register),      // 8XY5 - SUB Vx, Vy
+    ShiftRight(Register, Register), // 8XY6 - SHR Vx {, Vy}
+    SubYX(Register, Register),      // 8XY7 - SUBN Vx, Vy
+    ShiftLeft(Register, Register),  // 8XYE - SHL Vx {, Vy}
 
     SkipNotEqualXY(Register, Register), // 9XY0 - SNE Vx, Vy
     LoadI(Address),                     // ANNN - LD I, addr
@@ -84,15 +98,23 @@ pub enum Instruction {
     SkipKeyPressed(Register),    // EX9E - SKP Vx
     SkipKeyNotPressed(Register), // EXA1 - SKNP Vx
 
+    LoadILong(Address), // F000 NNNN - LD I, LONG addr (XO-CHIP, 4 bytes long)
+    SelectPlane(u8),    // FN01 - PLANE n (XO-CHIP)
+    LoadAudio,          // F002 - AUDIO (XO-CHIP)
+
     LoadDelay(Register),      // FX07 - LD Vx, DT
     WaitKeyPress(Register),   // FX0A - LD Vx, K
     SetDelay(Register),       // FX15 - LD DT, Vx
     SetSound(Register),       // FX18 - LD ST, Vx
     AddI(Register),           // FX1E - ADD I, Vx
     LoadFont(Register),       // FX29 - LD F, Vx
+    LoadBigFont(Register),    // FX30 - LD HF, Vx (SUPER-CHIP)
+    SetPitch(Register),       // FX3A - PITCH Vx (XO-CHIP)
     StoreBCD(Register),       // FX33 - LD B, Vx
     StoreRegisters(Register), // FX55 - LD [I], Vx
     LoadMemory(Register),     // FX65 - LD Vx, [I]
+    StoreFlags(Register),     // FX75 - LD R, Vx (SUPER-CHIP)
+    LoadFlags(Register),      // FX85 - LD Vx, R (SUPER-CHIP)
 }
 
 impl Instruction {
@@ -100,9 +122,16 @@ impl Instruction {
     pub fn from<I: Into<OpCode>>(opcode: I) -> Option<Instruction> {
         let opcode: OpCode = opcode.into();
         match opcode.0 & 0xF000 {
-            0x0000 => match opcode.n() {
-                0x0000 => Some(Instruction::ClearDisplay),
-                0x000E => Some(Instruction::Return),
+            0x0000 => match opcode.0 {
+                0x00E0 => Some(Instruction::ClearDisplay),
+                0x00EE => Some(Instruction::Return),
+                0x00FB => Some(Instruction::ScrollRight),
+                0x00FC => Some(Instruction::ScrollLeft),
+                0x00FD => Some(Instruction::Exit),
+                0x00FE => Some(Instruction::LowRes),
+                0x00FF => Some(Instruction::HighRes),
+                op if op & 0xFFF0 == 0x00C0 => Some(Instruction::ScrollDown(opcode.n())),
+                op if op & 0xFFF0 == 0x00D0 => Some(Instruction::ScrollUp(opcode.n())),
                 _ => None,
             },
 
@@ -110,7 +139,12 @@ impl Instruction {
             0x2000 => Some(Instruction::Call(opcode.nnn())),
             0x3000 => Some(Instruction::SkipEqual(opcode.x(), opcode.nn())),
             0x4000 => Some(Instruction::SkipNotEqual(opcode.x(), opcode.nn())),
-            0x5000 => Some(Instruction::SkipEqualXY(opcode.x(), opcode.y())),
+            0x5000 => match opcode.n() {
+                0x0000 => Some(Instruction::SkipEqualXY(opcode.x(), opcode.y())),
+                0x0002 => Some(Instruction::StoreRange(opcode.x(), opcode.y())),
+                0x0003 => Some(Instruction::LoadRange(opcode.x(), opcode.y())),
+                _ => None,
+            },
             0x6000 => Some(Instruction::Load(opcode.x(), opcode.nn())),
             0x7000 => Some(Instruction::Add(opcode.x(), opcode.nn())),
 
@@ -121,13 +155,13 @@ impl Instruction {
                 0x0003 => Some(Instruction::Xor(opcode.x(), opcode.y())),
                 0x0004 => Some(Instruction::AddXY(opcode.x(), opcode.y())),
                 0x0005 => Some(Instruction::SubXY(opcode.x(), opcode.y())),
-                0x0006 => Some(Instruction::ShiftRight(opcode.x())),
+                0x0006 => Some(Instruction::ShiftRight(opcode.x(), opcode.y())),
                 0x0007 => Some(Instruction::SubYX(opcode.x(), opcode.y())),
-                0x000E => Some(Instruction::ShiftLeft(opcode.x())),
+                0x000E => Some(Instruction::ShiftLeft(opcode.x(), opcode.y())),
                 _ => None,
             },
 
-            0x9000 => Some(Instruction::SkipNotEqualXY(opcode.x(), opcode.y())),
+            0x9000 if opcode.n() == 0 => Some(Instruction::SkipNotEqualXY(opcode.x(), opcode.y())),
             0xA000 => Some(Instruction::LoadI(opcode.nnn())),
             0xB000 => Some(Instruction::JumpV0(opcode.nnn())),
             0xC000 => Some(Instruction::Random(opcode.x(), opcode.nn())),
@@ -140,80 +174,205 @@ impl Instruction {
             },
 
             0xF000 => match opcode.nn() {
+                // The address of F000 NNNN is in the next word, see `Emulator::fetch`
+                0x0000 if opcode.x() == 0 => Some(Instruction::LoadILong(0)),
+                0x0001 => Some(Instruction::SelectPlane(opcode.x() as u8)),
+                0x0002 if opcode.x() == 0 => Some(Instruction::LoadAudio),
                 0x0007 => Some(Instruction::LoadDelay(opcode.x())),
                 0x000A => Some(Instruction::WaitKeyPress(opcode.x())),
                 0x0015 => Some(Instruction::SetDelay(opcode.x())),
                 0x0018 => Some(Instruction::SetSound(opcode.x())),
                 0x001E => Some(Instruction::AddI(opcode.x())),
                 0x0029 => Some(Instruction::LoadFont(opcode.x())),
+                0x0030 => Some(Instruction::LoadBigFont(opcode.x())),
+                0x003A => Some(Instruction::SetPitch(opcode.x())),
                 0x0033 => Some(Instruction::StoreBCD(opcode.x())),
                 0x0055 => Some(Instruction::StoreRegisters(opcode.x())),
                 0x0065 => Some(Instruction::LoadMemory(opcode.x())),
+                0x0075 => Some(Instruction::StoreFlags(opcode.x())),
+                0x0085 => Some(Instruction::LoadFlags(opcode.x())),
                 _ => None,
             },
             _ => None,
         }
     }
 
+    // Whether the instruction only exists in XO-CHIP, and is invalid in the other modes
+    pub fn is_xo_chip(&self) -> bool {
+        matches!(
+            *self,
+            Instruction::ScrollUp(_)
+                | Instruction::StoreRange(_, _)
+                | Instruction::LoadRange(_, _)
+                | Instruction::LoadILong(_)
+                | Instruction::SelectPlane(_)
+                | Instruction::LoadAudio
+                | Instruction::SetPitch(_)
+        )
+    }
+
     pub fn has_register(&self) -> bool {
+        matches!(
+            *self,
+            Instruction::SkipEqual(_, _)
+                | Instruction::SkipNotEqual(_, _)
+                | Instruction::StoreRange(_, _)
+                | Instruction::LoadRange(_, _)
+                | Instruction::Load(_, _)
+                | Instruction::Add(_, _)
+                | Instruction::Move(_, _)
+                | Instruction::Or(_, _)
+                | Instruction::And(_, _)
+                | Instruction::Xor(_, _)
+                | Instruction::AddXY(_, _)
+                | Instruction::SubXY(_, _)
+                | Instruction::ShiftRight(_, _)
+                | Instruction::SubYX(_, _)
+                | Instruction::ShiftLeft(_, _)
+                | Instruction::SkipNotEqualXY(_, _)
+                | Instruction::Random(_, _)
+                | Instruction::Draw(_, _, _)
+                | Instruction::SkipKeyPressed(_)
+                | Instruction::SkipKeyNotPressed(_)
+                | Instruction::LoadDelay(_)
+                | Instruction::WaitKeyPress(_)
+                | Instruction::SetDelay(_)
+                | Instruction::SetSound(_)
+                | Instruction::AddI(_)
+                | Instruction::LoadFont(_)
+                | Instruction::LoadBigFont(_)
+                | Instruction::SetPitch(_)
+                | Instruction::StoreBCD(_)
+                | Instruction::StoreRegisters(_)
+                | Instruction::LoadMemory(_)
+                | Instruction::StoreFlags(_)
+                | Instruction::LoadFlags(_)
+        )
+    }
+}
+
+// Cowgod style mnemonic, as in the comments of `Instruction`
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Instruction::SkipEqual(_, _) => true,
-            Instruction::SkipNotEqual(_, _) => true,
-            Instruction::Load(_, _) => true,
-            Instruction::Add(_, _) => true,
-            Instruction::Move(_, _) => true,
-            Instruction::Or(_, _) => true,
-            Instruction::And(_, _) => true,
-            Instruction::Xor(_, _) => true,
-            Instruction::AddXY(_, _) => true,
-            Instruction::SubXY(_, _) => true,
-            Instruction::ShiftRight(_) => true,
-            Instruction::SubYX(_, _) => true,
-            Instruction::ShiftLeft(_) => true,
-            Instruction::SkipNotEqualXY(_, _) => true,
-            Instruction::Random(_, _) => true,
-            Instruction::Draw(_, _, _) => true,
-            Instruction::SkipKeyPressed(_) => true,
-            Instruction::SkipKeyNotPressed(_) => true,
-            Instruction::LoadDelay(_) => true,
-            Instruction::WaitKeyPress(_) => true,
-            Instruction::SetDelay(_) => true,
-            Instruction::SetSound(_) => true,
-            Instruction::AddI(_) => true,
-            Instruction::LoadFont(_) => true,
-            Instruction::StoreBCD(_) => true,
-            Instruction::StoreRegisters(_) => true,
-            Instruction::LoadMemory(_) => true,
-            _ => false,
+            Instruction::ScrollDown(n) => write!(f, "SCD {}", n),
+            Instruction::ScrollUp(n) => write!(f, "SCU {}", n),
+            Instruction::ClearDisplay => write!(f, "CLS"),
+            Instruction::Return => write!(f, "RET"),
+            Instruction::ScrollRight => write!(f, "SCR"),
+            Instruction::ScrollLeft => write!(f, "SCL"),
+            Instruction::Exit => write!(f, "EXIT"),
+            Instruction::LowRes => write!(f, "LOW"),
+            Instruction::HighRes => write!(f, "HIGH"),
+
+            Instruction::Jump(addr) => write!(f, "JP {:#05X}", addr),
+            Instruction::Call(addr) => write!(f, "CALL {:#05X}", addr),
+            Instruction::SkipEqual(x, byte) => write!(f, "SE V{:X}, {:#04X}", x, byte),
+            Instruction::SkipNotEqual(x, byte) => write!(f, "SNE V{:X}, {:#04X}", x, byte),
+            Instruction::SkipEqualXY(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::StoreRange(x, y) => write!(f, "LD [I], V{:X} - V{:X}", x, y),
+            Instruction::LoadRange(x, y) => write!(f, "LD V{:X} - V{:X}, [I]", x, y),
+            Instruction::Load(x, byte) => write!(f, "LD V{:X}, {:#04X}", x, byte),
+            Instruction::Add(x, byte) => write!(f, "ADD V{:X}, {:#04X}", x, byte),
+
+            Instruction::Move(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddXY(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::SubXY(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::SubYX(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+
+            Instruction::SkipNotEqualXY(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LoadI(addr) => write!(f, "LD I, {:#05X}", addr),
+            Instruction::JumpV0(addr) => write!(f, "JP V0, {:#05X}", addr),
+            Instruction::Random(x, byte) => write!(f, "RND V{:X}, {:#04X}", x, byte),
+            Instruction::Draw(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+
+            Instruction::SkipKeyPressed(x) => write!(f, "SKP V{:X}", x),
+            Instruction::SkipKeyNotPressed(x) => write!(f, "SKNP V{:X}", x),
+
+            Instruction::LoadILong(addr) => write!(f, "LD I, LONG {:#06X}", addr),
+            Instruction::SelectPlane(n) => write!(f, "PLANE {}", n),
+            Instruction::LoadAudio => write!(f, "AUDIO"),
+
+            Instruction::LoadDelay(x) => write!(f, "LD V{:X}, DT", x),
+            Instruction::WaitKeyPress(x) => write!(f, "LD V{:X}, K", x),
+            Instruction::SetDelay(x) => write!(f, "LD DT, V{:X}", x),
+            Instruction::SetSound(x) => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddI(x) => write!(f, "ADD I, V{:X}", x),
+            Instruction::LoadFont(x) => write!(f, "LD F, V{:X}", x),
+            Instruction::LoadBigFont(x) => write!(f, "LD HF, V{:X}", x),
+            Instruction::SetPitch(x) => write!(f, "PITCH V{:X}", x),
+            Instruction::StoreBCD(x) => write!(f, "LD B, V{:X}", x),
+            Instruction::StoreRegisters(x) => write!(f, "LD [I], V{:X}", x),
+            Instruction::LoadMemory(x) => write!(f, "LD V{:X}, [I]", x),
+            Instruction::StoreFlags(x) => write!(f, "LD R, V{:X}", x),
+            Instruction::LoadFlags(x) => write!(f, "LD V{:X}, R", x),
         }
     }
 }
 
+// A data access made by an instruction, recorded for the debugger's watchpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAccess {
+    Read(usize, usize),  // Start address and length
+    Write(usize, usize), // Start address and length
+}
+
+// What happens when a ROM reaches past the end of memory or uses a key above 0xF
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutOfBounds {
+    #[default]
+    Error, // Stop with `MemoryOutOfBounds` or `InvalidKey`
+    Wrap, // Wrap addresses around memory and keys around the keypad
+}
+
 #[allow(dead_code)]
 pub struct Emulator {
     /* Memory Layout:
         |- 0x000 - 0x1FF: Chip 8 interpreter (contains font set in emulator)
-        |- 0x050 - 0x0A0: Used for the built in 4x5 pixel font set (0-F)
-        |- 0x200 - 0xFFF: Program ROM and work RAM
+        |- 0x000 - 0x050: Used for the built in 4x5 pixel font set (0-F)
+        |- 0x050 - 0x0F0: Used for the built in SUPER-CHIP 8x10 pixel font set (0-F)
+        |- 0x200 - 0xFFF: Program ROM and work RAM (0xFFFF on XO-CHIP)
     */
-    pub memory: [u8; MEMORY_SIZE], // 4K memory; 0x000 - 0xFFF
-    pub v: [u8; NUM_REGISTERS],    // 16 8-bit registers; 0x0 - 0xF
-    pub i: u16,                    // Memory address register
-    pub pc: u16,                   // Program counter
-    pub stack: [u16; STACK_SIZE],  // Stack; 16 levels of 16-bit values
-    pub sp: u8,                    // Stack pointer; points to the top of the stack
-    pub dt: u8,                    // Delay timer
-    pub st: u8,                    // Sound timer
-    pub draw_flag: bool,           // Draw flag
-    pub screen: [bool; (SCREEN_WIDTH * SCREEN_HEIGHT) as usize], // Screen
-    pub keypad: [bool; NUM_KEYS],  // Keys
+    pub memory: Vec<u8>,          // 4K memory; 0x000 - 0xFFF, 64K on XO-CHIP
+    pub v: [u8; NUM_REGISTERS],   // 16 8-bit registers; 0x0 - 0xF
+    pub i: u16,                   // Memory address register
+    pub pc: u16,                  // Program counter
+    pub stack: [u16; STACK_SIZE], // Stack; 16 levels of 16-bit values
+    pub sp: u8,                   // Stack pointer; points to the top of the stack
+    pub dt: u8,                   // Delay timer
+    pub st: u8,                   // Sound timer
+    pub draw_flag: bool,          // Draw flag
+    pub screen: FrameBuffer,      // Screen; 64x32, or 128x64 in SUPER-CHIP hi-res mode
+    pub keypad: [bool; NUM_KEYS], // Keys
+    pub rpl: [u8; NUM_RPL_FLAGS], // SUPER-CHIP RPL user flags
+    pub halted: bool,             // Set by the SUPER-CHIP exit instruction
+    pub planes: u8,               // XO-CHIP planes selected for drawing, one bit per plane
+    pub audio_pattern: [u8; AUDIO_PATTERN_SIZE], // XO-CHIP 1-bit audio samples
+    pub pitch: u8,                // XO-CHIP audio pattern playback rate
+    pub quirks: Quirks,           // Interpreter quirks the ROM expects
+    pub instructions_per_frame: u32, // Instructions run between two 60 Hz timer ticks
+    pub out_of_bounds: OutOfBounds, // What invalid memory and key accesses do
+    pub rom_hash: u64,            // Hash of the loaded ROM, ties save states to their game
+    pub cycles: u64,              // Instructions run since the emulator was created
+    pub frames: u64,              // 60 Hz timer ticks since the emulator was created
+    pub rng: Box<dyn RandomSource>, // Generator for CXNN, seeded randomly unless replaced
+    pub(crate) frame_time: Duration, // Time passed to `run_for` that hasn't made up a frame yet
+    pub(crate) instruction_pc: u16, // Address of the instruction being executed
+    pub(crate) vblank: bool,      // Set by the 60 Hz timer, consumed by DXYN with display_wait
+    pub(crate) trace_accesses: bool, // Record the memory accesses of each instruction
+    pub(crate) accesses: Vec<MemoryAccess>, // Accesses made by the last instruction
 }
 
 impl Emulator {
     // MISC operations
-    pub fn new() -> Self {
+    pub fn new(quirks: Quirks) -> Self {
         let mut emulator = Self {
-            memory: [0; MEMORY_SIZE],
+            memory: vec![0; Self::memory_size(&quirks)],
             v: [0; 16],
             i: 0,
             pc: ROM_START,
@@ -222,23 +381,59 @@ impl Emulator {
             dt: 0,
             st: 0,
             draw_flag: false,
-            screen: [false; (SCREEN_WIDTH * SCREEN_HEIGHT) as usize],
+            screen: FrameBuffer::default(),
             keypad: [false; NUM_KEYS],
+            rpl: [0; NUM_RPL_FLAGS],
+            halted: false,
+            planes: 1,
+            audio_pattern: [0; AUDIO_PATTERN_SIZE],
+            pitch: DEFAULT_PITCH,
+            quirks,
+            instructions_per_frame: INSTRUCTIONS_PER_FRAME,
+            out_of_bounds: OutOfBounds::Error,
+            rom_hash: 0,
+            cycles: 0,
+            frames: 0,
+            rng: Box::new(Xorshift::new(random::entropy_seed())),
+            frame_time: Duration::ZERO,
+            instruction_pc: ROM_START,
+            vblank: true,
+            trace_accesses: false,
+            accesses: Vec::new(),
         };
 
         // Load the font set into memory
         emulator.memory[..FONT_SET_SIZE].copy_from_slice(&FONT_SET);
+        let big_font_start = BIG_FONT_SET_START as usize;
+        emulator.memory[big_font_start..big_font_start + BIG_FONT_SET_SIZE]
+            .copy_from_slice(&BIG_FONT_SET);
 
         emulator
     }
 
-    pub fn load_rom(&mut self, rom: ROM) {
-        // Load the ROM into memory
-        for (i, byte) in rom.data.iter().enumerate() {
-            self.memory[0x200 + i] = *byte;
+    // The size of the address space for a quirks profile
+    pub(crate) fn memory_size(quirks: &Quirks) -> usize {
+        if quirks.xo_chip {
+            XO_MEMORY_SIZE
+        } else {
+            MEMORY_SIZE
         }
     }
 
+    // The largest ROM that fits in memory after 0x200
+    pub fn max_rom_size(&self) -> usize {
+        self.memory.len() - ROM_START as usize
+    }
+
+    // Load a ROM into memory, memory is left untouched if it doesn't fit
+    pub fn load_rom(&mut self, rom: ROM) -> Result<(), RomError> {
+        rom.validate(self.max_rom_size())?;
+        self.rom_hash = rom.hash();
+        let start = ROM_START as usize;
+        self.memory[start..start + rom.data.len()].copy_from_slice(&rom.data);
+        Ok(())
+    }
+
     fn push(&mut self, val: u16) {
         self.stack[self.sp as usize] = val;
         self.sp += 1;
@@ -250,71 +445,244 @@ impl Emulator {
     }
 
     fn clear_screen(&mut self) {
-        self.screen = [false; (SCREEN_WIDTH * SCREEN_HEIGHT) as usize];
+        self.screen.clear(self.planes);
+    }
+
+    // Record a memory access if the debugger is watching memory
+    fn trace(&mut self, access: MemoryAccess) {
+        if self.trace_accesses {
+            self.accesses.push(access);
+        }
+    }
+
+    // Read a 16 bit big endian word from memory, wrapping around at the end.
+    // For looking at memory from outside, instructions go through the checked helpers below.
+    pub fn read_word(&self, addr: u16) -> u16 {
+        let len = self.memory.len();
+        let hb = self.memory[addr as usize % len] as u16; // High byte (left side byte)
+        let lb = self.memory[(addr as usize + 1) % len] as u16; // Low byte (right side byte)
+        (hb << 8) | lb
+    }
+
+    // Check an address an instruction uses, wrapping it around if configured
+    fn address(&self, addr: usize) -> Result<usize, Chip8Error> {
+        if addr < self.memory.len() {
+            return Ok(addr);
+        }
+        match self.out_of_bounds {
+            OutOfBounds::Error => Err(self.fault(FaultKind::MemoryOutOfBounds(addr))),
+            OutOfBounds::Wrap => Ok(addr % self.memory.len()),
+        }
+    }
+
+    fn read(&self, addr: usize) -> Result<u8, Chip8Error> {
+        Ok(self.memory[self.address(addr)?])
+    }
+
+    fn write(&mut self, addr: usize, val: u8) -> Result<(), Chip8Error> {
+        let addr = self.address(addr)?;
+        self.memory[addr] = val;
+        Ok(())
+    }
+
+    // The XO-CHIP audio pattern the buzzer plays, None for the plain beep
+    // The pattern is all zero until F002 loads one, games that never do still beep.
+    pub fn buzzer_pattern(&self) -> Option<&[u8; AUDIO_PATTERN_SIZE]> {
+        let loaded = self.audio_pattern.iter().any(|&byte| byte != 0);
+        (self.quirks.xo_chip && loaded).then_some(&self.audio_pattern)
+    }
+
+    // Read the big endian word at PC and move past it
+    fn fetch_word(&mut self) -> Result<u16, Chip8Error> {
+        let hb = self.read(self.pc as usize)? as u16;
+        let lb = self.read(self.pc as usize + 1)? as u16;
+        let next = self.pc as usize + 2;
+        self.pc = match self.out_of_bounds {
+            // PC can't point past the end of 64K memory, so that fails right away
+            OutOfBounds::Error if next > u16::MAX as usize => {
+                return Err(self.fault(FaultKind::MemoryOutOfBounds(next)))
+            }
+            OutOfBounds::Error => next as u16, // Running off the end fails on the next fetch
+            OutOfBounds::Wrap => (next % self.memory.len()) as u16,
+        };
+        Ok((hb << 8) | lb)
+    }
+
+    // Skip the next instruction, which is 4 bytes long if it is XO-CHIP's F000 NNNN
+    fn skip(&mut self) {
+        if self.quirks.xo_chip && self.read_word(self.pc) == 0xF000 {
+            self.pc = self.pc.wrapping_add(4);
+        } else {
+            self.pc = self.pc.wrapping_add(2);
+        }
     }
 
     // KEYBOARD operations
+    // Check a key an instruction uses, wrapping it around if configured
+    fn key(&self, key: u8) -> Result<bool, Chip8Error> {
+        match (key as usize, self.out_of_bounds) {
+            (key, _) if key < NUM_KEYS => Ok(self.keypad[key]),
+            (key, OutOfBounds::Wrap) => Ok(self.keypad[key % NUM_KEYS]),
+            (_, OutOfBounds::Error) => Err(self.fault(FaultKind::InvalidKey(key))),
+        }
+    }
+
+    // The keypad entry of a key the frontend reports
+    // Keys past 0xF are ignored, or wrap around like the ones instructions use.
+    fn keypad_mut(&mut self, key: u8) -> Option<&mut bool> {
+        match (key as usize, self.out_of_bounds) {
+            (key, _) if key < NUM_KEYS => Some(&mut self.keypad[key]),
+            (key, OutOfBounds::Wrap) => Some(&mut self.keypad[key % NUM_KEYS]),
+            (_, OutOfBounds::Error) => None,
+        }
+    }
+
     pub fn key_down(&mut self, key: u8) {
-        self.keypad[key as usize] = true;
+        if let Some(pressed) = self.keypad_mut(key) {
+            *pressed = true;
+        }
     }
 
     pub fn key_up(&mut self, key: u8) {
-        self.keypad[key as usize] = false;
+        if let Some(pressed) = self.keypad_mut(key) {
+            *pressed = false;
+        }
     }
 
-    // Fetch the next instruction
-    pub fn fetch(&mut self) -> Option<Instruction> {
+    // Fetch the next instruction, None if the opcode isn't an instruction
+    pub fn fetch(&mut self) -> Result<Option<Instruction>, Chip8Error> {
+        self.instruction_pc = self.pc;
         // Read the 2 byte long opcode from memory
-        let hb = self.memory[self.pc as usize] as u16; // High byte (left side byte)
-        let lb = self.memory[(self.pc + 1) as usize] as u16; // Low byte (right side byte)
-        let combine = (hb << 8) | lb; // Combine the 2 bytes into a 16 bit opcode
-        let op = OpCode(combine); // Create an opcode from the 16 bit value
-        self.pc += 2; // Next instruction
+        let op = OpCode(self.fetch_word()?);
 
         // Turn the opcode into an instruction
-        let instruction = Instruction::from(op);
-        instruction
+        Ok(match self.decode(op) {
+            // XO-CHIP's long load takes its address from the following word
+            Some(Instruction::LoadILong(_)) => Some(Instruction::LoadILong(self.fetch_word()?)),
+            instruction => instruction,
+        })
+    }
+
+    // Decode the instruction at PC without running it
+    pub fn peek(&self) -> Option<Instruction> {
+        match self.decode(OpCode(self.read_word(self.pc))) {
+            Some(Instruction::LoadILong(_)) => Some(Instruction::LoadILong(
+                self.read_word(self.pc.wrapping_add(2)),
+            )),
+            instruction => instruction,
+        }
+    }
+
+    // Turn an opcode into an instruction, XO-CHIP's ones only in XO-CHIP mode
+    fn decode(&self, opcode: OpCode) -> Option<Instruction> {
+        Instruction::from(opcode)
+            .filter(|instruction| self.quirks.xo_chip || !instruction.is_xo_chip())
     }
 
     // One cycle of CHIP-8
     pub fn tick(&mut self) -> Result<(), Chip8Error> {
-        // Fetch the next instruction
-        let instruction = self.fetch();
+        // Nothing left to run after the exit instruction
+        if self.halted {
+            return Ok(());
+        }
+        self.accesses.clear();
 
-        match instruction {
-            Some(instruction) => {
-                // Execute the instruction
-                let result = self.execute(instruction);
+        // Fetch and execute the next instruction
+        let result = match self.fetch() {
+            Ok(Some(instruction)) => self.execute(instruction),
+            Ok(None) => Err(self.fault(FaultKind::InvalidInstruction)),
+            Err(e) => Err(e),
+        };
 
-                if let Err(e) = result {
-                    return Err(e);
-                }
-            }
-            None => {
-                return Err(Chip8Error::InvalidInstruction(self.pc));
-            }
+        // Leave PC on a failed instruction, so the debugger shows it
+        if result.is_err() {
+            self.pc = self.instruction_pc;
+        } else {
+            self.cycles += 1;
         }
-        Ok(())
+        result
+    }
+
+    // An error for the instruction being executed
+    fn fault(&self, kind: FaultKind) -> Chip8Error {
+        let opcode = self.read_word(self.instruction_pc);
+        let instruction = match self.decode(OpCode(opcode)) {
+            Some(Instruction::LoadILong(_)) => Some(Instruction::LoadILong(
+                self.read_word(self.instruction_pc.wrapping_add(2)),
+            )),
+            instruction => instruction,
+        };
+        Chip8Error::Fault(Fault {
+            kind,
+            pc: self.instruction_pc,
+            opcode,
+            instruction,
+            cycle: self.cycles,
+        })
     }
 
     pub fn execute(&mut self, instruction: Instruction) -> Result<(), Chip8Error> {
         match instruction {
+            // Scroll the display down by nibble rows
+            Instruction::ScrollDown(nibble) => {
+                self.screen.scroll(0, nibble as isize, self.planes);
+                self.draw_flag = true;
+                Ok(())
+            }
+            // Scroll the display up by nibble rows
+            Instruction::ScrollUp(nibble) => {
+                self.screen.scroll(0, -(nibble as isize), self.planes);
+                self.draw_flag = true;
+                Ok(())
+            }
             // Clear the display
             Instruction::ClearDisplay => {
                 self.clear_screen();
+                self.draw_flag = true;
                 Ok(())
             }
             // Return from a subroutine
             Instruction::Return => {
                 // Check if the stack pointer is 0
                 if self.sp == 0 {
-                    return Err(Chip8Error::StackUnderflow);
+                    return Err(self.fault(FaultKind::StackUnderflow));
                 }
 
                 let return_addr = self.pop();
                 self.pc = return_addr;
                 Ok(())
             }
+            // Scroll the display right by 4 pixels
+            Instruction::ScrollRight => {
+                self.screen.scroll(4, 0, self.planes);
+                self.draw_flag = true;
+                Ok(())
+            }
+            // Scroll the display left by 4 pixels
+            Instruction::ScrollLeft => {
+                self.screen.scroll(-4, 0, self.planes);
+                self.draw_flag = true;
+                Ok(())
+            }
+            // Exit the interpreter
+            Instruction::Exit => {
+                self.halted = true;
+                Ok(())
+            }
+            // Switch to 64x32 low resolution mode
+            Instruction::LowRes => {
+                self.screen
+                    .resize(SCREEN_WIDTH as usize, SCREEN_HEIGHT as usize);
+                self.draw_flag = true;
+                Ok(())
+            }
+            // Switch to 128x64 high resolution mode
+            Instruction::HighRes => {
+                self.screen
+                    .resize(HIRES_SCREEN_WIDTH as usize, HIRES_SCREEN_HEIGHT as usize);
+                self.draw_flag = true;
+                Ok(())
+            }
             // Jump to address
             Instruction::Jump(addr) => {
                 self.pc = addr;
@@ -324,7 +692,7 @@ impl Emulator {
             Instruction::Call(addr) => {
                 // Check if the stack pointer is at the max
                 if self.sp == STACK_SIZE as u8 {
-                    return Err(Chip8Error::StackOverflow);
+                    return Err(self.fault(FaultKind::StackOverflow));
                 }
 
                 self.push(self.pc);
@@ -334,21 +702,37 @@ impl Emulator {
             // Skip next instruction if Vx == byte
             Instruction::SkipEqual(x, byte) => {
                 if self.v[x] == byte {
-                    self.pc += 2;
+                    self.skip();
                 }
                 Ok(())
             }
             // Skip next instruction if Vx != byte
             Instruction::SkipNotEqual(x, byte) => {
                 if self.v[x] != byte {
-                    self.pc += 2;
+                    self.skip();
                 }
                 Ok(())
             }
             // Skip next instruction if Vx == Vy
             Instruction::SkipEqualXY(x, y) => {
                 if self.v[x] == self.v[y] {
-                    self.pc += 2;
+                    self.skip();
+                }
+                Ok(())
+            }
+            // Store registers Vx through Vy in memory starting at location I
+            Instruction::StoreRange(x, y) => {
+                self.trace(MemoryAccess::Write(self.i as usize, x.abs_diff(y) + 1));
+                for (offset, idx) in Self::register_range(x, y).into_iter().enumerate() {
+                    self.write(self.i as usize + offset, self.v[idx])?;
+                }
+                Ok(())
+            }
+            // Read registers Vx through Vy from memory starting at location I
+            Instruction::LoadRange(x, y) => {
+                self.trace(MemoryAccess::Read(self.i as usize, x.abs_diff(y) + 1));
+                for (offset, idx) in Self::register_range(x, y).into_iter().enumerate() {
+                    self.v[idx] = self.read(self.i as usize + offset)?;
                 }
                 Ok(())
             }
@@ -370,16 +754,25 @@ impl Emulator {
             // Set Vx = Vx OR Vy
             Instruction::Or(x, y) => {
                 self.v[x] |= self.v[y];
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
                 Ok(())
             }
             // Set Vx = Vx AND Vy
             Instruction::And(x, y) => {
                 self.v[x] &= self.v[y];
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
                 Ok(())
             }
             // Set Vx = Vx XOR Vy
             Instruction::Xor(x, y) => {
                 self.v[x] ^= self.v[y];
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
                 Ok(())
             }
             // Set Vx = Vx + Vy, set VF = carry
@@ -396,10 +789,15 @@ impl Emulator {
                 self.v[0xF] = !borrow as u8;
                 Ok(())
             }
-            // Set Vx = Vx SHR 1
-            Instruction::ShiftRight(x) => {
-                self.v[0xF] = self.v[x] & 0x1;
-                self.v[x] >>= 1;
+            // Set Vx = Vx SHR 1 (or Vy SHR 1)
+            Instruction::ShiftRight(x, y) => {
+                let val = if self.quirks.shift_uses_vy {
+                    self.v[y]
+                } else {
+                    self.v[x]
+                };
+                self.v[x] = val >> 1;
+                self.v[0xF] = val & 0x1;
                 Ok(())
             }
             // Set Vx = Vy - Vx, set VF = NOT borrow
@@ -409,16 +807,21 @@ impl Emulator {
                 self.v[0xF] = !borrow as u8;
                 Ok(())
             }
-            // Set Vx = Vx SHL 1
-            Instruction::ShiftLeft(x) => {
-                self.v[0xF] = (self.v[x] >> 7) & 0x1;
-                self.v[x] <<= 1;
+            // Set Vx = Vx SHL 1 (or Vy SHL 1)
+            Instruction::ShiftLeft(x, y) => {
+                let val = if self.quirks.shift_uses_vy {
+                    self.v[y]
+                } else {
+                    self.v[x]
+                };
+                self.v[x] = val << 1;
+                self.v[0xF] = (val >> 7) & 0x1;
                 Ok(())
             }
             // Skip next instruction if Vx != Vy
             Instruction::SkipNotEqualXY(x, y) => {
                 if self.v[x] != self.v[y] {
-                    self.pc += 2;
+                    self.skip();
                 }
                 Ok(())
             }
@@ -427,37 +830,76 @@ impl Emulator {
                 self.i = addr;
                 Ok(())
             }
-            // Jump to location addr + V0
+            // Jump to location addr + V0 (or XNN + Vx)
             Instruction::JumpV0(addr) => {
-                self.pc = addr + (self.v[0] as u16);
+                let x = if self.quirks.jump_uses_vx {
+                    ((addr & 0x0F00) >> 8) as usize
+                } else {
+                    0
+                };
+                let target = self.address(addr as usize + self.v[x] as usize)?;
+                self.pc = target as u16;
                 Ok(())
             }
             // Set Vx = random byte AND byte
             Instruction::Random(x, byte) => {
-                let mut rng = rand::thread_rng();
-                self.v[x] = rng.gen::<u8>() & byte;
+                self.v[x] = self.rng.next_byte() & byte;
                 Ok(())
             }
             // Display n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision
+            // A nibble of 0 draws a 16x16 sprite of 2-byte rows
             Instruction::Draw(x, y, nibble) => {
-                let x_coord = self.v[x] as usize;
-                let y_coord = self.v[y] as usize;
+                // Wait for the vertical blank, one sprite per frame
+                if self.quirks.display_wait && !self.vblank {
+                    self.pc = self.pc.wrapping_sub(2);
+                    return Ok(());
+                }
+
+                let (width, height) = (self.screen.width, self.screen.height);
+                let (sprite_width, sprite_height) = match nibble {
+                    0 => (16, 16),
+                    n => (8, n as usize),
+                };
+                let bytes_per_row = sprite_width / 8;
+                let sprite_size = bytes_per_row * sprite_height;
+
+                // The starting position always wraps around the screen
+                let x_coord = self.v[x] as usize % width;
+                let y_coord = self.v[y] as usize % height;
                 let mut collision = false;
 
-                for row in 0..nibble {
-                    let addr = (self.i + row as u16) as usize;
-                    let pixels = self.memory[addr];
-
-                    for col in 0..8 {
-                        if (pixels & (0x80 >> col)) != 0 {
-                            // Sprites should wrap around screen, so apply modulo
-                            let x = (x_coord + col) % SCREEN_WIDTH as usize;
-                            let y = (y_coord + row as usize) % SCREEN_HEIGHT as usize;
-                            // Get our pixel's index for our 1D screen array
-                            let idx = x + (SCREEN_WIDTH as usize) * y;
-                            // Check if we're about to flip the pixel and set
-                            collision |= self.screen[idx];
-                            self.screen[idx] ^= true;
+                // Each selected plane reads its own copy of the sprite, one after another
+                let planes = self.planes;
+                let selected = (0..NUM_PLANES as u8)
+                    .map(|plane| 1 << plane)
+                    .filter(|plane| planes & plane != 0);
+                let sprite_data = selected.clone().count() * sprite_size;
+                self.trace(MemoryAccess::Read(self.i as usize, sprite_data));
+                // Read all of it first, so reading past memory fails before anything is drawn
+                let data = (0..sprite_data)
+                    .map(|n| self.read(self.i as usize + n))
+                    .collect::<Result<Vec<u8>, _>>()?;
+                self.vblank = false;
+
+                for (sprite, plane) in data.chunks(sprite_size).zip(selected) {
+                    for (row, bytes) in sprite.chunks(bytes_per_row).enumerate() {
+                        let pixels = bytes
+                            .iter()
+                            .fold(0u16, |pixels, &byte| (pixels << 8) | byte as u16);
+
+                        for col in 0..sprite_width {
+                            if (pixels & (1 << (sprite_width - 1 - col))) != 0 {
+                                // Pixels past the edge are either clipped or wrapped around
+                                if self.quirks.clip_sprites
+                                    && (x_coord + col >= width || y_coord + row >= height)
+                                {
+                                    continue;
+                                }
+                                let x = (x_coord + col) % width;
+                                let y = (y_coord + row) % height;
+                                // Check if we're about to flip the pixel and set
+                                collision |= self.screen.toggle(x, y, plane);
+                            }
                         }
                     }
                 }
@@ -469,17 +911,34 @@ impl Emulator {
             }
             // Skip next instruction if key with the value of Vx is pressed
             Instruction::SkipKeyPressed(x) => {
-                let key = self.v[x];
-                if self.keypad[key as usize] {
-                    self.pc += 2;
+                if self.key(self.v[x])? {
+                    self.skip();
                 }
                 Ok(())
             }
             // Skip next instruction if key with the value of Vx is not pressed
             Instruction::SkipKeyNotPressed(x) => {
-                let key = self.v[x];
-                if !self.keypad[key as usize] {
-                    self.pc += 2;
+                if !self.key(self.v[x])? {
+                    self.skip();
+                }
+                Ok(())
+            }
+            // Set I = 16 bit addr
+            Instruction::LoadILong(addr) => {
+                self.i = addr;
+                Ok(())
+            }
+            // Select the planes used for drawing, clearing and scrolling
+            Instruction::SelectPlane(n) => {
+                self.planes = n & ((1 << NUM_PLANES) - 1);
+                Ok(())
+            }
+            // Load the 16 byte audio pattern starting at location I
+            Instruction::LoadAudio => {
+                let i = self.i as usize;
+                self.trace(MemoryAccess::Read(i, AUDIO_PATTERN_SIZE));
+                for offset in 0..AUDIO_PATTERN_SIZE {
+                    self.audio_pattern[offset] = self.read(i + offset)?;
                 }
                 Ok(())
             }
@@ -500,7 +959,7 @@ impl Emulator {
                 }
 
                 if !pressed {
-                    self.pc -= 2;
+                    self.pc = self.pc.wrapping_sub(2);
                 }
                 Ok(())
             }
@@ -524,6 +983,16 @@ impl Emulator {
                 self.i = (self.v[x] as u16) * 5;
                 Ok(())
             }
+            // Set I = large font sprite for digit Vx
+            Instruction::LoadBigFont(x) => {
+                self.i = BIG_FONT_SET_START + ((self.v[x] & 0xF) as u16) * 10;
+                Ok(())
+            }
+            // Set the audio pattern playback pitch = Vx
+            Instruction::SetPitch(x) => {
+                self.pitch = self.v[x];
+                Ok(())
+            }
             // Store BCD representation of Vx in memory locations I, I+1, and I+2
             Instruction::StoreBCD(x) => {
                 let val = self.v[x] as f32;
@@ -534,46 +1003,126 @@ impl Emulator {
                 // Get the ones digit by taking the remainder of the division by 10
                 let ones = (val % 10.0).floor() as u8;
 
-                self.memory[self.i as usize] = hundreds;
-                self.memory[(self.i + 1) as usize] = tens;
-                self.memory[(self.i + 2) as usize] = ones;
+                self.trace(MemoryAccess::Write(self.i as usize, 3));
+                let i = self.i as usize;
+                self.write(i, hundreds)?;
+                self.write(i + 1, tens)?;
+                self.write(i + 2, ones)?;
                 Ok(())
             }
             // Store registers V0 through Vx in memory starting at location I
             Instruction::StoreRegisters(x) => {
+                self.trace(MemoryAccess::Write(self.i as usize, x + 1));
                 for idx in 0..=x {
-                    self.memory[(self.i as usize) + idx] = self.v[idx];
+                    self.write(self.i as usize + idx, self.v[idx])?;
                 }
+                self.increment_i_after_memory(x);
                 Ok(())
             }
             // Read registers V0 through Vx from memory starting at location I
             Instruction::LoadMemory(x) => {
+                self.trace(MemoryAccess::Read(self.i as usize, x + 1));
                 for idx in 0..=x {
-                    self.v[idx] = self.memory[(self.i as usize) + idx];
+                    self.v[idx] = self.read(self.i as usize + idx)?;
                 }
+                self.increment_i_after_memory(x);
                 Ok(())
             }
+            // Store registers V0 through Vx in the RPL user flags
+            Instruction::StoreFlags(x) => {
+                if x >= self.rpl_flags() {
+                    return Err(self.fault(FaultKind::InvalidRegister(x)));
+                }
+                self.rpl[..=x].copy_from_slice(&self.v[..=x]);
+                Ok(())
+            }
+            // Read registers V0 through Vx from the RPL user flags
+            Instruction::LoadFlags(x) => {
+                if x >= self.rpl_flags() {
+                    return Err(self.fault(FaultKind::InvalidRegister(x)));
+                }
+                self.v[..=x].copy_from_slice(&self.rpl[..=x]);
+                Ok(())
+            }
+        }
+    }
+
+    // RPL user flags FX75 / FX85 can use
+    fn rpl_flags(&self) -> usize {
+        if self.quirks.xo_chip {
+            NUM_RPL_FLAGS
+        } else {
+            SCHIP_RPL_FLAGS
         }
     }
 
+    // Move I after FX55 / FX65 the way the quirks say
+    fn increment_i_after_memory(&mut self, x: Register) {
+        if !self.quirks.memory_increments_i {
+            return;
+        }
+        let step = if self.quirks.memory_increments_i_by_x {
+            x as u16
+        } else {
+            x as u16 + 1
+        };
+        self.i = self.i.wrapping_add(step);
+    }
+
+    // Registers Vx through Vy, in descending order if x > y
+    fn register_range(x: Register, y: Register) -> Vec<Register> {
+        if x <= y {
+            (x..=y).collect()
+        } else {
+            (y..=x).rev().collect()
+        }
+    }
+
+    // Run one 60 Hz frame: `instructions_per_frame` instructions, then a timer tick
+    pub fn run_frame(&mut self) -> Result<(), Chip8Error> {
+        for _ in 0..self.instructions_per_frame {
+            if self.halted {
+                break;
+            }
+            self.tick()?;
+        }
+        self.timer_tick();
+        Ok(())
+    }
+
+    // Run as many frames as fit in the time passed, carrying the remainder over to the next
+    // call. Works the same with a real clock and a virtual one; returns the frames run.
+    pub fn run_for(&mut self, elapsed: Duration) -> Result<u32, Chip8Error> {
+        self.frame_time += elapsed;
+        let mut frames = 0;
+        while self.frame_time >= FRAME_DURATION {
+            self.frame_time -= FRAME_DURATION;
+            self.run_frame()?;
+            frames += 1;
+        }
+        Ok(frames)
+    }
+
     pub fn timer_tick(&mut self) {
+        // The timers run at the display's refresh rate, so this is also the vertical blank
+        self.vblank = true;
+        self.frames += 1;
+        self.rng.frame();
+
         // Decrement delay timer if it's greater than zero every tick
         if self.dt > 0 {
             self.dt -= 1;
         }
 
         // Decrement sound timer if it's greater than zero every tick
-        // If it's zero, play the "beep" sound
+        // The frontend's audio driver beeps while it's running
         if self.st > 0 {
-            if self.st == 1 {
-                println!("BEEP!");
-            }
             self.st -= 1;
         }
     }
 
     pub fn reset_memory(&mut self) {
-        self.memory = [0; MEMORY_SIZE];
+        self.memory = vec![0; Self::memory_size(&self.quirks)];
     }
 
     pub fn reset_registers(&mut self) {
@@ -1 +1,332 @@
+// Debugger: breakpoints, watchpoints, stepping and pause / resume on top of `Emulator::tick`
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::time::Duration;
 
+use crate::constants::*;
+use crate::cpu::{Address, Emulator, Instruction};
+use crate::disassembler::disassemble;
+use crate::errors::{Chip8Error, Fault};
+use crate::symbols::SymbolMap;
+
+pub mod watchpoint;
+
+use watchpoint::Watchpoint;
+
+// Why the debugger paused the emulator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Breakpoint(Address),        // Reached a breakpoint
+    Watchpoint(usize, Address), // A watchpoint triggered: its id and the instruction's address
+    Stepped,                    // Finished a single step
+    TargetReached,              // Finished a step over, step out or run to cursor
+    Halted,                     // The ROM ran the exit instruction
+}
+
+// Where a step over, step out or run to cursor stops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Address(Address), // Stop when PC reaches the address
+    Return(u8),       // Stop when the stack pointer drops below this depth
+}
+
+pub struct Debugger<'a> {
+    pub emulator: &'a mut Emulator,
+    pub symbols: SymbolMap, // Label names of the running program
+    breakpoints: BTreeSet<Address>,
+    watchpoints: BTreeMap<usize, Watchpoint>,
+    next_watchpoint: usize,
+    target: Option<Target>,
+    paused: bool,
+    resume_from: Option<Address>, // Where execution last stopped, its breakpoint is skipped once
+    frame_cycles: u32,            // Instructions already run in the current frame
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(emulator: &'a mut Emulator) -> Self {
+        Self {
+            emulator,
+            symbols: SymbolMap::new(),
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            next_watchpoint: 0,
+            target: None,
+            paused: false,
+            resume_from: None,
+            frame_cycles: 0,
+        }
+    }
+
+    // BREAKPOINT operations
+    pub fn add_breakpoint(&mut self, addr: Address) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: Address) {
+        self.breakpoints.remove(&addr);
+    }
+
+    // Add the breakpoint if it isn't set, remove it if it is
+    pub fn toggle_breakpoint(&mut self, addr: Address) {
+        if !self.breakpoints.remove(&addr) {
+            self.breakpoints.insert(addr);
+        }
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = &Address> {
+        self.breakpoints.iter()
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    // Use the labels of a symbol map, and stop at the breakpoints it asks for
+    pub fn set_symbols(&mut self, symbols: SymbolMap) {
+        self.breakpoints.extend(symbols.breakpoints.keys());
+        self.symbols = symbols;
+    }
+
+    // WATCHPOINT operations
+    // Returns the id the watchpoint is reported with
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> usize {
+        let id = self.next_watchpoint;
+        self.next_watchpoint += 1;
+        self.watchpoints.insert(id, watchpoint);
+        self.update_trace();
+        id
+    }
+
+    pub fn remove_watchpoint(&mut self, id: usize) -> Option<Watchpoint> {
+        let watchpoint = self.watchpoints.remove(&id);
+        self.update_trace();
+        watchpoint
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = (&usize, &Watchpoint)> {
+        self.watchpoints.iter()
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+        self.update_trace();
+    }
+
+    // Only make the emulator record memory accesses while something reads them
+    fn update_trace(&mut self) {
+        self.emulator.trace_accesses = self.watchpoints.values().any(Watchpoint::needs_trace);
+    }
+
+    // EXECUTION control
+    pub fn pause(&mut self) {
+        self.target = None;
+        self.stop();
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Run exactly one instruction, then pause
+    pub fn step(&mut self) -> Result<StopReason, Chip8Error> {
+        self.target = None;
+        let result = self.execute_one();
+        self.stop();
+        Ok(match result? {
+            Some(reason) => reason,
+            None if self.emulator.halted => StopReason::Halted,
+            None => StopReason::Stepped,
+        })
+    }
+
+    // Run one instruction, or a whole subroutine if it is a call
+    // Subroutines run as part of the following frames, which report `TargetReached`;
+    // anything else is stepped right away.
+    pub fn step_over(&mut self) -> Result<Option<StopReason>, Chip8Error> {
+        match self.emulator.peek() {
+            Some(Instruction::Call(_)) => {
+                self.run_until(Target::Address(self.emulator.pc.wrapping_add(2)));
+                Ok(None)
+            }
+            _ => self.step().map(Some),
+        }
+    }
+
+    // Run until the current subroutine returns, as part of the following frames
+    // Outside of a subroutine this is a single step.
+    pub fn step_out(&mut self) -> Result<Option<StopReason>, Chip8Error> {
+        if self.emulator.sp == 0 {
+            return self.step().map(Some);
+        }
+        self.run_until(Target::Return(self.emulator.sp));
+        Ok(None)
+    }
+
+    // Run until PC reaches an address, as part of the following frames
+    pub fn run_to(&mut self, addr: Address) {
+        self.run_until(Target::Address(addr));
+    }
+
+    fn stop(&mut self) {
+        self.paused = true;
+        self.resume_from = Some(self.emulator.pc);
+    }
+
+    fn run_until(&mut self, target: Target) {
+        self.target = Some(target);
+        self.paused = false;
+    }
+
+    // Run one instruction, and the timers if it finishes a frame
+    // Returns the first watchpoint the instruction triggered.
+    fn execute_one(&mut self) -> Result<Option<StopReason>, Chip8Error> {
+        self.resume_from = None;
+        let pc = self.emulator.pc;
+        let before: Vec<Vec<u8>> = self
+            .watchpoints
+            .values()
+            .map(|watchpoint| watchpoint.snapshot(self.emulator))
+            .collect();
+
+        self.emulator.tick()?;
+
+        // Check before the timers run, so only the instruction's own changes count
+        let triggered = self
+            .watchpoints
+            .iter()
+            .zip(&before)
+            .find(|((_, watchpoint), before)| watchpoint.triggered(self.emulator, before))
+            .map(|((&id, _), _)| StopReason::Watchpoint(id, pc));
+
+        self.frame_cycles += 1;
+        if self.frame_cycles >= self.emulator.instructions_per_frame {
+            self.frame_cycles = 0;
+            self.emulator.timer_tick();
+        }
+        Ok(triggered)
+    }
+
+    // Check whether the emulator should stop before running the instruction at PC
+    fn should_stop(&mut self) -> Option<StopReason> {
+        if self.emulator.halted {
+            return Some(StopReason::Halted);
+        }
+        let reached = match self.target {
+            Some(Target::Address(addr)) => self.emulator.pc == addr,
+            Some(Target::Return(depth)) => self.emulator.sp < depth,
+            None => false,
+        };
+        if reached {
+            self.target = None;
+            return Some(StopReason::TargetReached);
+        }
+        let pc = self.emulator.pc;
+        if self.breakpoints.contains(&pc) && self.resume_from != Some(pc) {
+            return Some(StopReason::Breakpoint(pc));
+        }
+        None
+    }
+
+    // Run the rest of the current frame, like `Emulator::run_frame`, unless something stops
+    // it first. A frame that stopped halfway is finished by the next call.
+    // Errors pause the emulator at the faulting instruction.
+    pub fn run_frame(&mut self) -> Result<Option<StopReason>, Chip8Error> {
+        if self.paused {
+            return Ok(None);
+        }
+        loop {
+            if let Some(reason) = self.should_stop() {
+                // Any stop ends a step over, step out or run to cursor
+                self.target = None;
+                self.stop();
+                return Ok(Some(reason));
+            }
+            let ends_frame = self.frame_cycles + 1 >= self.emulator.instructions_per_frame;
+            match self.execute_one() {
+                Ok(Some(reason)) => {
+                    self.target = None;
+                    self.stop();
+                    return Ok(Some(reason));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    self.stop();
+                    return Err(e);
+                }
+            }
+            if ends_frame {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Run as many frames as fit in the time passed, like `Emulator::run_for`
+    // Time doesn't pass while paused.
+    pub fn run_for(&mut self, elapsed: Duration) -> Result<Option<StopReason>, Chip8Error> {
+        if self.paused {
+            return Ok(None);
+        }
+        self.emulator.frame_time += elapsed;
+        while self.emulator.frame_time >= FRAME_DURATION {
+            self.emulator.frame_time -= FRAME_DURATION;
+            if let Some(reason) = self.run_frame()? {
+                self.emulator.frame_time = Duration::ZERO;
+                return Ok(Some(reason));
+            }
+        }
+        Ok(None)
+    }
+
+    // CRASH REPORT
+    // The error, registers, stack and the code around the failed instruction
+    pub fn crash_report(&self, fault: &Fault) -> String {
+        let emulator = &*self.emulator;
+        let mut report = String::new();
+        // Writing to a String can't fail
+        let _ = writeln!(report, "Crash: {}", fault);
+        if !self.symbols.labels.is_empty() {
+            let _ = writeln!(report, "  in {}", self.symbols.describe(fault.pc));
+        }
+
+        let _ = writeln!(report, "Registers:");
+        for (n, values) in emulator.v.chunks(8).enumerate() {
+            let values: Vec<String> = values.iter().map(|v| format!("{:02X}", v)).collect();
+            let _ = writeln!(
+                report,
+                "  V{:X}-V{:X}: {}",
+                n * 8,
+                n * 8 + 7,
+                values.join(" ")
+            );
+        }
+        let _ = writeln!(
+            report,
+            "  I={:04X} DT={:02X} ST={:02X}",
+            emulator.i, emulator.dt, emulator.st
+        );
+
+        let _ = write!(report, "Stack ({}):", emulator.sp);
+        for &addr in emulator.stack[..emulator.sp as usize].iter().rev() {
+            let _ = write!(report, " {}", self.symbols.describe(addr));
+        }
+        let _ = writeln!(report);
+
+        // Memory is decoded from a few instructions before the fault, with the same alignment
+        let _ = writeln!(report, "Code:");
+        let start = fault.pc.saturating_sub(CRASH_CONTEXT * 2) as usize;
+        let end = (fault.pc as usize + CRASH_CONTEXT as usize * 2 + 2).min(emulator.memory.len());
+        let start = start.min(end);
+        for line in disassemble(&emulator.memory[start..end], start as Address) {
+            if let Some(label) = self.symbols.label(line.addr) {
+                let _ = writeln!(report, "     {}:", label);
+            }
+            let marker = if line.addr == fault.pc { "=>" } else { "  " };
+            let _ = writeln!(report, "  {} {}", marker, line);
+        }
+        report
+    }
+}
@@ -0,0 +1,274 @@
+// Watchpoints: stop when an instruction touches memory or changes a register
+//
+// Watchpoints are checked after every instruction the debugger runs. Changes made by the
+// 60 Hz timers don't trigger them, so a hit always points at the instruction responsible.
+use crate::cpu::{Address, Emulator, MemoryAccess, Register};
+
+// Something whose value can be watched for changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Memory(Address, u16), // Start address and length
+    V(Register),
+    I,
+    Dt,
+    St,
+}
+
+impl Location {
+    // Current bytes of the location, memory past the end and registers past VF read as nothing
+    fn bytes(&self, emulator: &Emulator) -> Vec<u8> {
+        match *self {
+            Location::Memory(start, len) => {
+                let start = (start as usize).min(emulator.memory.len());
+                let end = (start + len as usize).min(emulator.memory.len());
+                emulator.memory[start..end].to_vec()
+            }
+            Location::V(x) => emulator.v.get(x).map_or(Vec::new(), |&v| vec![v]),
+            Location::I => emulator.i.to_be_bytes().to_vec(),
+            Location::Dt => vec![emulator.dt],
+            Location::St => vec![emulator.st],
+        }
+    }
+}
+
+// What a watchpoint waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watch {
+    Read(Address, u16),  // An instruction reads memory in the range (start, length)
+    Write(Address, u16), // An instruction writes memory in the range, even the same value
+    Change(Location),    // An instruction changes the value
+}
+
+// A single value compared by a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    V(Register),
+    I,
+    Dt,
+    St,
+    Pc,
+    Memory(Address), // The byte at the address
+}
+
+impl Value {
+    // Registers past VF and memory past the end read as 0
+    fn read(&self, emulator: &Emulator) -> u16 {
+        match *self {
+            Value::V(x) => emulator.v.get(x).copied().unwrap_or(0) as u16,
+            Value::I => emulator.i,
+            Value::Dt => emulator.dt as u16,
+            Value::St => emulator.st as u16,
+            Value::Pc => emulator.pc,
+            Value::Memory(addr) => emulator.memory.get(addr as usize).copied().unwrap_or(0) as u16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+// A comparison between a value and a constant, e.g. V3 == 0x10
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub value: Value,
+    pub compare: Compare,
+    pub operand: u16,
+}
+
+impl Condition {
+    pub fn holds(&self, emulator: &Emulator) -> bool {
+        let value = self.value.read(emulator);
+        match self.compare {
+            Compare::Eq => value == self.operand,
+            Compare::Ne => value != self.operand,
+            Compare::Lt => value < self.operand,
+            Compare::Le => value <= self.operand,
+            Compare::Gt => value > self.operand,
+            Compare::Ge => value >= self.operand,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub watch: Watch,
+    pub condition: Option<Condition>, // Checked after the instruction ran
+}
+
+impl Watchpoint {
+    pub fn new(watch: Watch) -> Self {
+        Self {
+            watch,
+            condition: None,
+        }
+    }
+
+    // Only trigger while the condition holds
+    pub fn when(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    // Parse a watchpoint like "write 3A0+4", "read 300", "change v3", "change [3A0+2]" or
+    // "change i if v3 == 10". Addresses and numbers are hex, the 0x prefix is optional.
+    // Memory to watch for changes is in brackets, so "change a" can't be taken for VA.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (watch, condition) = match text.split_once(" if ") {
+            Some((watch, condition)) => (watch, Some(condition)),
+            None => (text, None),
+        };
+
+        let mut words = watch.split_whitespace();
+        let kind = words.next()?.to_ascii_lowercase();
+        let target = words.next()?;
+        if words.next().is_some() {
+            return None;
+        }
+        let watch = match kind.as_str() {
+            "read" | "r" => {
+                let (start, len) = parse_memory(target).or_else(|| parse_range(target))?;
+                Watch::Read(start, len)
+            }
+            "write" | "w" => {
+                let (start, len) = parse_memory(target).or_else(|| parse_range(target))?;
+                Watch::Write(start, len)
+            }
+            "change" | "c" => Watch::Change(parse_location(target)?),
+            _ => return None,
+        };
+
+        let watchpoint = Watchpoint::new(watch);
+        match condition {
+            Some(condition) => Some(watchpoint.when(parse_condition(condition)?)),
+            None => Some(watchpoint),
+        }
+    }
+
+    // Bytes a `Change` watchpoint compares, taken before each instruction
+    pub(crate) fn snapshot(&self, emulator: &Emulator) -> Vec<u8> {
+        match self.watch {
+            Watch::Change(location) => location.bytes(emulator),
+            _ => Vec::new(),
+        }
+    }
+
+    // Check the watchpoint after an instruction, given its snapshot from before it
+    pub(crate) fn triggered(&self, emulator: &Emulator, before: &[u8]) -> bool {
+        let hit = match self.watch {
+            Watch::Read(start, len) => emulator.accesses.iter().any(|access| match *access {
+                MemoryAccess::Read(addr, size) => overlaps(start, len, addr, size),
+                _ => false,
+            }),
+            Watch::Write(start, len) => emulator.accesses.iter().any(|access| match *access {
+                MemoryAccess::Write(addr, size) => overlaps(start, len, addr, size),
+                _ => false,
+            }),
+            Watch::Change(location) => location.bytes(emulator) != before,
+        };
+        hit && self
+            .condition
+            .is_none_or(|condition| condition.holds(emulator))
+    }
+
+    // Whether the emulator has to record memory accesses for this watchpoint
+    pub(crate) fn needs_trace(&self) -> bool {
+        matches!(self.watch, Watch::Read(..) | Watch::Write(..))
+    }
+}
+
+// Whether the watched range and an access share an address
+fn overlaps(start: Address, len: u16, addr: usize, size: usize) -> bool {
+    let start = start as usize;
+    addr < start + len as usize && start < addr + size
+}
+
+fn parse_hex(text: &str) -> Option<u16> {
+    let hex = text.trim_start_matches("0x").trim_start_matches("0X");
+    u16::from_str_radix(hex, 16).ok()
+}
+
+// "ADDR" or "ADDR+LEN"
+fn parse_range(text: &str) -> Option<(Address, u16)> {
+    match text.split_once('+') {
+        Some((start, len)) => Some((parse_hex(start)?, parse_hex(len)?.max(1))),
+        None => Some((parse_hex(text)?, 1)),
+    }
+}
+
+// "[ADDR]" or "[ADDR+LEN]"
+fn parse_memory(text: &str) -> Option<(Address, u16)> {
+    let range = text.strip_prefix('[')?.strip_suffix(']')?;
+    parse_range(range)
+}
+
+fn parse_register(text: &str) -> Option<Register> {
+    // A single hex digit, V0 - VF
+    let x = text.strip_prefix(['v', 'V'])?;
+    if x.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(x, 16).ok().map(|x| x as Register)
+}
+
+fn parse_location(text: &str) -> Option<Location> {
+    match text.to_ascii_lowercase().as_str() {
+        "i" => Some(Location::I),
+        "dt" => Some(Location::Dt),
+        "st" => Some(Location::St),
+        _ => match parse_register(text) {
+            Some(x) => Some(Location::V(x)),
+            None => {
+                let (start, len) = parse_memory(text)?;
+                Some(Location::Memory(start, len))
+            }
+        },
+    }
+}
+
+fn parse_value(text: &str) -> Option<Value> {
+    match text.to_ascii_lowercase().as_str() {
+        "i" => Some(Value::I),
+        "dt" => Some(Value::Dt),
+        "st" => Some(Value::St),
+        "pc" => Some(Value::Pc),
+        _ => match parse_register(text) {
+            Some(x) => Some(Value::V(x)),
+            None => text
+                .strip_prefix('[')
+                .and_then(|addr| addr.strip_suffix(']'))
+                .and_then(parse_hex)
+                .map(Value::Memory),
+        },
+    }
+}
+
+// "VALUE OP NUMBER", e.g. "v3 == 10" or "[3A0] >= 80"
+fn parse_condition(text: &str) -> Option<Condition> {
+    let mut words = text.split_whitespace();
+    let value = parse_value(words.next()?)?;
+    let compare = match words.next()? {
+        "==" => Compare::Eq,
+        "!=" => Compare::Ne,
+        "<" => Compare::Lt,
+        "<=" => Compare::Le,
+        ">" => Compare::Gt,
+        ">=" => Compare::Ge,
+        _ => return None,
+    };
+    let operand = parse_hex(words.next()?)?;
+    if words.next().is_some() {
+        return None;
+    }
+    Some(Condition {
+        value,
+        compare,
+        operand,
+    })
+}
@@ -0,0 +1,187 @@
+// Disassembler: turns ROM bytes or any range of memory back into readable listings
+//
+// Decoding is a linear sweep over 2-byte words. Words that aren't an instruction, and a
+// trailing odd byte, are listed as data.
+use std::fmt::{self, Write};
+
+use crate::constants::*;
+use crate::cpu::{Address, Instruction};
+use crate::drivers::rom_driver::ROM;
+
+// Mnemonics to list instructions with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Cowgod, // CLS, LD V0, 0x12, DRW V0, V1, 5, ...
+    Octo,   // clear, v0 := 0x12, sprite v0 v1 5, ...
+}
+
+impl Syntax {
+    // Look up a syntax by name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "cowgod" => Some(Syntax::Cowgod),
+            "octo" => Some(Syntax::Octo),
+            _ => None,
+        }
+    }
+
+    // The instruction's mnemonic in this syntax
+    pub fn format(&self, instruction: &Instruction) -> String {
+        match *self {
+            Syntax::Cowgod => instruction.to_string(),
+            Syntax::Octo => octo(instruction),
+        }
+    }
+
+    // Bytes that aren't an instruction, a word or a single trailing byte
+    fn format_data(&self, bytes: &[u8]) -> String {
+        match (*self, bytes) {
+            (Syntax::Cowgod, &[byte]) => format!("DB {:#04X}", byte),
+            (Syntax::Cowgod, &[hb, lb, ..]) => format!("DW {:#06X}", (hb as u16) << 8 | lb as u16),
+            _ => bytes
+                .iter()
+                .map(|b| format!("{:#04X}", b))
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+// One decoded instruction, or data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub addr: Address,
+    pub bytes: Vec<u8>, // 2 bytes, 4 for F000 NNNN, 1 for a trailing odd byte
+    pub instruction: Option<Instruction>, // None for data
+}
+
+impl Line {
+    // "ADDR: OPCODE  MNEMONIC"
+    pub fn format(&self, syntax: Syntax) -> String {
+        let opcode: String = self.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        let text = match self.instruction {
+            Some(ref instruction) => syntax.format(instruction),
+            None => syntax.format_data(&self.bytes),
+        };
+        format!("{:04X}: {:<8}  {}", self.addr, opcode, text)
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format(Syntax::Cowgod))
+    }
+}
+
+// Decode the bytes, which start at address `origin`
+pub fn disassemble(data: &[u8], origin: Address) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let addr = origin.wrapping_add(pos as u16);
+        if pos + 1 == data.len() {
+            lines.push(Line {
+                addr,
+                bytes: data[pos..].to_vec(),
+                instruction: None,
+            });
+            break;
+        }
+
+        let word = (data[pos] as u16) << 8 | data[pos + 1] as u16;
+        let (instruction, len) = match Instruction::from(word) {
+            // The XO-CHIP long load takes its address from the next word
+            Some(Instruction::LoadILong(_)) if pos + 4 <= data.len() => {
+                let addr = (data[pos + 2] as u16) << 8 | data[pos + 3] as u16;
+                (Some(Instruction::LoadILong(addr)), 4)
+            }
+            Some(Instruction::LoadILong(_)) => (None, 2),
+            instruction => (instruction, 2),
+        };
+        lines.push(Line {
+            addr,
+            bytes: data[pos..pos + len].to_vec(),
+            instruction,
+        });
+        pos += len;
+    }
+    lines
+}
+
+// Decode a ROM as it is loaded into memory
+pub fn disassemble_rom(rom: &ROM) -> Vec<Line> {
+    disassemble(&rom.data, ROM_START)
+}
+
+// Format decoded lines as a listing, one per line
+pub fn listing(lines: &[Line], syntax: Syntax) -> String {
+    let mut out = String::new();
+    for line in lines {
+        // Writing to a String can't fail
+        let _ = writeln!(out, "{}", line.format(syntax));
+    }
+    out
+}
+
+// Octo mnemonic of an instruction
+// Octo has no skip instructions, skips become the matching `if ... then`.
+fn octo(instruction: &Instruction) -> String {
+    match *instruction {
+        Instruction::ScrollDown(n) => format!("scroll-down {}", n),
+        Instruction::ScrollUp(n) => format!("scroll-up {}", n),
+        Instruction::ClearDisplay => "clear".to_string(),
+        Instruction::Return => "return".to_string(),
+        Instruction::ScrollRight => "scroll-right".to_string(),
+        Instruction::ScrollLeft => "scroll-left".to_string(),
+        Instruction::Exit => "exit".to_string(),
+        Instruction::LowRes => "lores".to_string(),
+        Instruction::HighRes => "hires".to_string(),
+
+        Instruction::Jump(addr) => format!("jump {:#05X}", addr),
+        Instruction::Call(addr) => format!(":call {:#05X}", addr),
+        Instruction::SkipEqual(x, byte) => format!("if v{:x} != {:#04X} then", x, byte),
+        Instruction::SkipNotEqual(x, byte) => format!("if v{:x} == {:#04X} then", x, byte),
+        Instruction::SkipEqualXY(x, y) => format!("if v{:x} != v{:x} then", x, y),
+        Instruction::StoreRange(x, y) => format!("save v{:x} - v{:x}", x, y),
+        Instruction::LoadRange(x, y) => format!("load v{:x} - v{:x}", x, y),
+        Instruction::Load(x, byte) => format!("v{:x} := {:#04X}", x, byte),
+        Instruction::Add(x, byte) => format!("v{:x} += {:#04X}", x, byte),
+
+        Instruction::Move(x, y) => format!("v{:x} := v{:x}", x, y),
+        Instruction::Or(x, y) => format!("v{:x} |= v{:x}", x, y),
+        Instruction::And(x, y) => format!("v{:x} &= v{:x}", x, y),
+        Instruction::Xor(x, y) => format!("v{:x} ^= v{:x}", x, y),
+        Instruction::AddXY(x, y) => format!("v{:x} += v{:x}", x, y),
+        Instruction::SubXY(x, y) => format!("v{:x} -= v{:x}", x, y),
+        Instruction::ShiftRight(x, y) => format!("v{:x} >>= v{:x}", x, y),
+        Instruction::SubYX(x, y) => format!("v{:x} =- v{:x}", x, y),
+        Instruction::ShiftLeft(x, y) => format!("v{:x} <<= v{:x}", x, y),
+
+        Instruction::SkipNotEqualXY(x, y) => format!("if v{:x} == v{:x} then", x, y),
+        Instruction::LoadI(addr) => format!("i := {:#05X}", addr),
+        Instruction::JumpV0(addr) => format!("jump0 {:#05X}", addr),
+        Instruction::Random(x, byte) => format!("v{:x} := random {:#04X}", x, byte),
+        Instruction::Draw(x, y, n) => format!("sprite v{:x} v{:x} {}", x, y, n),
+
+        Instruction::SkipKeyPressed(x) => format!("if v{:x} -key then", x),
+        Instruction::SkipKeyNotPressed(x) => format!("if v{:x} key then", x),
+
+        Instruction::LoadILong(addr) => format!("i := long {:#06X}", addr),
+        Instruction::SelectPlane(n) => format!("plane {}", n),
+        Instruction::LoadAudio => "audio".to_string(),
+
+        Instruction::LoadDelay(x) => format!("v{:x} := delay", x),
+        Instruction::WaitKeyPress(x) => format!("v{:x} := key", x),
+        Instruction::SetDelay(x) => format!("delay := v{:x}", x),
+        Instruction::SetSound(x) => format!("buzzer := v{:x}", x),
+        Instruction::AddI(x) => format!("i += v{:x}", x),
+        Instruction::LoadFont(x) => format!("i := hex v{:x}", x),
+        Instruction::LoadBigFont(x) => format!("i := bighex v{:x}", x),
+        Instruction::SetPitch(x) => format!("pitch := v{:x}", x),
+        Instruction::StoreBCD(x) => format!("bcd v{:x}", x),
+        Instruction::StoreRegisters(x) => format!("save v{:x}", x),
+        Instruction::LoadMemory(x) => format!("load v{:x}", x),
+        Instruction::StoreFlags(x) => format!("saveflags v{:x}", x),
+        Instruction::LoadFlags(x) => format!("loadflags v{:x}", x),
+    }
+}
@@ -0,0 +1,192 @@
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Import constants
+use crate::constants::*;
+use crate::platform::Audio;
+
+// The shape of the beeper's tone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Sine,
+    Triangle,
+}
+
+impl Waveform {
+    // Look up a waveform by name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "square" => Some(Waveform::Square),
+            "sine" => Some(Waveform::Sine),
+            "triangle" => Some(Waveform::Triangle),
+            _ => None,
+        }
+    }
+
+    // Sample the waveform at a phase in [0, 1)
+    fn sample(&self, phase: f32) -> f32 {
+        match *self {
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+            Waveform::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeeperConfig {
+    pub waveform: Waveform,
+    pub frequency: f32, // Tone frequency in Hz
+    pub volume: f32,    // 0.0 - 1.0
+    pub sample_rate: u32,
+}
+
+impl Default for BeeperConfig {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Square,
+            frequency: BEEP_FREQUENCY,
+            volume: BEEP_VOLUME,
+            sample_rate: SAMPLE_RATE,
+        }
+    }
+}
+
+// Bits per second an XO-CHIP audio pattern plays at, 4000 at the default pitch of 64
+// and doubling every 48 steps
+pub fn pattern_rate(pitch: u8) -> f32 {
+    4000.0 * 2f32.powf((pitch as f32 - DEFAULT_PITCH as f32) / 48.0)
+}
+
+// Tone generator shared by the audio backends
+// The tone fades in and out over a few milliseconds so starting and stopping doesn't click
+pub struct Beeper {
+    pub config: BeeperConfig,
+    pub playing: bool,
+    pub pattern: Option<[u8; AUDIO_PATTERN_SIZE]>, // XO-CHIP samples played instead of the tone
+    pub pitch: u8,                                 // XO-CHIP pitch register for the pattern
+    phase: f32,                                    // Position in the current period, 0.0 - 1.0
+    position: f32,                                 // Bit of the pattern being played, 0.0 - 128.0
+    gain: f32, // Envelope, moves towards 1.0 while playing and 0.0 while not
+}
+
+impl Beeper {
+    pub fn new(config: BeeperConfig) -> Self {
+        Self {
+            config,
+            playing: false,
+            pattern: None,
+            pitch: DEFAULT_PITCH,
+            phase: 0.0,
+            position: 0.0,
+            gain: 0.0,
+        }
+    }
+
+    // Generate the next sample, in [-volume, volume]
+    pub fn next_sample(&mut self) -> f32 {
+        let rate = self.config.sample_rate as f32;
+        // Move the envelope one step towards its target
+        let step = 1.0 / (rate * BEEP_RAMP_SECONDS);
+        let target = if self.playing { 1.0 } else { 0.0 };
+        if self.gain < target {
+            self.gain = (self.gain + step).min(target);
+        } else {
+            self.gain = (self.gain - step).max(target);
+        }
+
+        // Keep the phase running while silent so the waveform is continuous
+        let sample = match &self.pattern {
+            Some(pattern) => {
+                let bits = (AUDIO_PATTERN_SIZE * 8) as f32;
+                let bit = self.position as usize;
+                self.position = (self.position + pattern_rate(self.pitch) / rate) % bits;
+                match pattern[bit / 8] & (0x80 >> (bit % 8)) {
+                    0 => -1.0,
+                    _ => 1.0,
+                }
+            }
+            None => self.config.waveform.sample(self.phase),
+        };
+        self.phase = (self.phase + self.config.frequency / rate).fract();
+
+        sample * self.gain * self.config.volume
+    }
+}
+
+// Audio backend that renders the beeper into a 16-bit mono WAV file instead of a device
+// Every `set_playing` call renders one 60 Hz frame of samples
+pub struct WavAudio {
+    pub beeper: Beeper,
+    pub samples: Vec<i16>,
+    path: PathBuf,
+}
+
+impl WavAudio {
+    pub fn new<P: AsRef<Path>>(path: P, config: BeeperConfig) -> Self {
+        Self {
+            beeper: Beeper::new(config),
+            samples: Vec::new(),
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    // Write the samples rendered so far to the file
+    pub fn finish(&self) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(&self.path)?);
+        self.write_wav(&mut file)?;
+        file.flush()
+    }
+
+    // Write the samples rendered so far as a WAV file
+    pub fn write_wav<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let sample_rate = self.beeper.config.sample_rate;
+        let data_len = (self.samples.len() * 2) as u32;
+
+        // RIFF header
+        out.write_all(b"RIFF")?;
+        out.write_all(&(36 + data_len).to_le_bytes())?;
+        out.write_all(b"WAVE")?;
+        // Format chunk: PCM, 1 channel, 16 bits per sample
+        out.write_all(b"fmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&sample_rate.to_le_bytes())?;
+        out.write_all(&(sample_rate * 2).to_le_bytes())?;
+        out.write_all(&2u16.to_le_bytes())?;
+        out.write_all(&16u16.to_le_bytes())?;
+        // Data chunk
+        out.write_all(b"data")?;
+        out.write_all(&data_len.to_le_bytes())?;
+        for sample in &self.samples {
+            out.write_all(&sample.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl Audio for WavAudio {
+    fn set_pattern(&mut self, pattern: Option<&[u8; AUDIO_PATTERN_SIZE]>, pitch: u8) {
+        self.beeper.pattern = pattern.copied();
+        self.beeper.pitch = pitch;
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        self.beeper.playing = playing;
+        let frame_len = self.beeper.config.sample_rate as u64 / TIMER_HZ;
+        for _ in 0..frame_len {
+            let sample = self.beeper.next_sample();
+            self.samples.push((sample * i16::MAX as f32) as i16);
+        }
+    }
+}
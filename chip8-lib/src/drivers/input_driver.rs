@@ -0,0 +1,340 @@
+// Import SDL2
+use sdl2::controller::{Button, GameController};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::{EventPump, GameControllerSubsystem, Sdl};
+
+use crate::constants::NUM_KEYS;
+use crate::keymap::KeyBindings;
+use crate::platform::{Input, InputEvent};
+
+// Define the Keyboard struct, it also reads game controllers
+pub struct Keyboard {
+    pub event_pump: EventPump,
+    pub keymap: Keymap,
+    controller_subsystem: Option<GameControllerSubsystem>, // None if SDL2 can't use controllers
+    controllers: Vec<GameController>,                      // Connected controllers
+}
+
+// Implement the Keyboard struct
+impl Keyboard {
+    // Create a new keyboard from an SDL2 context
+    pub fn new(sdl_context: &Sdl, keymap: Keymap) -> Self {
+        let event_pump = sdl_context.event_pump().unwrap();
+        // Controllers are optional, the keyboard still works without them
+        let controller_subsystem = sdl_context
+            .game_controller()
+            .map_err(|e| eprintln!("Game controllers unavailable: {}", e))
+            .ok();
+        Keyboard {
+            event_pump,
+            keymap,
+            controller_subsystem,
+            controllers: Vec::new(),
+        }
+    }
+}
+
+impl Input for Keyboard {
+    fn poll(&mut self) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+        for event in self.event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => events.push(InputEvent::Quit),
+                // Emulator hotkeys
+                Event::KeyDown {
+                    keycode: Some(Keycode::Space),
+                    repeat: false,
+                    ..
+                } => events.push(InputEvent::TogglePause),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F10),
+                    ..
+                } => events.push(InputEvent::Step),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F11),
+                    ..
+                } => events.push(InputEvent::StepOver),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F12),
+                    ..
+                } => events.push(InputEvent::StepOut),
+                // Rewind while Backspace is held
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace),
+                    repeat: false,
+                    ..
+                } => events.push(InputEvent::Rewind(true)),
+                Event::KeyUp {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } => events.push(InputEvent::Rewind(false)),
+                // Alt+Enter toggles fullscreen, Ctrl and +/- change the window size
+                Event::KeyDown {
+                    keycode: Some(Keycode::Return),
+                    keymod,
+                    repeat: false,
+                    ..
+                } if keymod.intersects(Mod::LALTMOD | Mod::RALTMOD) => {
+                    events.push(InputEvent::ToggleFullscreen)
+                }
+                Event::KeyDown {
+                    keycode: Some(key),
+                    keymod,
+                    ..
+                } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD)
+                    && scale_step(key).is_some() =>
+                {
+                    events.push(InputEvent::ChangeScale(scale_step(key).unwrap()))
+                }
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..) | WindowEvent::Exposed,
+                    ..
+                } => events.push(InputEvent::Redraw),
+                // F9 cycles through the color palettes
+                Event::KeyDown {
+                    keycode: Some(Keycode::F9),
+                    repeat: false,
+                    ..
+                } => events.push(InputEvent::NextPalette),
+                // F1 - F4 save to slots 1 - 4, F5 - F8 load them
+                Event::KeyDown {
+                    keycode: Some(key),
+                    repeat: false,
+                    ..
+                } if quick_save_slot(key).is_some() => {
+                    let (slot, save) = quick_save_slot(key).unwrap();
+                    if save {
+                        events.push(InputEvent::SaveState(slot));
+                    } else {
+                        events.push(InputEvent::LoadState(slot));
+                    }
+                }
+                // Handle key presses
+                Event::KeyDown {
+                    keycode: Some(key), ..
+                } => {
+                    if let Some(key) = self.keymap.map(key) {
+                        events.push(InputEvent::KeyDown(key));
+                    }
+                }
+                // Handle key releases
+                Event::KeyUp {
+                    keycode: Some(key), ..
+                } => {
+                    if let Some(key) = self.keymap.map(key) {
+                        events.push(InputEvent::KeyUp(key));
+                    }
+                }
+                // SDL2 also reports the controllers connected at startup as added
+                Event::ControllerDeviceAdded { which, .. } => {
+                    if let Some(subsystem) = &self.controller_subsystem {
+                        match subsystem.open(which) {
+                            Ok(controller) => self.controllers.push(controller),
+                            Err(e) => eprintln!("Can't open game controller {}: {}", which, e),
+                        }
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } => self
+                    .controllers
+                    .retain(|controller| controller.instance_id() != which),
+                // Handle controller buttons
+                Event::ControllerButtonDown { button, .. } => {
+                    if let Some(key) = self.keymap.map_button(button) {
+                        events.push(InputEvent::KeyDown(key));
+                    }
+                }
+                Event::ControllerButtonUp { button, .. } => {
+                    if let Some(key) = self.keymap.map_button(button) {
+                        events.push(InputEvent::KeyUp(key));
+                    }
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+// Scale steps on the keys that zoom in and out
+fn scale_step(key: Keycode) -> Option<i32> {
+    match key {
+        Keycode::Equals | Keycode::Plus | Keycode::KpPlus => Some(1),
+        Keycode::Minus | Keycode::KpMinus => Some(-1),
+        _ => None,
+    }
+}
+
+// The save state slot on a function key, and whether the key saves or loads it
+fn quick_save_slot(key: Keycode) -> Option<(u8, bool)> {
+    match key {
+        Keycode::F1 => Some((1, true)),
+        Keycode::F2 => Some((2, true)),
+        Keycode::F3 => Some((3, true)),
+        Keycode::F4 => Some((4, true)),
+        Keycode::F5 => Some((1, false)),
+        Keycode::F6 => Some((2, false)),
+        Keycode::F7 => Some((3, false)),
+        Keycode::F8 => Some((4, false)),
+        _ => None,
+    }
+}
+
+// Which keyboard keys and controller buttons press each CHIP-8 key 0x0 - 0xF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    pub keys: Vec<(Keycode, u8)>, // Keyboard keys and the CHIP-8 key they press
+    pub buttons: Vec<(Button, u8)>, // Controller buttons and the CHIP-8 key they press
+}
+
+// Names accepted by `Keymap::from_name`
+pub const KEYMAP_NAMES: [&str; 3] = ["qwerty", "azerty", "dvorak"];
+
+impl Keymap {
+    // One keyboard key for each CHIP-8 key, and no controller buttons
+    fn layout(keys: [Keycode; NUM_KEYS]) -> Self {
+        Self {
+            keys: keys
+                .iter()
+                .enumerate()
+                .map(|(k, &key)| (key, k as u8))
+                .collect(),
+            buttons: Vec::new(),
+        }
+    }
+
+    // The 1234/QWER/ASDF/ZXCV block on a QWERTY keyboard
+    pub fn qwerty() -> Self {
+        Self::layout([
+            Keycode::X,    // 0
+            Keycode::Num1, // 1
+            Keycode::Num2, // 2
+            Keycode::Num3, // 3
+            Keycode::Q,    // 4
+            Keycode::W,    // 5
+            Keycode::E,    // 6
+            Keycode::A,    // 7
+            Keycode::S,    // 8
+            Keycode::D,    // 9
+            Keycode::Z,    // A
+            Keycode::C,    // B
+            Keycode::Num4, // C
+            Keycode::R,    // D
+            Keycode::F,    // E
+            Keycode::V,    // F
+        ])
+    }
+
+    // The same block on an AZERTY keyboard, SDL2 reports its number row as digits
+    pub fn azerty() -> Self {
+        let mut keymap = Self::qwerty();
+        keymap.bind_key(Keycode::A, 0x4);
+        keymap.bind_key(Keycode::Z, 0x5);
+        keymap.bind_key(Keycode::Q, 0x7);
+        keymap.bind_key(Keycode::W, 0xA);
+        keymap
+    }
+
+    // The same block on a Dvorak keyboard
+    pub fn dvorak() -> Self {
+        Self::layout([
+            Keycode::Q,         // 0
+            Keycode::Num1,      // 1
+            Keycode::Num2,      // 2
+            Keycode::Num3,      // 3
+            Keycode::Quote,     // 4
+            Keycode::Comma,     // 5
+            Keycode::Period,    // 6
+            Keycode::A,         // 7
+            Keycode::O,         // 8
+            Keycode::E,         // 9
+            Keycode::Semicolon, // A
+            Keycode::J,         // B
+            Keycode::Num4,      // C
+            Keycode::P,         // D
+            Keycode::U,         // E
+            Keycode::K,         // F
+        ])
+    }
+
+    // Look up a keymap by name, see `KEYMAP_NAMES`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "qwerty" => Some(Self::qwerty()),
+            "azerty" => Some(Self::azerty()),
+            "dvorak" => Some(Self::dvorak()),
+            _ => None,
+        }
+    }
+
+    // Make a keyboard key press a CHIP-8 key, instead of what it did before
+    pub fn bind_key(&mut self, key: Keycode, chip8_key: u8) {
+        self.keys.retain(|&(k, _)| k != key);
+        self.keys.push((key, chip8_key));
+    }
+
+    // Make a controller button press a CHIP-8 key, instead of what it did before
+    pub fn bind_button(&mut self, button: Button, chip8_key: u8) {
+        self.buttons.retain(|&(b, _)| b != button);
+        self.buttons.push((button, chip8_key));
+    }
+
+    // Get the CHIP-8 key on a keyboard key
+    pub fn map(&self, key: Keycode) -> Option<u8> {
+        self.keys
+            .iter()
+            .find(|&&(k, _)| k == key)
+            .map(|&(_, chip8_key)| chip8_key)
+    }
+
+    // Get the CHIP-8 key on a controller button
+    pub fn map_button(&self, button: Button) -> Option<u8> {
+        self.buttons
+            .iter()
+            .find(|&&(b, _)| b == button)
+            .map(|&(_, chip8_key)| chip8_key)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::qwerty()
+    }
+}
+
+impl KeyBindings for Keymap {
+    type Key = Keycode;
+    type Button = Button;
+
+    const LAYOUT_NAMES: &'static [&'static str] = &KEYMAP_NAMES;
+
+    fn layout(name: &str) -> Option<Self> {
+        Self::from_name(name)
+    }
+
+    // SDL2's key names: "W", "Up", "Return", "Keypad 8"
+    fn key_from_name(name: &str) -> Option<Keycode> {
+        Keycode::from_name(name)
+    }
+
+    // SDL2's button names: "a", "start", "dpup"
+    fn button_from_name(name: &str) -> Option<Button> {
+        Button::from_string(name)
+    }
+
+    fn use_layout(&mut self, layout: &Self) {
+        self.keys = layout.keys.clone();
+    }
+
+    fn bind_key(&mut self, key: Keycode, chip8_key: u8) {
+        Keymap::bind_key(self, key, chip8_key);
+    }
+
+    fn bind_button(&mut self, button: Button, chip8_key: u8) {
+        Keymap::bind_button(self, button, chip8_key);
+    }
+}
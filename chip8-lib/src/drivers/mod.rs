@@ -1,2 +1,13 @@
+pub mod audio_driver;
 pub mod rom_driver;
+pub mod viewport;
+
+// SDL2 frontend drivers
+#[cfg(feature = "sdl")]
+pub mod input_driver;
+#[cfg(feature = "sdl")]
 pub mod screen_driver;
+
+// rodio audio driver
+#[cfg(feature = "audio")]
+pub mod speaker_driver;
@@ -1,8 +1,10 @@
-use std::{
-    fs::File,
-    io::{self, Read},
-    path::Path,
-};
+use std::{fs::File, io::Read, path::Path};
+
+use crate::constants::*;
+use crate::errors::RomError;
+
+// The largest ROM any platform can load, XO-CHIP's address space after 0x200
+pub const MAX_ROM_SIZE: usize = XO_MEMORY_SIZE - ROM_START as usize;
 
 pub struct ROM {
     pub data: Vec<u8>,
@@ -14,23 +16,54 @@ impl ROM {
         ROM { data, name }
     }
 
-    pub fn from_file(file_path: &str) -> io::Result<ROM> {
-        let path = Path::new(file_path);
-        let mut file = File::open(path).unwrap();
+    // Check that a ROM isn't empty and fits in `max` bytes
+    pub fn validate(&self, max: usize) -> Result<(), RomError> {
+        if self.data.is_empty() {
+            return Err(RomError::Empty);
+        }
+        if self.data.len() > max {
+            return Err(RomError::TooLarge {
+                size: self.data.len(),
+                max,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn from_bytes(data: &[u8], name: &str) -> Result<ROM, RomError> {
+        let rom = ROM::new(data.to_vec(), name.to_string());
+        rom.validate(MAX_ROM_SIZE)?;
+        Ok(rom)
+    }
+
+    // Read a ROM from any source, stops reading once it's too large for any platform
+    pub fn from_reader<R: Read>(reader: R, name: &str) -> Result<ROM, RomError> {
         let mut data: Vec<u8> = Vec::new();
+        reader
+            .take(MAX_ROM_SIZE as u64 + 1)
+            .read_to_end(&mut data)?;
 
-        file.read_to_end(&mut data).unwrap();
-        let name: String = path
+        let rom = ROM::new(data, name.to_string());
+        rom.validate(MAX_ROM_SIZE)?;
+        Ok(rom)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(file_path: P) -> Result<ROM, RomError> {
+        let path = file_path.as_ref();
+        let file = File::open(path)?;
+        let name = path
             .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string()
-            .split('.')
-            .next()
-            .unwrap()
-            .to_string();
-
-        Ok(ROM::new(data, name))
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split('.').next())
+            .unwrap_or_default();
+
+        ROM::from_reader(file, name)
+    }
+
+    // 64-bit FNV-1a hash of the ROM's data, stable across platforms and builds
+    pub fn hash(&self) -> u64 {
+        self.data.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+        })
     }
 }
@@ -1,73 +1,179 @@
 use sdl2::Sdl;
 // Import SDL2
-use sdl2::pixels::Color;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
-use sdl2::render::WindowCanvas;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::video::{FullscreenType, WindowContext};
 
 // Import constants
 use crate::constants::*;
+use crate::drivers::viewport::{viewport, ScaleMode};
+use crate::framebuffer::FrameBuffer;
+use crate::palette::Palette;
+use crate::platform::Display;
 
-// Colors as SDL2 Color structs
-const SDL_BACK_COLOR: Color = Color::RGB(0x0E, 0x0F, 0x12);
-const SDL_FORE_COLOR: Color = Color::RGB(0x35, 0xD6, 0x2F);
+// Window settings for the screen
+#[derive(Debug, Clone)]
+pub struct ScreenConfig {
+    pub scale: u32, // Window pixels per CHIP-8 low-res pixel
+    pub scale_mode: ScaleMode,
+    pub palette: Palette,
+    pub fullscreen: bool,
+}
+
+impl Default for ScreenConfig {
+    fn default() -> Self {
+        Self {
+            scale: DEFAULT_SCREEN_SCALE,
+            scale_mode: ScaleMode::default(),
+            palette: Palette::default(),
+            fullscreen: false,
+        }
+    }
+}
+
+// Convert a 0xRRGGBB color to an SDL2 Color struct
+fn sdl_color(color: u32) -> Color {
+    Color::RGB((color >> 16) as u8, (color >> 8) as u8, color as u8)
+}
 
 // Define the Screen struct
+// The framebuffer is uploaded to a streaming texture, then copied to the window in one go.
 pub struct Screen {
     pub canvas: WindowCanvas,
+    pub scale: u32, // Window pixels per CHIP-8 low-res pixel when not in fullscreen
+    pub scale_mode: ScaleMode,
+    pub palette: Palette,
+    // With SDL2's unsafe_textures a texture doesn't borrow its creator, it lives until it's
+    // destroyed or the canvas is dropped
+    texture_creator: TextureCreator<WindowContext>,
+    texture: Texture,
+    texture_size: (usize, usize), // The framebuffer resolution the texture is for
 }
 
 // Implement the Screen struct
 impl Screen {
     // Create a new screen
-    pub fn new() -> (Self, Sdl) {
+    pub fn new(config: ScreenConfig) -> (Self, Sdl) {
         // Create a new SDL2 context
         let sdl_context = sdl2::init().unwrap();
         // Create a new video context
         let video_subsystem = sdl_context.video().unwrap();
+        // Scale textures with nearest neighbour, so pixels stay sharp
+        sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "0");
         // Create a new window
-        let window = video_subsystem
-            .window(
-                "CHIP-8 Emulator",
-                SCREEN_WIDTH * SCREEN_SCALE,
-                SCREEN_HEIGHT * SCREEN_SCALE,
-            )
-            .position_centered()
-            .build()
-            .unwrap();
+        let mut window = video_subsystem.window(
+            "CHIP-8 Emulator",
+            SCREEN_WIDTH * config.scale,
+            SCREEN_HEIGHT * config.scale,
+        );
+        window.position_centered().resizable();
+        if config.fullscreen {
+            window.fullscreen_desktop();
+        }
+        let window = window.build().unwrap();
         // Create a new canvas
         let mut canvas = window.into_canvas().build().unwrap();
         // Set the canvas draw color to back color
-        canvas.set_draw_color(SDL_BACK_COLOR);
+        canvas.set_draw_color(sdl_color(config.palette.color(0)));
         // Clear the canvas
         canvas.clear();
-        // Scale the canvas
-        canvas
-            .set_scale(SCREEN_SCALE as f32, SCREEN_SCALE as f32)
-            .unwrap();
+        let texture_creator = canvas.texture_creator();
+        let texture_size = (SCREEN_WIDTH as usize, SCREEN_HEIGHT as usize);
+        let texture = Self::create_texture(&texture_creator, texture_size);
         // Return the new screen
-        (Screen { canvas }, sdl_context)
+        let screen = Screen {
+            canvas,
+            scale: config.scale,
+            scale_mode: config.scale_mode,
+            palette: config.palette,
+            texture_creator,
+            texture,
+            texture_size,
+        };
+        (screen, sdl_context)
+    }
+
+    fn create_texture(
+        texture_creator: &TextureCreator<WindowContext>,
+        (width, height): (usize, usize),
+    ) -> Texture {
+        texture_creator
+            .create_texture_streaming(PixelFormatEnum::RGB24, width as u32, height as u32)
+            .unwrap()
+    }
+
+    pub fn draw_screen(&mut self, screen: &FrameBuffer) {
+        let colors: Vec<u32> = screen
+            .pixels
+            .iter()
+            .map(|&pixel| self.palette.color(pixel))
+            .collect();
+        self.draw_colors(screen.width, screen.height, &colors);
     }
 
-    pub fn draw_screen(&mut self, screen: &[bool; (SCREEN_WIDTH * SCREEN_HEIGHT) as usize]) {
-        // Draw the screen
-        for (i, pixel) in screen.iter().enumerate() {
-			let x = i % (SCREEN_WIDTH as usize);
-			let y = i / (SCREEN_WIDTH as usize);
-			// Set the draw color
-            if *pixel {
-				self.canvas.set_draw_color(SDL_FORE_COLOR);
-            } else {
-				self.canvas.set_draw_color(SDL_BACK_COLOR);
-			}
-			// Draw the pixel
-			self.canvas
-                .fill_rect(Rect::new(x as i32, y as i32, 1, 1))
-                .unwrap();
+    // Draw a `width` x `height` screen of 0xRRGGBB colors, e.g. from a `DisplayFilter`
+    pub fn draw_colors(&mut self, width: usize, height: usize, colors: &[u32]) {
+        // The screen changed resolution, make a texture the new size
+        if self.texture_size != (width, height) {
+            self.texture_size = (width, height);
+            let texture = Self::create_texture(&self.texture_creator, self.texture_size);
+            let old = std::mem::replace(&mut self.texture, texture);
+            // SAFETY: the old texture was made by this screen's canvas, which is still alive,
+            // and nothing uses it anymore
+            unsafe { old.destroy() };
         }
+        // Upload the pixels
+        self.texture
+            .with_lock(None, |buffer, pitch| {
+                for (y, row) in colors.chunks(width).enumerate() {
+                    for (x, &color) in row.iter().enumerate() {
+                        let offset = y * pitch + x * 3;
+                        buffer[offset] = (color >> 16) as u8;
+                        buffer[offset + 1] = (color >> 8) as u8;
+                        buffer[offset + 2] = color as u8;
+                    }
+                }
+            })
+            .unwrap();
+        // Letterbox the screen in the window
+        let output = self.canvas.output_size().unwrap();
+        let dest = viewport(output, width as u32, height as u32, self.scale_mode);
+        let dest = Rect::new(dest.x, dest.y, dest.width, dest.height);
+        self.clear();
+        self.canvas.copy(&self.texture, None, dest).unwrap();
         // Present the canvas
         self.canvas.present();
     }
 
+    // Change the window size to a new scale, fullscreen keeps filling the display
+    pub fn set_scale(&mut self, scale: u32) {
+        self.scale = scale.clamp(1, MAX_SCREEN_SCALE);
+        if self.is_fullscreen() {
+            return;
+        }
+        let (width, height) = (SCREEN_WIDTH * self.scale, SCREEN_HEIGHT * self.scale);
+        if let Err(e) = self.canvas.window_mut().set_size(width, height) {
+            eprintln!("Can't resize the window: {}", e);
+        }
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.canvas.window().fullscreen_state() != FullscreenType::Off
+    }
+
+    // Switch between a window and the whole display
+    pub fn toggle_fullscreen(&mut self) {
+        let state = if self.is_fullscreen() {
+            FullscreenType::Off
+        } else {
+            FullscreenType::Desktop
+        };
+        if let Err(e) = self.canvas.window_mut().set_fullscreen(state) {
+            eprintln!("Can't change fullscreen mode: {}", e);
+        }
+    }
+
     // Clear the screen
     pub fn clear(&mut self) {
         // Set the canvas draw color to black
@@ -82,3 +188,9 @@ impl Screen {
         self.canvas.present();
     }
 }
+
+impl Display for Screen {
+    fn draw(&mut self, screen: &FrameBuffer) {
+        self.draw_screen(screen);
+    }
+}
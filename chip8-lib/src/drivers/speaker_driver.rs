@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Import rodio
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+
+use crate::constants::AUDIO_PATTERN_SIZE;
+use crate::drivers::audio_driver::{Beeper, BeeperConfig};
+use crate::errors::Chip8Error;
+use crate::platform::Audio;
+
+// The XO-CHIP pattern and pitch shared with the audio thread
+type SharedPattern = Arc<Mutex<(Option<[u8; AUDIO_PATTERN_SIZE]>, u8)>>;
+
+// Endless rodio source that plays the beeper while the shared flag is set
+struct BeeperSource {
+    beeper: Beeper,
+    playing: Arc<AtomicBool>,
+    pattern: SharedPattern,
+}
+
+impl Iterator for BeeperSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        self.beeper.playing = self.playing.load(Ordering::Relaxed);
+        // Keep the last pattern rather than wait while the emulator changes it
+        if let Ok(shared) = self.pattern.try_lock() {
+            (self.beeper.pattern, self.beeper.pitch) = *shared;
+        }
+        Some(self.beeper.next_sample())
+    }
+}
+
+impl Source for BeeperSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.beeper.config.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+// Define the Speaker struct, plays the beeper on the default output device
+pub struct Speaker {
+    playing: Arc<AtomicBool>,
+    pattern: SharedPattern,
+    // The stream has to stay alive for the sink to make any sound
+    _sink: Sink,
+    _stream: OutputStream,
+    _handle: OutputStreamHandle,
+}
+
+// Implement the Speaker struct
+impl Speaker {
+    pub fn new(config: BeeperConfig) -> Result<Self, Chip8Error> {
+        let (stream, handle) =
+            OutputStream::try_default().map_err(|e| Chip8Error::AudioError(e.to_string()))?;
+        let sink = Sink::try_new(&handle).map_err(|e| Chip8Error::AudioError(e.to_string()))?;
+
+        let playing = Arc::new(AtomicBool::new(false));
+        let beeper = Beeper::new(config);
+        let pattern = Arc::new(Mutex::new((beeper.pattern, beeper.pitch)));
+        sink.append(BeeperSource {
+            beeper,
+            playing: playing.clone(),
+            pattern: pattern.clone(),
+        });
+
+        Ok(Speaker {
+            playing,
+            pattern,
+            _sink: sink,
+            _stream: stream,
+            _handle: handle,
+        })
+    }
+}
+
+impl Audio for Speaker {
+    fn set_pattern(&mut self, pattern: Option<&[u8; AUDIO_PATTERN_SIZE]>, pitch: u8) {
+        if let Ok(mut shared) = self.pattern.lock() {
+            *shared = (pattern.copied(), pitch);
+        }
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        self.playing.store(playing, Ordering::Relaxed);
+    }
+}
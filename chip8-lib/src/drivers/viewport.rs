@@ -0,0 +1,53 @@
+// Fitting the CHIP-8 screen into a window of any size, the rest of the window is letterboxed
+// Kept apart from the SDL2 screen driver, so any frontend can use it.
+
+// How the screen is fitted into the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleMode {
+    #[default]
+    Integer, // Whole window pixels per CHIP-8 pixel, so every pixel is the same size
+    Aspect, // As large as fits, keeping the screen's aspect ratio
+}
+
+// Names accepted by `ScaleMode::from_name`
+pub const SCALE_MODE_NAMES: [&str; 2] = ["integer", "aspect"];
+
+impl ScaleMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "integer" => Some(ScaleMode::Integer),
+            "aspect" | "fit" => Some(ScaleMode::Aspect),
+            _ => None,
+        }
+    }
+}
+
+// The part of the window the screen is drawn in, in window pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Where a `width` x `height` screen goes in a window of `output` size
+pub fn viewport(output: (u32, u32), width: u32, height: u32, mode: ScaleMode) -> Viewport {
+    let (out_width, out_height) = output;
+    let fit = (out_width as f32 / width as f32).min(out_height as f32 / height as f32);
+    // Fall back to a fractional scale when the window is too small for 1x
+    let scale = match mode {
+        ScaleMode::Integer if fit >= 1.0 => fit.floor(),
+        _ => fit,
+    };
+    let (w, h) = (
+        ((width as f32 * scale) as u32).max(1),
+        ((height as f32 * scale) as u32).max(1),
+    );
+    Viewport {
+        x: (out_width.saturating_sub(w) / 2) as i32,
+        y: (out_height.saturating_sub(h) / 2) as i32,
+        width: w,
+        height: h,
+    }
+}
@@ -1,24 +1,171 @@
+use std::error::Error;
 use std::fmt;
+use std::io;
+
+use crate::cpu::{Address, Instruction};
 
 #[derive(Debug)]
 pub enum Chip8Error {
+    Fault(Fault), // An instruction failed
+    AudioError(String),
+    SaveStateError(String),
+    RomMismatch(u64, u64), // Save state made with another ROM: (state's ROM hash, loaded ROM hash)
+    Rom(RomError),
+    MovieError(String),
+    Desync {
+        frame: u64,
+        expected: u64,
+        actual: u64,
+    }, // Movie playback's screen checksums differ
+}
+
+// What went wrong in a failed instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultKind {
     StackUnderflow,
     StackOverflow,
-    InvalidRegister(usize),
-    InvalidNibble(u8),
-    DisplayError(String),
-    InvalidInstruction(u16),
+    InvalidInstruction,
+    InvalidRegister(usize),   // A register the instruction can't use
+    MemoryOutOfBounds(usize), // The address past the end of memory
+    InvalidKey(u8),           // A key above 0xF
+}
+
+// A failed instruction and where it happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fault {
+    pub kind: FaultKind,
+    pub pc: Address,                      // Address of the failed instruction
+    pub opcode: u16,                      // Its first opcode word
+    pub instruction: Option<Instruction>, // None if the opcode didn't decode
+    pub cycle: u64,                       // Instructions run before it
+}
+
+impl std::fmt::Display for FaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FaultKind::StackUnderflow => write!(f, "Stack Underflow"),
+            FaultKind::StackOverflow => write!(f, "Stack Overflow"),
+            FaultKind::InvalidInstruction => write!(f, "Invalid Instruction"),
+            FaultKind::InvalidRegister(register) => write!(f, "Invalid Register: V{:X}", register),
+            FaultKind::MemoryOutOfBounds(addr) => write!(f, "Memory Out Of Bounds: {:#06X}", addr),
+            FaultKind::InvalidKey(key) => write!(f, "Invalid Key: {:#04X}", key),
+        }
+    }
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} @ PC: {:#06X} [{:04X}",
+            self.kind, self.pc, self.opcode
+        )?;
+        if let Some(instruction) = self.instruction {
+            write!(f, " {}", instruction)?;
+        }
+        write!(f, "] after {} cycles", self.cycle)
+    }
 }
 
 impl std::fmt::Display for Chip8Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Chip8Error::StackUnderflow => write!(f, "Stack Underflow"),
-            Chip8Error::StackOverflow => write!(f, "Stack Overflow"),
-            Chip8Error::InvalidRegister(register) => write!(f, "Invalid Register: {}", register),
-            Chip8Error::InvalidNibble(nibble) => write!(f, "Invalid Nibble: {}", nibble),
-            Chip8Error::DisplayError(ref e) => write!(f, "Display Error: {}", e),
-            Chip8Error::InvalidInstruction(pc) => write!(f, "Invalid Instruction @ PC: {}", pc),
+            Chip8Error::Fault(ref fault) => write!(f, "{}", fault),
+            Chip8Error::AudioError(ref e) => write!(f, "Audio Error: {}", e),
+            Chip8Error::SaveStateError(ref e) => write!(f, "Save State Error: {}", e),
+            Chip8Error::RomMismatch(state, loaded) => write!(
+                f,
+                "Save State is for another ROM: {:016X}, loaded ROM is {:016X}",
+                state, loaded
+            ),
+            Chip8Error::Rom(ref e) => write!(f, "ROM Error: {}", e),
+            Chip8Error::MovieError(ref e) => write!(f, "Movie Error: {}", e),
+            Chip8Error::Desync {
+                frame,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Movie desynced on frame {}: screen checksum {:016X}, recorded {:016X}",
+                frame, actual, expected
+            ),
         }
     }
 }
+
+impl Error for Chip8Error {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Chip8Error::Rom(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Fault> for Chip8Error {
+    fn from(fault: Fault) -> Self {
+        Chip8Error::Fault(fault)
+    }
+}
+
+// Why a ROM couldn't be read or doesn't fit in memory
+#[derive(Debug)]
+pub enum RomError {
+    Io(io::Error),
+    Empty,
+    TooLarge { size: usize, max: usize }, // Sizes in bytes
+}
+
+impl std::fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RomError::Io(ref e) => write!(f, "{}", e),
+            RomError::Empty => write!(f, "ROM is empty"),
+            RomError::TooLarge { size, max } => {
+                write!(f, "ROM is {} bytes, only {} bytes fit in memory", size, max)
+            }
+        }
+    }
+}
+
+impl Error for RomError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            RomError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RomError {
+    fn from(e: io::Error) -> Self {
+        RomError::Io(e)
+    }
+}
+
+impl From<RomError> for Chip8Error {
+    fn from(e: RomError) -> Self {
+        Chip8Error::Rom(e)
+    }
+}
+
+// An error in assembly source, pointing at where it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub file: String,
+    pub line: usize,   // 1-based
+    pub column: usize, // 1-based
+    pub message: String,
+}
+
+impl std::fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for AsmError {}
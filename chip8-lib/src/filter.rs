@@ -0,0 +1,103 @@
+// Display filter: post-processing of the framebuffer on the CPU, once per 60 Hz frame
+//
+// Games erase a sprite and draw it again somewhere else, so with XOR drawing moving sprites
+// are blank for part of the frames and flicker. Phosphor fade keeps a pixel that went dark
+// glowing for a few frames, like a CRT's phosphor, so they look solid again.
+use crate::cpu::Emulator;
+use crate::framebuffer::FrameBuffer;
+use crate::palette::Palette;
+
+// Channels closer than this to their color count as done fading
+const FADE_DONE: f32 = 0.5;
+
+#[derive(Debug, Clone, Default)]
+pub struct DisplayFilter {
+    pub decay: f32,        // Brightness a dark pixel keeps each frame, 0 turns fading off
+    pub vblank_only: bool, // Only draw completed frames, never a screen halfway through one
+    glow: Vec<[f32; 3]>,   // The RGB color of every pixel on the last output
+    size: (usize, usize),  // The resolution of `glow`
+    frame: Option<u64>,    // The emulator frame of the last output
+    fading: bool,          // Whether some pixels haven't reached their color yet
+}
+
+impl DisplayFilter {
+    pub fn new(decay: f32, vblank_only: bool) -> Self {
+        Self {
+            decay: decay.clamp(0.0, 1.0),
+            vblank_only,
+            ..Self::default()
+        }
+    }
+
+    // Whether the screen needs drawing again: it changed, or it's still fading and
+    // a frame has passed
+    pub fn should_draw(&self, emulator: &Emulator) -> bool {
+        let new_frame = self.frame != Some(emulator.frames);
+        let changed = emulator.draw_flag || self.fading;
+        if self.vblank_only {
+            new_frame && changed
+        } else {
+            emulator.draw_flag || (new_frame && self.fading)
+        }
+    }
+
+    // The 0xRRGGBB color of every pixel of the emulator's screen, after the filter
+    pub fn apply(&mut self, emulator: &Emulator, palette: &Palette) -> Vec<u32> {
+        let frames = match self.frame {
+            Some(frame) => emulator.frames.saturating_sub(frame),
+            None => 0,
+        };
+        self.frame = Some(emulator.frames);
+        self.fade(&emulator.screen, palette, frames)
+    }
+
+    // Move every pixel `frames` frames closer to its color in the screen.
+    // Lit pixels light up at once, dark ones fade out by `decay` a frame.
+    pub fn fade(&mut self, screen: &FrameBuffer, palette: &Palette, frames: u64) -> Vec<u32> {
+        let targets = screen.pixels.iter().map(|&pixel| rgb(palette.color(pixel)));
+        // A new resolution starts without any glow
+        if self.size != (screen.width, screen.height) {
+            self.size = (screen.width, screen.height);
+            self.glow = targets.clone().collect();
+        }
+
+        // With the filter off, pixels go dark at once even without a new frame
+        let keep = match self.decay {
+            decay if decay > 0.0 => decay.powi(frames.min(i32::MAX as u64) as i32),
+            _ => 0.0,
+        };
+        let mut fading = false;
+        for ((glow, target), &pixel) in self.glow.iter_mut().zip(targets).zip(&screen.pixels) {
+            for (channel, target) in glow.iter_mut().zip(target) {
+                *channel = match pixel {
+                    0 => target + (*channel - target) * keep,
+                    _ => target,
+                };
+                if (*channel - target).abs() < FADE_DONE {
+                    *channel = target;
+                } else {
+                    fading = true;
+                }
+            }
+        }
+        self.fading = fading;
+
+        self.glow
+            .iter()
+            .map(|&[r, g, b]| (r.round() as u32) << 16 | (g.round() as u32) << 8 | b.round() as u32)
+            .collect()
+    }
+
+    // Whether some pixels are still fading out
+    pub fn is_fading(&self) -> bool {
+        self.fading
+    }
+}
+
+fn rgb(color: u32) -> [f32; 3] {
+    [
+        (color >> 16 & 0xFF) as f32,
+        (color >> 8 & 0xFF) as f32,
+        (color & 0xFF) as f32,
+    ]
+}
@@ -0,0 +1,86 @@
+use crate::constants::*;
+
+// A screen whose resolution can change at runtime (64x32 or 128x64 on SUPER-CHIP)
+// Every pixel is a bitmask of the XO-CHIP planes it is lit in; plain CHIP-8 only uses plane 0
+#[derive(Clone)]
+pub struct FrameBuffer {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>, // Row-major, `width * height` pixels
+}
+
+impl FrameBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    // Whether the screen is in SUPER-CHIP's 128x64 mode
+    pub fn is_hires(&self) -> bool {
+        self.width == HIRES_SCREEN_WIDTH as usize
+    }
+
+    // Change the resolution, clearing the screen
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height];
+    }
+
+    // Clear the given planes
+    pub fn clear(&mut self, planes: u8) {
+        self.pixels.iter_mut().for_each(|pixel| *pixel &= !planes);
+    }
+
+    // Get the planes a pixel is lit in
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[x + self.width * y]
+    }
+
+    // XOR a pixel in one plane, returning true if it was turned off (a collision)
+    pub fn toggle(&mut self, x: usize, y: usize, plane: u8) -> bool {
+        let idx = x + self.width * y;
+        let collision = self.pixels[idx] & plane != 0;
+        self.pixels[idx] ^= plane;
+        collision
+    }
+
+    // 64-bit FNV-1a hash of the resolution and pixels, to compare screens cheaply
+    pub fn checksum(&self) -> u64 {
+        let size = [self.width as u8, self.height as u8];
+        size.iter()
+            .chain(&self.pixels)
+            .fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+            })
+    }
+
+    // Scroll the given planes by (dx, dy) pixels, uncovered pixels become blank
+    pub fn scroll(&mut self, dx: isize, dy: isize, planes: u8) {
+        let old = self.pixels.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let src_x = x as isize - dx;
+                let src_y = y as isize - dy;
+                let src = if (0..self.width as isize).contains(&src_x)
+                    && (0..self.height as isize).contains(&src_y)
+                {
+                    old[src_x as usize + self.width * src_y as usize]
+                } else {
+                    0
+                };
+                let idx = x + self.width * y;
+                self.pixels[idx] = (old[idx] & !planes) | (src & planes);
+            }
+        }
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new(SCREEN_WIDTH as usize, SCREEN_HEIGHT as usize)
+    }
+}
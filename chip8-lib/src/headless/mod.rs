@@ -0,0 +1,287 @@
+// Headless runner: runs a ROM with scripted input and no display, for automated testing
+//
+// Input scripts have one event per line, with the frame it happens on and a hex key:
+//   30 down 5
+//   32 up 5
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::cpu::Emulator;
+use crate::errors::{Chip8Error, FaultKind};
+use crate::framebuffer::FrameBuffer;
+use crate::platform::InputEvent;
+
+mod png;
+
+// How long to run the ROM for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Cycles(u64), // Instructions executed
+    Frames(u64), // 60 Hz frames
+}
+
+// How a headless run ended
+#[derive(Debug)]
+pub enum Outcome {
+    Finished,          // Ran up to the limit
+    Halted,            // The ROM ran the SUPER-CHIP exit instruction
+    Timeout,           // Ran out of wall clock time before the limit
+    Error(Chip8Error), // The emulator failed
+}
+
+impl Outcome {
+    // Process exit code for the outcome, so CI can tell failures apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Outcome::Finished | Outcome::Halted => 0,
+            Outcome::Error(Chip8Error::Fault(fault)) => match fault.kind {
+                FaultKind::InvalidInstruction => 2,
+                FaultKind::StackOverflow | FaultKind::StackUnderflow => 3,
+                FaultKind::MemoryOutOfBounds(_) | FaultKind::InvalidKey(_) => 6,
+                FaultKind::InvalidRegister(_) => 7,
+            },
+            Outcome::Timeout => 4,
+            Outcome::Error(_) => 5,
+        }
+    }
+}
+
+// Key presses and releases to replay, sorted by frame
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputScript {
+    pub events: Vec<(u64, InputEvent)>,
+}
+
+impl InputScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Add a key event on a frame, events on the same frame keep their order
+    pub fn add(&mut self, frame: u64, event: InputEvent) {
+        let idx = self.events.partition_point(|&(f, _)| f <= frame);
+        self.events.insert(idx, (frame, event));
+    }
+
+    // Read the text format, blank lines and # comments are skipped
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut script = InputScript::new();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let frame = match words.next() {
+                Some(frame) => frame.parse().ok()?,
+                None => continue,
+            };
+            let action = words.next()?;
+            let key = u8::from_str_radix(words.next()?, 16).ok()?;
+            if key > 0xF || words.next().is_some() {
+                return None;
+            }
+            match action {
+                "down" => script.add(frame, InputEvent::KeyDown(key)),
+                "up" => script.add(frame, InputEvent::KeyUp(key)),
+                _ => return None,
+            }
+        }
+        Some(script)
+    }
+}
+
+// Runs an emulator without any platform drivers
+// Limits and script frames count from the emulator's own cycle and frame counters.
+pub struct Runner {
+    pub emulator: Emulator,
+    pub script: InputScript,
+    pub timeout: Option<Duration>, // Wall clock time allowed for a run
+    next_event: usize,             // Index of the next script event to apply
+}
+
+impl Runner {
+    pub fn new(emulator: Emulator, script: InputScript) -> Self {
+        Self {
+            emulator,
+            script,
+            timeout: None,
+            next_event: 0,
+        }
+    }
+
+    // Run until the limit, the exit instruction, an error or the timeout.
+    // A frame cut short by a cycle limit doesn't tick the timers.
+    pub fn run(&mut self, limit: Limit) -> Outcome {
+        let started = Instant::now();
+        loop {
+            if self
+                .timeout
+                .is_some_and(|timeout| started.elapsed() > timeout)
+            {
+                return Outcome::Timeout;
+            }
+            if self.emulator.halted {
+                return Outcome::Halted;
+            }
+            // Cycles left before the limit, whole frames for a frame limit
+            let cycles = match limit {
+                Limit::Frames(frames) if self.emulator.frames >= frames => {
+                    return Outcome::Finished
+                }
+                Limit::Cycles(cycles) if self.emulator.cycles >= cycles => {
+                    return Outcome::Finished
+                }
+                Limit::Cycles(cycles) => cycles - self.emulator.cycles,
+                Limit::Frames(_) => u64::MAX,
+            };
+            self.apply_input();
+
+            let result = if cycles < self.emulator.instructions_per_frame as u64 {
+                self.run_cycles(cycles)
+            } else {
+                self.emulator.run_frame()
+            };
+            if let Err(e) = result {
+                return Outcome::Error(e);
+            }
+        }
+    }
+
+    // Run the last few instructions before a cycle limit, stopping at the exit instruction
+    fn run_cycles(&mut self, cycles: u64) -> Result<(), Chip8Error> {
+        for _ in 0..cycles {
+            if self.emulator.halted {
+                break;
+            }
+            self.emulator.tick()?;
+        }
+        Ok(())
+    }
+
+    // Press and release the keys the script has for the current frame
+    fn apply_input(&mut self) {
+        while let Some(&(frame, event)) = self.script.events.get(self.next_event) {
+            if frame > self.emulator.frames {
+                break;
+            }
+            match event {
+                InputEvent::KeyDown(key) => self.emulator.key_down(key),
+                InputEvent::KeyUp(key) => self.emulator.key_up(key),
+                _ => {}
+            }
+            self.next_event += 1;
+        }
+    }
+
+    // The registers, stack and memory as a JSON object
+    pub fn state_json(&self) -> String {
+        let emulator = &self.emulator;
+        let list = |values: &mut dyn Iterator<Item = u32>| {
+            values
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        let mut json = String::new();
+        // Writing to a String can't fail
+        let _ = writeln!(json, "{{");
+        let _ = writeln!(json, "  \"cycles\": {},", emulator.cycles);
+        let _ = writeln!(json, "  \"frames\": {},", emulator.frames);
+        let _ = writeln!(json, "  \"pc\": {},", emulator.pc);
+        let _ = writeln!(json, "  \"i\": {},", emulator.i);
+        let _ = writeln!(json, "  \"sp\": {},", emulator.sp);
+        let _ = writeln!(json, "  \"dt\": {},", emulator.dt);
+        let _ = writeln!(json, "  \"st\": {},", emulator.st);
+        let _ = writeln!(json, "  \"halted\": {},", emulator.halted);
+        let _ = writeln!(
+            json,
+            "  \"v\": [{}],",
+            list(&mut emulator.v.iter().map(|&v| v as u32))
+        );
+        let _ = writeln!(
+            json,
+            "  \"stack\": [{}],",
+            list(&mut emulator.stack.iter().map(|&addr| addr as u32))
+        );
+        let _ = writeln!(
+            json,
+            "  \"screen\": {{\"width\": {}, \"height\": {}}},",
+            emulator.screen.width, emulator.screen.height
+        );
+        let _ = writeln!(
+            json,
+            "  \"memory\": [{}]",
+            list(&mut emulator.memory.iter().map(|&byte| byte as u32))
+        );
+        let _ = writeln!(json, "}}");
+        json
+    }
+}
+
+// File formats the screen can be dumped as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenFormat {
+    Ascii, // '.' for blank pixels, '#' for plane 0, a hex digit for other planes
+    Pbm,   // Binary PBM, a pixel is black if it's lit in any plane
+    Png,   // 8-bit grayscale PNG
+}
+
+pub const SCREEN_FORMAT_NAMES: [&str; 3] = ["ascii", "pbm", "png"];
+
+impl ScreenFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ascii" | "txt" => Some(ScreenFormat::Ascii),
+            "pbm" => Some(ScreenFormat::Pbm),
+            "png" => Some(ScreenFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self, screen: &FrameBuffer) -> Vec<u8> {
+        match self {
+            ScreenFormat::Ascii => ascii(screen).into_bytes(),
+            ScreenFormat::Pbm => pbm(screen),
+            ScreenFormat::Png => {
+                // Plane 0 is white, the other plane combinations get darker grays
+                let gray: Vec<u8> = screen
+                    .pixels
+                    .iter()
+                    .map(|&pixel| match pixel {
+                        0 => 0,
+                        planes => 255 - (planes - 1) * 16,
+                    })
+                    .collect();
+                png::encode(screen.width, screen.height, &gray)
+            }
+        }
+    }
+}
+
+fn ascii(screen: &FrameBuffer) -> String {
+    let mut text = String::with_capacity((screen.width + 1) * screen.height);
+    for row in screen.pixels.chunks(screen.width) {
+        for &pixel in row {
+            text.push(match pixel {
+                0 => '.',
+                1 => '#',
+                planes => char::from_digit(planes as u32, 16).unwrap_or('?'),
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn pbm(screen: &FrameBuffer) -> Vec<u8> {
+    let mut data = format!("P4\n{} {}\n", screen.width, screen.height).into_bytes();
+    // Rows are packed 8 pixels per byte, most significant bit first
+    for row in screen.pixels.chunks(screen.width) {
+        for pixels in row.chunks(8) {
+            let byte = pixels.iter().enumerate().fold(0u8, |byte, (bit, &pixel)| {
+                byte | ((pixel != 0) as u8) << (7 - bit)
+            });
+            data.push(byte);
+        }
+    }
+    data
+}
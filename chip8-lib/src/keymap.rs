@@ -0,0 +1,143 @@
+// Keymap files: which keyboard keys and controller buttons press which CHIP-8 keys, for
+// every ROM or single ones. See default_keymap.txt for the format.
+//
+// The file format doesn't depend on a platform library, a frontend's keymap implements
+// `KeyBindings` with its own key and button types and names.
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::constants::NUM_KEYS;
+
+// A frontend's keymap, that keymap files can change
+pub trait KeyBindings: Sized {
+    type Key: Copy + PartialEq + fmt::Debug;
+    type Button: Copy + PartialEq + fmt::Debug;
+
+    // Names accepted by `layout`
+    const LAYOUT_NAMES: &'static [&'static str];
+
+    // A keymap with one keyboard key for each CHIP-8 key, by keyboard layout name
+    fn layout(name: &str) -> Option<Self>;
+    fn key_from_name(name: &str) -> Option<Self::Key>;
+    fn button_from_name(name: &str) -> Option<Self::Button>;
+
+    // Replace every keyboard key with the layout's, keeping the controller buttons
+    fn use_layout(&mut self, layout: &Self);
+    // Make a keyboard key press a CHIP-8 key, instead of what it did before
+    fn bind_key(&mut self, key: Self::Key, chip8_key: u8);
+    // Make a controller button press a CHIP-8 key, instead of what it did before
+    fn bind_button(&mut self, button: Self::Button, chip8_key: u8);
+}
+
+// One line of a keymap file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Binding<K: KeyBindings> {
+    Layout(K),             // `layout = NAME`, replaces every keyboard key
+    Key(K::Key, u8),       // `key NAME = K`
+    Button(K::Button, u8), // `pad BUTTON = K`
+}
+
+impl<K: KeyBindings> Binding<K> {
+    fn apply(&self, keymap: &mut K) {
+        match self {
+            Binding::Layout(layout) => keymap.use_layout(layout),
+            Binding::Key(key, chip8_key) => keymap.bind_key(*key, *chip8_key),
+            Binding::Button(button, chip8_key) => keymap.bind_button(*button, *chip8_key),
+        }
+    }
+}
+
+// A keymap file: bindings for every ROM, then [NAME] sections with more for single ROMs
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeymapConfig<K: KeyBindings> {
+    pub global: Vec<Binding<K>>,
+    pub sections: Vec<(String, Vec<Binding<K>>)>, // ROM file name or hex hash, and its bindings
+}
+
+impl<K: KeyBindings> Default for KeymapConfig<K> {
+    fn default() -> Self {
+        Self {
+            global: Vec::new(),
+            sections: Vec::new(),
+        }
+    }
+}
+
+impl<K: KeyBindings> KeymapConfig<K> {
+    // The built in profiles: arrow keys and a controller for every ROM, and the
+    // layouts of common games. The names are SDL2's.
+    pub fn builtin() -> Self {
+        Self::parse(include_str!("default_keymap.txt")).expect("built in keymap is valid")
+    }
+
+    // Read a keymap file, the error names the line that's wrong
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = KeymapConfig::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                config.sections.push((name.trim().to_string(), Vec::new()));
+                continue;
+            }
+
+            let binding = parse_binding(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            match config.sections.last_mut() {
+                Some((_, bindings)) => bindings.push(binding),
+                None => config.global.push(binding),
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    // Apply the bindings for every ROM, then the sections for this ROM
+    pub fn apply(&self, keymap: &mut K, rom_name: &str, rom_hash: u64) {
+        let for_rom = |name: &str| {
+            name.eq_ignore_ascii_case(rom_name) || u64::from_str_radix(name, 16) == Ok(rom_hash)
+        };
+        let sections = self
+            .sections
+            .iter()
+            .filter(|(name, _)| for_rom(name))
+            .flat_map(|(_, bindings)| bindings);
+        for binding in self.global.iter().chain(sections) {
+            binding.apply(keymap);
+        }
+    }
+}
+
+// `layout = NAME`, `key NAME = K` or `pad BUTTON = K`
+fn parse_binding<K: KeyBindings>(line: &str) -> Result<Binding<K>, String> {
+    let (input, value) = line
+        .split_once('=')
+        .ok_or_else(|| "expected INPUT = VALUE".to_string())?;
+    let (input, value) = (input.trim(), value.trim());
+    if input == "layout" {
+        let layout = K::layout(value)
+            .ok_or_else(|| format!("expected one of {}", K::LAYOUT_NAMES.join(", ")))?;
+        return Ok(Binding::Layout(layout));
+    }
+
+    let chip8_key = u8::from_str_radix(value, 16)
+        .ok()
+        .filter(|&key| key < NUM_KEYS as u8)
+        .ok_or_else(|| format!("{} isn't a CHIP-8 key 0 - F", value))?;
+    match input.split_once(' ') {
+        Some(("key", name)) => K::key_from_name(name.trim())
+            .map(|key| Binding::Key(key, chip8_key))
+            .ok_or_else(|| format!("unknown keyboard key {}", name.trim())),
+        Some(("pad", name)) => K::button_from_name(name.trim())
+            .map(|button| Binding::Button(button, chip8_key))
+            .ok_or_else(|| format!("unknown controller button {}", name.trim())),
+        _ => Err("expected layout, key NAME or pad BUTTON".to_string()),
+    }
+}
@@ -1,5 +1,20 @@
+pub mod assembler;
 pub mod constants;
 pub mod cpu;
 pub mod debugger;
+pub mod disassembler;
 pub mod drivers;
 pub mod errors;
+pub mod filter;
+pub mod framebuffer;
+pub mod headless;
+pub mod keymap;
+pub mod movie;
+pub mod octo;
+pub mod palette;
+pub mod platform;
+pub mod quirks;
+pub mod random;
+pub mod rewind;
+pub mod savestate;
+pub mod symbols;
@@ -0,0 +1,306 @@
+// Movies: the input of a run, frame by frame, that plays back to exactly the same run
+//
+// Text format, numbers in decimal except the hashes and quirks:
+//   chip8-movie 2
+//   rom 0123456789ABCDEF         hash of the ROM
+//   quirks 3B                    `Quirks::to_bits`
+//   random xorshift 42           generator and seed
+//   speed 11                     instructions per frame
+//   memory error                 out of bounds accesses: error or wrap
+//   length 600                   frames recorded
+//   input 30 down 5              key events, applied when the frame starts
+//   check 60 89ABCDEF01234567    framebuffer checksum after the frame
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::constants::*;
+use crate::cpu::{Emulator, OutOfBounds};
+use crate::debugger::{Debugger, StopReason};
+use crate::errors::Chip8Error;
+use crate::platform::InputEvent;
+use crate::quirks::Quirks;
+use crate::random::RandomKind;
+
+pub const MOVIE_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: u64,
+    pub quirks: Quirks,
+    pub random: RandomKind,
+    pub seed: u64,
+    pub instructions_per_frame: u32,
+    pub out_of_bounds: OutOfBounds,
+    pub length: u64,                    // Frames recorded
+    pub inputs: Vec<(u64, InputEvent)>, // Key events and the frame they start, in order
+    pub checksums: BTreeMap<u64, u64>,  // Framebuffer checksums after some of the frames
+}
+
+impl Movie {
+    // An empty movie with the settings of an emulator that has its ROM loaded
+    pub fn new(emulator: &Emulator, random: RandomKind, seed: u64) -> Self {
+        Self {
+            rom_hash: emulator.rom_hash,
+            quirks: emulator.quirks,
+            random,
+            seed,
+            instructions_per_frame: emulator.instructions_per_frame,
+            out_of_bounds: emulator.out_of_bounds,
+            length: 0,
+            inputs: Vec::new(),
+            checksums: BTreeMap::new(),
+        }
+    }
+
+    // A fresh emulator with the movie's settings, the ROM still has to be loaded
+    pub fn emulator(&self) -> Emulator {
+        let mut emulator = Emulator::new(self.quirks);
+        emulator.instructions_per_frame = self.instructions_per_frame;
+        emulator.out_of_bounds = self.out_of_bounds;
+        emulator.rng = self.random.create(self.seed);
+        emulator
+    }
+
+    // Check that the emulator runs the ROM the movie was made with
+    pub fn check_rom(&self, emulator: &Emulator) -> Result<(), Chip8Error> {
+        if self.rom_hash != emulator.rom_hash {
+            return Err(Chip8Error::MovieError(format!(
+                "recorded with ROM {:016X}, loaded ROM is {:016X}",
+                self.rom_hash, emulator.rom_hash
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        // Writing to a String can't fail
+        let _ = writeln!(text, "chip8-movie {}", MOVIE_VERSION);
+        let _ = writeln!(text, "rom {:016X}", self.rom_hash);
+        let _ = writeln!(text, "quirks {:02X}", self.quirks.to_bits());
+        let _ = writeln!(text, "random {} {}", self.random.name(), self.seed);
+        let _ = writeln!(text, "speed {}", self.instructions_per_frame);
+        let memory = match self.out_of_bounds {
+            OutOfBounds::Error => "error",
+            OutOfBounds::Wrap => "wrap",
+        };
+        let _ = writeln!(text, "memory {}", memory);
+        let _ = writeln!(text, "length {}", self.length);
+        for &(frame, event) in &self.inputs {
+            let (action, key) = match event {
+                InputEvent::KeyDown(key) => ("down", key),
+                InputEvent::KeyUp(key) => ("up", key),
+                _ => continue,
+            };
+            let _ = writeln!(text, "input {} {} {:X}", frame, action, key);
+        }
+        for (frame, checksum) in &self.checksums {
+            let _ = writeln!(text, "check {} {:016X}", frame, checksum);
+        }
+        text
+    }
+
+    // Read the text format back, every header line is required
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let mut header = |name: &str| {
+            let mut words = lines.next()?.split_whitespace();
+            (words.next()? == name).then(|| words.collect::<Vec<_>>())
+        };
+
+        if header("chip8-movie")? != [MOVIE_VERSION.to_string()] {
+            return None;
+        }
+        let rom_hash = u64::from_str_radix(header("rom")?.first()?, 16).ok()?;
+        let quirks = Quirks::from_bits(u8::from_str_radix(header("quirks")?.first()?, 16).ok()?);
+        let (random, seed) = match header("random")?.as_slice() {
+            [name, seed] => (RandomKind::from_name(name)?, seed.parse().ok()?),
+            _ => return None,
+        };
+        let instructions_per_frame = header("speed")?
+            .first()?
+            .parse()
+            .ok()
+            .filter(|speed| (1..=MAX_INSTRUCTIONS_PER_FRAME).contains(speed))?;
+        let out_of_bounds = match *header("memory")?.first()? {
+            "error" => OutOfBounds::Error,
+            "wrap" => OutOfBounds::Wrap,
+            _ => return None,
+        };
+        let length = header("length")?.first()?.parse().ok()?;
+
+        let mut movie = Movie {
+            rom_hash,
+            quirks,
+            random,
+            seed,
+            instructions_per_frame,
+            out_of_bounds,
+            length,
+            inputs: Vec::new(),
+            checksums: BTreeMap::new(),
+        };
+        for line in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["input", frame, action, key] => {
+                    let key = u8::from_str_radix(key, 16).ok().filter(|&key| key <= 0xF)?;
+                    let event = match *action {
+                        "down" => InputEvent::KeyDown(key),
+                        "up" => InputEvent::KeyUp(key),
+                        _ => return None,
+                    };
+                    movie.inputs.push((frame.parse().ok()?, event));
+                }
+                ["check", frame, checksum] => {
+                    let checksum = u64::from_str_radix(checksum, 16).ok()?;
+                    movie.checksums.insert(frame.parse().ok()?, checksum);
+                }
+                _ => return None,
+            }
+        }
+        Some(movie)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a movie"))
+    }
+}
+
+// Whether a session adds to its movie or follows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovieMode {
+    Record,
+    Play,
+}
+
+// Runs frames while recording or playing a movie.
+// Key events only ever change at the start of a frame, so a recording plays back exactly.
+pub struct MovieSession {
+    pub movie: Movie,
+    pub mode: MovieMode,
+    pending: Vec<InputEvent>, // Keys pressed while recording, waiting for the next frame
+    next_input: usize,        // Index of the next input to play
+    started: Option<u64>,     // The last frame whose inputs were applied
+}
+
+impl MovieSession {
+    pub fn new(movie: Movie, mode: MovieMode) -> Self {
+        Self {
+            movie,
+            mode,
+            pending: Vec::new(),
+            next_input: 0,
+            started: None,
+        }
+    }
+
+    // A key event from the user; recorded for the next frame, ignored during playback
+    pub fn key(&mut self, event: InputEvent) {
+        if self.mode == MovieMode::Record {
+            self.pending.push(event);
+        }
+    }
+
+    // Whether playback has gone past the end of the movie
+    pub fn finished(&self, emulator: &Emulator) -> bool {
+        self.mode == MovieMode::Play && emulator.frames >= self.movie.length
+    }
+
+    // Run as many frames as fit in the time passed, like `Debugger::run_for`.
+    // Playback fails with `Desync` when a frame doesn't end with the recorded screen.
+    pub fn run_for(
+        &mut self,
+        debugger: &mut Debugger,
+        elapsed: Duration,
+    ) -> Result<Option<StopReason>, Chip8Error> {
+        if debugger.is_paused() {
+            return Ok(None);
+        }
+        debugger.emulator.frame_time += elapsed;
+        while debugger.emulator.frame_time >= FRAME_DURATION {
+            debugger.emulator.frame_time -= FRAME_DURATION;
+
+            let frame = debugger.emulator.frames;
+            self.start_frame(debugger.emulator);
+            let stopped = match debugger.run_frame() {
+                // A recording keeps the frame it failed in, so playing it back fails the same
+                Err(e) if self.mode == MovieMode::Record => {
+                    self.movie.length = frame + 1;
+                    return Err(e);
+                }
+                result => result?,
+            };
+            if debugger.emulator.frames > frame {
+                self.end_frame(debugger.emulator)?;
+            }
+            if stopped.is_some() {
+                debugger.emulator.frame_time = Duration::ZERO;
+                return Ok(stopped);
+            }
+        }
+        Ok(None)
+    }
+
+    // Apply the frame's key events, once even if the debugger stops halfway through it
+    fn start_frame(&mut self, emulator: &mut Emulator) {
+        let frame = emulator.frames;
+        if self.started == Some(frame) {
+            return;
+        }
+        self.started = Some(frame);
+
+        let events = match self.mode {
+            MovieMode::Record => {
+                let events: Vec<InputEvent> = self.pending.drain(..).collect();
+                self.movie
+                    .inputs
+                    .extend(events.iter().map(|&event| (frame, event)));
+                events
+            }
+            MovieMode::Play => {
+                let inputs = &self.movie.inputs[self.next_input..];
+                let count = inputs.iter().take_while(|&&(f, _)| f <= frame).count();
+                self.next_input += count;
+                inputs[..count].iter().map(|&(_, event)| event).collect()
+            }
+        };
+        for event in events {
+            match event {
+                InputEvent::KeyDown(key) => emulator.key_down(key),
+                InputEvent::KeyUp(key) => emulator.key_up(key),
+                _ => {}
+            }
+        }
+    }
+
+    // Record or check the screen after every `MOVIE_CHECK_INTERVAL` frames
+    fn end_frame(&mut self, emulator: &Emulator) -> Result<(), Chip8Error> {
+        let frame = emulator.frames;
+        let checksum = emulator.screen.checksum();
+        match self.mode {
+            MovieMode::Record => {
+                self.movie.length = frame;
+                if frame.is_multiple_of(MOVIE_CHECK_INTERVAL) {
+                    self.movie.checksums.insert(frame, checksum);
+                }
+            }
+            MovieMode::Play => match self.movie.checksums.get(&frame) {
+                Some(&expected) if expected != checksum => {
+                    return Err(Chip8Error::Desync {
+                        frame,
+                        expected,
+                        actual: checksum,
+                    });
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+// :calc expressions, evaluated right to left without precedence like Octo
+//
+// `:calc name { 2 * HERE + 1 }` is 2 * (HERE + 1). Parentheses group, unary operators
+// apply to everything to their right.
+use std::f64::consts::{E, PI};
+
+use super::{parse_number, Compiler, Token};
+use crate::constants::*;
+use crate::errors::AsmError;
+
+const UNARY: [&str; 14] = [
+    "-", "~", "!", "sin", "cos", "tan", "exp", "log", "abs", "sqrt", "sign", "ceil", "floor", "@",
+];
+
+const BINARY: [&str; 19] = [
+    "+", "-", "*", "/", "%", "&", "|", "^", "<<", ">>", "pow", "min", "max", "<", ">", "<=", ">=",
+    "==", "!=",
+];
+
+impl Compiler {
+    // Evaluate the expression up to the closing }, the { was already taken
+    pub(super) fn calc_block(&mut self) -> Result<f64, AsmError> {
+        let open = self.last.clone();
+        let mut tokens = Vec::new();
+        loop {
+            let token = self.next()?;
+            if token.text == "}" {
+                break;
+            }
+            tokens.push(token);
+        }
+        if tokens.is_empty() {
+            return Err(self.error(&open, "empty expression"));
+        }
+
+        let mut pos = 0;
+        let value = self.expression(&tokens, &mut pos)?;
+        match tokens.get(pos) {
+            Some(token) => Err(self.error(token, format!("unexpected `{}`", token.text))),
+            None => Ok(value),
+        }
+    }
+
+    fn expression(&self, tokens: &[Token], pos: &mut usize) -> Result<f64, AsmError> {
+        let lhs = self.term(tokens, pos)?;
+        match tokens.get(*pos) {
+            Some(op) if BINARY.contains(&op.text.as_str()) => {
+                *pos += 1;
+                let rhs = self.expression(tokens, pos)?;
+                self.binary(op, lhs, rhs)
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    fn term(&self, tokens: &[Token], pos: &mut usize) -> Result<f64, AsmError> {
+        let token = match tokens.get(*pos) {
+            Some(token) => token,
+            None => return Err(self.error(&tokens[tokens.len() - 1], "expected a value")),
+        };
+        *pos += 1;
+
+        match token.text.as_str() {
+            "(" => {
+                let value = self.expression(tokens, pos)?;
+                match tokens.get(*pos) {
+                    Some(close) if close.text == ")" => {
+                        *pos += 1;
+                        Ok(value)
+                    }
+                    _ => Err(self.error(token, "`(` is never closed")),
+                }
+            }
+            op if UNARY.contains(&op) => {
+                let value = self.expression(tokens, pos)?;
+                self.unary(token, value)
+            }
+            "HERE" => Ok(self.here as f64),
+            "PI" => Ok(PI),
+            "E" => Ok(E),
+            name => {
+                if let Some(value) = parse_number(name) {
+                    return Ok(value as f64);
+                }
+                if name.starts_with(|c: char| c.is_ascii_digit()) {
+                    if let Ok(value) = name.parse() {
+                        return Ok(value);
+                    }
+                }
+                if let Some(&value) = self.constants.get(name) {
+                    return Ok(value);
+                }
+                if let Some(&addr) = self.labels.get(name) {
+                    return Ok(addr as f64);
+                }
+                Err(self.error(token, format!("undefined name `{}`", name)))
+            }
+        }
+    }
+
+    fn unary(&self, op: &Token, value: f64) -> Result<f64, AsmError> {
+        Ok(match op.text.as_str() {
+            "-" => -value,
+            "~" => !(value as i64) as f64,
+            "!" => (value == 0.0) as u8 as f64,
+            "sin" => value.sin(),
+            "cos" => value.cos(),
+            "tan" => value.tan(),
+            "exp" => value.exp(),
+            "log" => value.ln(),
+            "abs" => value.abs(),
+            "sqrt" => value.sqrt(),
+            "sign" => value.signum(),
+            "ceil" => value.ceil(),
+            "floor" => value.floor(),
+            // The byte compiled at an address so far
+            _ => {
+                let addr = value as usize;
+                if !(ROM_START as usize..XO_MEMORY_SIZE).contains(&addr) {
+                    return Err(self.error(op, format!("address out of range: {:#X}", addr)));
+                }
+                let byte = self.rom.get(addr - ROM_START as usize).copied();
+                byte.unwrap_or(0) as f64
+            }
+        })
+    }
+
+    fn binary(&self, op: &Token, lhs: f64, rhs: f64) -> Result<f64, AsmError> {
+        let (a, b) = (lhs as i64, rhs as i64);
+        Ok(match op.text.as_str() {
+            "+" => lhs + rhs,
+            "-" => lhs - rhs,
+            "*" => lhs * rhs,
+            "/" | "%" if rhs == 0.0 => return Err(self.error(op, "division by zero")),
+            "/" => lhs / rhs,
+            "%" => lhs % rhs,
+            "&" => (a & b) as f64,
+            "|" => (a | b) as f64,
+            "^" => (a ^ b) as f64,
+            "<<" => a.checked_shl(b as u32).unwrap_or(0) as f64,
+            ">>" => a.checked_shr(b as u32).unwrap_or(0) as f64,
+            "pow" => lhs.powf(rhs),
+            "min" => lhs.min(rhs),
+            "max" => lhs.max(rhs),
+            "<" => (lhs < rhs) as u8 as f64,
+            ">" => (lhs > rhs) as u8 as f64,
+            "<=" => (lhs <= rhs) as u8 as f64,
+            ">=" => (lhs >= rhs) as u8 as f64,
+            "==" => (lhs == rhs) as u8 as f64,
+            _ => (lhs != rhs) as u8 as f64,
+        })
+    }
+}
@@ -0,0 +1,907 @@
+// Octo compiler: compiles Octo programs into ROMs, with a symbol map for the debugger
+//
+// Supports the language of the Octo manual: labels, :alias, :const, :calc, :macro, :org,
+// :byte, :call, :unpack, :next, :breakpoint, loop / while / again and
+// if ... then / begin / else / end, including the SUPER-CHIP and XO-CHIP instructions.
+// Like Octo, the ROM starts with a jump to the `main` label, and tokens are separated by
+// whitespace with # starting a comment.
+mod calc;
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+use crate::constants::*;
+use crate::cpu::Address;
+use crate::drivers::rom_driver::ROM;
+use crate::errors::AsmError;
+use crate::symbols::SymbolMap;
+
+// How deep macros can expand inside other macros, catches macros expanding themselves
+const MAX_MACRO_DEPTH: usize = 64;
+
+// A compiled program
+pub struct Program {
+    pub rom: ROM,
+    pub symbols: SymbolMap,
+}
+
+// Compile Octo source into a program, `name` is used for the ROM and in errors
+pub fn compile(name: &str, source: &str) -> Result<Program, AsmError> {
+    let mut compiler = Compiler::new(name, source);
+    compiler.run()?;
+    Ok(Program {
+        rom: ROM::new(compiler.rom, name.to_string()),
+        symbols: compiler.symbols,
+    })
+}
+
+// Compile an Octo source file into a program named after it
+pub fn compile_file<P: AsRef<Path>>(path: P) -> Result<Program, AsmError> {
+    let path = path.as_ref();
+    let file = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|e| AsmError {
+        file: file.clone(),
+        line: 0,
+        column: 0,
+        message: e.to_string(),
+    })?;
+
+    let mut compiler = Compiler::new(&file, &source);
+    compiler.run()?;
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or_default()
+        .to_string();
+    Ok(Program {
+        rom: ROM::new(compiler.rom, name),
+        symbols: compiler.symbols,
+    })
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+    column: usize,
+    depth: usize, // Macro expansions the token came out of
+}
+
+struct Macro {
+    params: Vec<String>,
+    body: Vec<Token>,
+}
+
+// A reference to a label that wasn't defined yet, patched at the end
+#[derive(Debug, Clone, Copy)]
+enum Fixup {
+    Addr(usize),           // Low 12 bits of the instruction at the address
+    Long(usize),           // The 16-bit word at the address
+    LongHigh(usize),       // The address' high byte
+    UnpackHigh(usize, u8), // Nibble in the high 4 bits and the address' high 4 bits
+    UnpackLow(usize),      // The address' low byte
+}
+
+// Open control flow, closed by `else`, `end` or `again`
+enum Block {
+    If(usize),                 // Address of the jump past the block
+    Else(usize),               // Address of the jump past the else block
+    Loop(Address, Vec<usize>), // Start address and the jumps out of `while`s
+}
+
+// A value or register right of an operator
+#[derive(Debug, Clone, Copy)]
+enum Operand {
+    V(u16),
+    Byte(u16),
+}
+
+// The condition of an `if` or `while`
+#[derive(Debug, Clone, Copy)]
+enum Condition {
+    Eq(u16, Operand),
+    Ne(u16, Operand),
+    Key(u16),
+    NotKey(u16),
+    Lt(u16, Operand),
+    Gt(u16, Operand),
+    Le(u16, Operand),
+    Ge(u16, Operand),
+}
+
+impl Condition {
+    fn negate(self) -> Self {
+        match self {
+            Condition::Eq(x, rhs) => Condition::Ne(x, rhs),
+            Condition::Ne(x, rhs) => Condition::Eq(x, rhs),
+            Condition::Key(x) => Condition::NotKey(x),
+            Condition::NotKey(x) => Condition::Key(x),
+            Condition::Lt(x, rhs) => Condition::Ge(x, rhs),
+            Condition::Ge(x, rhs) => Condition::Lt(x, rhs),
+            Condition::Gt(x, rhs) => Condition::Le(x, rhs),
+            Condition::Le(x, rhs) => Condition::Gt(x, rhs),
+        }
+    }
+}
+
+struct Compiler {
+    file: String,
+    tokens: VecDeque<Token>,
+    last: Token, // The last token taken, for errors at the end of the file
+    rom: Vec<u8>,
+    here: usize,
+    labels: HashMap<String, Address>,
+    constants: HashMap<String, f64>,
+    aliases: HashMap<String, u16>,
+    macros: HashMap<String, Macro>,
+    pending: Vec<(Fixup, Token)>,
+    blocks: Vec<(Block, Token)>,
+    symbols: SymbolMap,
+}
+
+// Split the source into whitespace separated tokens, dropping comments
+fn tokenize(source: &str) -> VecDeque<Token> {
+    let mut tokens = VecDeque::new();
+    for (n, line) in source.lines().enumerate() {
+        let mut rest = line;
+        while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+            let word = &rest[start..];
+            let len = word.find(char::is_whitespace).unwrap_or(word.len());
+            if word.starts_with('#') {
+                break;
+            }
+            tokens.push_back(Token {
+                text: word[..len].to_string(),
+                line: n + 1,
+                column: line.len() - word.len() + 1,
+                depth: 0,
+            });
+            rest = &word[len..];
+        }
+    }
+    tokens
+}
+
+// Decimal, 0x hex or 0b binary, optionally negative
+fn parse_number(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(bin) = digits.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2).ok()?
+    } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
+
+// v0 - vf
+fn parse_register(text: &str) -> Option<u16> {
+    let x = text.strip_prefix(['v', 'V'])?;
+    if x.len() != 1 {
+        return None;
+    }
+    u16::from_str_radix(x, 16).ok()
+}
+
+// Words with a meaning of their own, which can't be names
+const KEYWORDS: [&str; 45] = [
+    ":=", "+=", "-=", "=-", "|=", "&=", "^=", ">>=", "<<=", "==", "!=", "<", ">", "<=", ">=",
+    "key", "-key", "hex", "bighex", "random", "delay", "buzzer", "pitch", "i", "long", "then",
+    "begin", "else", "end", "if", "loop", "while", "again", "return", ";", "clear", "sprite",
+    "jump", "jump0", "save", "load", "bcd", "native", "{", "}",
+];
+
+impl Compiler {
+    fn new(file: &str, source: &str) -> Self {
+        Self {
+            file: file.to_string(),
+            tokens: tokenize(source),
+            last: Token {
+                text: String::new(),
+                line: 1,
+                column: 1,
+                depth: 0,
+            },
+            rom: Vec::new(),
+            here: ROM_START as usize,
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            pending: Vec::new(),
+            blocks: Vec::new(),
+            symbols: SymbolMap::new(),
+        }
+    }
+
+    fn error<S: Into<String>>(&self, token: &Token, message: S) -> AsmError {
+        AsmError {
+            file: self.file.clone(),
+            line: token.line,
+            column: token.column,
+            message: message.into(),
+        }
+    }
+
+    fn run(&mut self) -> Result<(), AsmError> {
+        // Reserve the jump to main
+        let start = self.last.clone();
+        self.inst(0x1000)?;
+
+        while let Some(token) = self.tokens.pop_front() {
+            self.last = token.clone();
+            self.statement(token)?;
+        }
+
+        if let Some((_, token)) = self.blocks.last() {
+            return Err(self.error(token, format!("`{}` is never closed", token.text)));
+        }
+        for (fixup, token) in std::mem::take(&mut self.pending) {
+            match self.labels.get(&token.text) {
+                Some(&addr) => self.patch(fixup, addr, &token)?,
+                None => return Err(self.error(&token, format!("undefined name `{}`", token.text))),
+            }
+        }
+        match self.labels.get("main") {
+            Some(&main) => self.patch(Fixup::Addr(ROM_START as usize), main, &start),
+            None => Err(self.error(&start, "the program has no `main` label")),
+        }
+    }
+
+    // TOKENS
+    fn next(&mut self) -> Result<Token, AsmError> {
+        match self.tokens.pop_front() {
+            Some(token) => {
+                self.last = token.clone();
+                Ok(token)
+            }
+            None => Err(self.error(&self.last, "unexpected end of file")),
+        }
+    }
+
+    // Take the next token, which has to be `text`
+    fn expect(&mut self, text: &str) -> Result<(), AsmError> {
+        let token = self.next()?;
+        if token.text != text {
+            return Err(self.error(
+                &token,
+                format!("expected `{}`, found `{}`", text, token.text),
+            ));
+        }
+        Ok(())
+    }
+
+    fn peek_is(&self, text: &str) -> bool {
+        self.tokens.front().is_some_and(|token| token.text == text)
+    }
+
+    // A new name for a label, constant, alias or macro
+    fn name(&mut self) -> Result<Token, AsmError> {
+        let token = self.next()?;
+        if parse_number(&token.text).is_some()
+            || parse_register(&token.text).is_some()
+            || KEYWORDS.contains(&token.text.as_str())
+            || token.text.starts_with(':')
+        {
+            return Err(self.error(&token, format!("`{}` can't be used as a name", token.text)));
+        }
+        Ok(token)
+    }
+
+    fn register_of(&self, token: &Token) -> Option<u16> {
+        parse_register(&token.text).or_else(|| self.aliases.get(&token.text).copied())
+    }
+
+    fn register(&mut self) -> Result<u16, AsmError> {
+        let token = self.next()?;
+        self.register_of(&token).ok_or_else(|| {
+            self.error(
+                &token,
+                format!("expected a register, found `{}`", token.text),
+            )
+        })
+    }
+
+    // The value of a number, constant, label or { calc } starting with `token`
+    // None for a name that isn't defined (yet).
+    fn value_of(&mut self, token: &Token) -> Result<Option<i64>, AsmError> {
+        if token.text == "{" {
+            return Ok(Some(self.calc_block()?.floor() as i64));
+        }
+        if let Some(value) = parse_number(&token.text) {
+            return Ok(Some(value));
+        }
+        if let Some(&value) = self.constants.get(&token.text) {
+            return Ok(Some(value.floor() as i64));
+        }
+        Ok(self.labels.get(&token.text).map(|&addr| addr as i64))
+    }
+
+    // A value that has to be known now
+    fn value(&mut self) -> Result<(i64, Token), AsmError> {
+        let token = self.next()?;
+        match self.value_of(&token)? {
+            Some(value) => Ok((value, token)),
+            None => Err(self.error(&token, format!("undefined name `{}`", token.text))),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u16, AsmError> {
+        let (value, token) = self.value()?;
+        if !(-0x80..=0xFF).contains(&value) {
+            return Err(self.error(&token, format!("byte out of range: {}", value)));
+        }
+        Ok(value as u16 & 0xFF)
+    }
+
+    fn nibble(&mut self) -> Result<u16, AsmError> {
+        let (value, token) = self.value()?;
+        if !(0..=0xF).contains(&value) {
+            return Err(self.error(&token, format!("nibble out of range: {}", value)));
+        }
+        Ok(value as u16)
+    }
+
+    // A register or a byte
+    fn operand(&mut self) -> Result<Operand, AsmError> {
+        let is_register = self
+            .tokens
+            .front()
+            .is_some_and(|token| self.register_of(token).is_some());
+        if is_register {
+            Ok(Operand::V(self.register()?))
+        } else {
+            Ok(Operand::Byte(self.byte()?))
+        }
+    }
+
+    // OUTPUT
+    fn emit(&mut self, byte: u8) -> Result<(), AsmError> {
+        if self.here >= XO_MEMORY_SIZE {
+            return Err(self.error(&self.last, "program doesn't fit in memory"));
+        }
+        let offset = self.here - ROM_START as usize;
+        if offset >= self.rom.len() {
+            self.rom.resize(offset + 1, 0);
+        }
+        self.rom[offset] = byte;
+        self.here += 1;
+        Ok(())
+    }
+
+    fn inst(&mut self, opcode: u16) -> Result<(), AsmError> {
+        let [hb, lb] = opcode.to_be_bytes();
+        self.emit(hb)?;
+        self.emit(lb)
+    }
+
+    // An instruction ending in an address, which can be a label defined later
+    fn inst_addr(&mut self, opcode: u16) -> Result<(), AsmError> {
+        let token = self.next()?;
+        match self.value_of(&token)? {
+            Some(addr) => {
+                let addr = self.check_addr(addr, 0xFFF, &token)?;
+                self.inst(opcode | addr)
+            }
+            None => {
+                self.pending.push((Fixup::Addr(self.here), token));
+                self.inst(opcode)
+            }
+        }
+    }
+
+    fn check_addr(&self, addr: i64, max: u16, token: &Token) -> Result<u16, AsmError> {
+        if !(0..=max as i64).contains(&addr) {
+            return Err(self.error(token, format!("address out of range: {:#X}", addr)));
+        }
+        Ok(addr as u16)
+    }
+
+    fn write(&mut self, at: usize, byte: u8) {
+        self.rom[at - ROM_START as usize] = byte;
+    }
+
+    // Fill in a forward reference
+    fn patch(&mut self, fixup: Fixup, addr: Address, token: &Token) -> Result<(), AsmError> {
+        match fixup {
+            Fixup::Addr(at) => {
+                let addr = self.check_addr(addr as i64, 0xFFF, token)?;
+                let [hb, lb] = addr.to_be_bytes();
+                let high = self.rom[at - ROM_START as usize] & 0xF0;
+                self.write(at, high | hb);
+                self.write(at + 1, lb);
+            }
+            Fixup::Long(at) => {
+                let [hb, lb] = addr.to_be_bytes();
+                self.write(at, hb);
+                self.write(at + 1, lb);
+            }
+            Fixup::LongHigh(at) => self.write(at, (addr >> 8) as u8),
+            Fixup::UnpackHigh(at, nibble) => {
+                let addr = self.check_addr(addr as i64, 0xFFF, token)?;
+                self.write(at, nibble << 4 | (addr >> 8) as u8);
+            }
+            Fixup::UnpackLow(at) => self.write(at, addr as u8),
+        }
+        Ok(())
+    }
+
+    fn define_label(&mut self, token: &Token, addr: usize) -> Result<(), AsmError> {
+        if self.labels.contains_key(&token.text) || self.constants.contains_key(&token.text) {
+            return Err(self.error(token, format!("`{}` is already defined", token.text)));
+        }
+        self.labels.insert(token.text.clone(), addr as Address);
+        self.symbols.add_label(addr as Address, &token.text);
+        Ok(())
+    }
+
+    // STATEMENTS
+    fn statement(&mut self, token: Token) -> Result<(), AsmError> {
+        if let Some(x) = self.register_of(&token) {
+            return self.register_statement(x);
+        }
+        if self.macros.contains_key(&token.text) {
+            return self.expand_macro(&token);
+        }
+
+        match token.text.as_str() {
+            ":" => {
+                let name = self.name()?;
+                self.define_label(&name, self.here)
+            }
+            ":alias" => {
+                let name = self.name()?;
+                let x = self.register()?;
+                self.aliases.insert(name.text, x);
+                Ok(())
+            }
+            ":const" => {
+                let name = self.name()?;
+                let (value, _) = self.value()?;
+                self.define_constant(&name, value as f64)
+            }
+            ":calc" => {
+                let name = self.name()?;
+                self.expect("{")?;
+                let value = self.calc_block()?;
+                self.define_constant(&name, value)
+            }
+            ":macro" => self.define_macro(),
+            ":org" => {
+                let (addr, token) = self.value()?;
+                if !(ROM_START as i64..XO_MEMORY_SIZE as i64).contains(&addr) {
+                    return Err(self.error(&token, format!("address out of range: {:#X}", addr)));
+                }
+                self.here = addr as usize;
+                Ok(())
+            }
+            ":byte" => {
+                let byte = self.byte()?;
+                self.emit(byte as u8)
+            }
+            ":call" => self.inst_addr(0x2000),
+            ":unpack" => self.unpack(),
+            ":next" => {
+                let name = self.name()?;
+                self.define_label(&name, self.here + 1)
+            }
+            ":breakpoint" => {
+                let name = self.next()?;
+                self.symbols
+                    .add_breakpoint(self.here as Address, &name.text);
+                Ok(())
+            }
+            ":monitor" => {
+                // Memory monitors are an Octo IDE feature, skip the address and length
+                self.next()?;
+                self.next()?;
+                Ok(())
+            }
+
+            "clear" => self.inst(0x00E0),
+            "return" | ";" => self.inst(0x00EE),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.inst(0x00C0 | n)
+            }
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.inst(0x00D0 | n)
+            }
+            "scroll-right" => self.inst(0x00FB),
+            "scroll-left" => self.inst(0x00FC),
+            "exit" => self.inst(0x00FD),
+            "lores" => self.inst(0x00FE),
+            "hires" => self.inst(0x00FF),
+            "native" => self.inst_addr(0x0000),
+            "jump" => self.inst_addr(0x1000),
+            "jump0" => self.inst_addr(0xB000),
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.inst(0xD000 | x << 8 | y << 4 | n)
+            }
+            "bcd" => {
+                let x = self.register()?;
+                self.inst(0xF033 | x << 8)
+            }
+            "save" | "load" => {
+                let x = self.register()?;
+                let store = token.text == "save";
+                if self.peek_is("-") {
+                    self.next()?;
+                    let y = self.register()?;
+                    return self.inst(if store { 0x5002 } else { 0x5003 } | x << 8 | y << 4);
+                }
+                self.inst(if store { 0xF055 } else { 0xF065 } | x << 8)
+            }
+            "saveflags" => {
+                let x = self.register()?;
+                self.inst(0xF075 | x << 8)
+            }
+            "loadflags" => {
+                let x = self.register()?;
+                self.inst(0xF085 | x << 8)
+            }
+            "plane" => {
+                let n = self.nibble()?;
+                self.inst(0xF001 | n << 8)
+            }
+            "audio" => self.inst(0xF002),
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                let opcode = match token.text.as_str() {
+                    "delay" => 0xF015,
+                    "buzzer" => 0xF018,
+                    _ => 0xF03A,
+                };
+                self.inst(opcode | x << 8)
+            }
+            "i" => self.i_statement(),
+
+            "if" => self.if_statement(),
+            "else" => match self.blocks.pop() {
+                Some((Block::If(jump), _)) => {
+                    let end = self.here;
+                    self.inst(0x1000)?;
+                    self.patch(Fixup::Addr(jump), self.here as Address, &token)?;
+                    self.blocks.push((Block::Else(end), token));
+                    Ok(())
+                }
+                _ => Err(self.error(&token, "`else` without `if ... begin`")),
+            },
+            "end" => match self.blocks.pop() {
+                Some((Block::If(jump), _)) | Some((Block::Else(jump), _)) => {
+                    self.patch(Fixup::Addr(jump), self.here as Address, &token)
+                }
+                _ => Err(self.error(&token, "`end` without `if ... begin`")),
+            },
+            "loop" => {
+                self.blocks
+                    .push((Block::Loop(self.here as Address, Vec::new()), token));
+                Ok(())
+            }
+            "while" => {
+                let condition = self.condition()?;
+                self.skip_unless(condition.negate())?;
+                let jump = self.here;
+                match self
+                    .blocks
+                    .iter_mut()
+                    .rev()
+                    .find(|(block, _)| matches!(block, Block::Loop(..)))
+                {
+                    Some((Block::Loop(_, breaks), _)) => breaks.push(jump),
+                    _ => return Err(self.error(&token, "`while` outside of a loop")),
+                }
+                self.inst(0x1000)
+            }
+            "again" => match self.blocks.pop() {
+                Some((Block::Loop(start, breaks), _)) => {
+                    let start = self.check_addr(start as i64, 0xFFF, &token)?;
+                    self.inst(0x1000 | start)?;
+                    for jump in breaks {
+                        self.patch(Fixup::Addr(jump), self.here as Address, &token)?;
+                    }
+                    Ok(())
+                }
+                _ => Err(self.error(&token, "`again` without `loop`")),
+            },
+
+            _ => {
+                // A number or constant is a data byte, any other name calls a subroutine
+                if let Some(value) = self.value_of(&token)? {
+                    if self.labels.contains_key(&token.text) {
+                        return self.call(token);
+                    }
+                    if !(-0x80..=0xFF).contains(&value) {
+                        return Err(self.error(&token, format!("byte out of range: {}", value)));
+                    }
+                    return self.emit(value as u8);
+                }
+                if KEYWORDS.contains(&token.text.as_str()) || token.text.starts_with(':') {
+                    return Err(self.error(&token, format!("unexpected `{}`", token.text)));
+                }
+                self.call(token)
+            }
+        }
+    }
+
+    // Call the subroutine at a label
+    fn call(&mut self, token: Token) -> Result<(), AsmError> {
+        self.tokens.push_front(token);
+        self.inst_addr(0x2000)
+    }
+
+    fn define_constant(&mut self, token: &Token, value: f64) -> Result<(), AsmError> {
+        if self.labels.contains_key(&token.text) {
+            return Err(self.error(token, format!("`{}` is already a label", token.text)));
+        }
+        self.constants.insert(token.text.clone(), value);
+        Ok(())
+    }
+
+    // vX := ..., vX += ... and the other register operators
+    fn register_statement(&mut self, x: u16) -> Result<(), AsmError> {
+        let op = self.next()?;
+        let xy = |y: u16| x << 8 | y << 4;
+        match op.text.as_str() {
+            ":=" => {
+                if self.peek_is("random") {
+                    self.next()?;
+                    let byte = self.byte()?;
+                    return self.inst(0xC000 | x << 8 | byte);
+                }
+                if self.peek_is("key") || self.peek_is("delay") {
+                    let source = self.next()?;
+                    let opcode = if source.text == "key" { 0xF00A } else { 0xF007 };
+                    return self.inst(opcode | x << 8);
+                }
+                match self.operand()? {
+                    Operand::V(y) => self.inst(0x8000 | xy(y)),
+                    Operand::Byte(byte) => self.inst(0x6000 | x << 8 | byte),
+                }
+            }
+            "+=" => match self.operand()? {
+                Operand::V(y) => self.inst(0x8004 | xy(y)),
+                Operand::Byte(byte) => self.inst(0x7000 | x << 8 | byte),
+            },
+            "-=" => match self.operand()? {
+                Operand::V(y) => self.inst(0x8005 | xy(y)),
+                // Subtracting a byte is adding its two's complement
+                Operand::Byte(byte) => self.inst(0x7000 | x << 8 | (0x100 - byte) & 0xFF),
+            },
+            "=-" | "|=" | "&=" | "^=" | ">>=" | "<<=" => {
+                let y = self.register()?;
+                let n = match op.text.as_str() {
+                    "|=" => 0x1,
+                    "&=" => 0x2,
+                    "^=" => 0x3,
+                    ">>=" => 0x6,
+                    "=-" => 0x7,
+                    _ => 0xE,
+                };
+                self.inst(0x8000 | xy(y) | n)
+            }
+            _ => Err(self.error(&op, format!("unknown operator `{}`", op.text))),
+        }
+    }
+
+    // i := ..., i += vX
+    fn i_statement(&mut self) -> Result<(), AsmError> {
+        let op = self.next()?;
+        match op.text.as_str() {
+            "+=" => {
+                let x = self.register()?;
+                self.inst(0xF01E | x << 8)
+            }
+            ":=" if self.peek_is("hex") || self.peek_is("bighex") => {
+                let font = self.next()?;
+                let x = self.register()?;
+                let opcode = if font.text == "hex" { 0xF029 } else { 0xF030 };
+                self.inst(opcode | x << 8)
+            }
+            ":=" if self.peek_is("long") => {
+                self.next()?;
+                self.inst(0xF000)?;
+                let token = self.next()?;
+                match self.value_of(&token)? {
+                    Some(addr) => {
+                        let addr = self.check_addr(addr, 0xFFFF, &token)?;
+                        self.inst(addr)
+                    }
+                    None => {
+                        self.pending.push((Fixup::Long(self.here), token));
+                        self.inst(0)
+                    }
+                }
+            }
+            ":=" => self.inst_addr(0xA000),
+            _ => Err(self.error(&op, format!("unknown operator `{}` for i", op.text))),
+        }
+    }
+
+    // :unpack nibble label / :unpack long label, loads an address into v0 and v1
+    fn unpack(&mut self) -> Result<(), AsmError> {
+        let long = self.peek_is("long");
+        let nibble = if long {
+            self.next()?;
+            0
+        } else {
+            self.nibble()? as u8
+        };
+        let token = self.next()?;
+        match self.value_of(&token)? {
+            Some(addr) => {
+                let addr = self.check_addr(addr, if long { 0xFFFF } else { 0xFFF }, &token)?;
+                let high = (nibble as u16) << 12 | addr;
+                self.inst(0x6000 | high >> 8)?;
+                self.inst(0x6100 | addr & 0xFF)
+            }
+            None if long => {
+                self.pending
+                    .push((Fixup::LongHigh(self.here + 1), token.clone()));
+                self.inst(0x6000)?;
+                self.pending.push((Fixup::UnpackLow(self.here + 1), token));
+                self.inst(0x6100)
+            }
+            None => {
+                self.pending
+                    .push((Fixup::UnpackHigh(self.here + 1, nibble), token.clone()));
+                self.inst(0x6000)?;
+                self.pending.push((Fixup::UnpackLow(self.here + 1), token));
+                self.inst(0x6100)
+            }
+        }
+    }
+
+    // if <condition> then / if <condition> begin
+    fn if_statement(&mut self) -> Result<(), AsmError> {
+        let condition = self.condition()?;
+        let token = self.next()?;
+        match token.text.as_str() {
+            "then" => self.skip_unless(condition),
+            "begin" => {
+                self.skip_unless(condition.negate())?;
+                self.blocks.push((Block::If(self.here), token));
+                self.inst(0x1000)
+            }
+            _ => Err(self.error(&token, "expected `then` or `begin`")),
+        }
+    }
+
+    // vX == byte, vX key, vX < vY, ...
+    fn condition(&mut self) -> Result<Condition, AsmError> {
+        let x = self.register()?;
+        let op = self.next()?;
+        Ok(match op.text.as_str() {
+            "key" => Condition::Key(x),
+            "-key" => Condition::NotKey(x),
+            "==" => Condition::Eq(x, self.operand()?),
+            "!=" => Condition::Ne(x, self.operand()?),
+            "<" => Condition::Lt(x, self.operand()?),
+            ">" => Condition::Gt(x, self.operand()?),
+            "<=" => Condition::Le(x, self.operand()?),
+            ">=" => Condition::Ge(x, self.operand()?),
+            _ => return Err(self.error(&op, format!("unknown comparison `{}`", op.text))),
+        })
+    }
+
+    // Emit instructions that skip the next one unless the condition holds
+    // <, >, <= and >= subtract into vF and test the borrow, like Octo.
+    fn skip_unless(&mut self, condition: Condition) -> Result<(), AsmError> {
+        match condition {
+            Condition::Eq(x, Operand::Byte(byte)) => self.inst(0x4000 | x << 8 | byte),
+            Condition::Eq(x, Operand::V(y)) => self.inst(0x9000 | x << 8 | y << 4),
+            Condition::Ne(x, Operand::Byte(byte)) => self.inst(0x3000 | x << 8 | byte),
+            Condition::Ne(x, Operand::V(y)) => self.inst(0x5000 | x << 8 | y << 4),
+            Condition::Key(x) => self.inst(0xE0A1 | x << 8),
+            Condition::NotKey(x) => self.inst(0xE09E | x << 8),
+            // vF = 1 when x >= rhs
+            Condition::Lt(x, rhs) | Condition::Ge(x, rhs) => {
+                match rhs {
+                    Operand::V(y) => {
+                        self.inst(0x8F00 | x << 4)?;
+                        self.inst(0x8F05 | y << 4)?;
+                    }
+                    Operand::Byte(byte) => {
+                        self.inst(0x6F00 | byte)?;
+                        self.inst(0x8F07 | x << 4)?;
+                    }
+                }
+                let skip_when = if matches!(condition, Condition::Lt(..)) {
+                    1
+                } else {
+                    0
+                };
+                self.inst(0x3F00 | skip_when)
+            }
+            // vF = 1 when rhs >= x
+            Condition::Gt(x, rhs) | Condition::Le(x, rhs) => {
+                match rhs {
+                    Operand::V(y) => self.inst(0x8F00 | y << 4)?,
+                    Operand::Byte(byte) => self.inst(0x6F00 | byte)?,
+                }
+                self.inst(0x8F05 | x << 4)?;
+                let skip_when = if matches!(condition, Condition::Gt(..)) {
+                    1
+                } else {
+                    0
+                };
+                self.inst(0x3F00 | skip_when)
+            }
+        }
+    }
+
+    // MACROS
+    // :macro name params... { body }
+    fn define_macro(&mut self) -> Result<(), AsmError> {
+        let name = self.name()?;
+        let mut params = Vec::new();
+        loop {
+            let token = self.next()?;
+            if token.text == "{" {
+                break;
+            }
+            params.push(token.text);
+        }
+
+        let mut body = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = self.next()?;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" if depth == 0 => break,
+                "}" => depth -= 1,
+                _ => {}
+            }
+            body.push(token);
+        }
+        self.macros.insert(name.text, Macro { params, body });
+        Ok(())
+    }
+
+    // Replace a macro invocation with its body, arguments substituted
+    fn expand_macro(&mut self, token: &Token) -> Result<(), AsmError> {
+        if token.depth >= MAX_MACRO_DEPTH {
+            return Err(self.error(token, format!("macro `{}` expands too deeply", token.text)));
+        }
+        let num_params = self.macros[&token.text].params.len();
+        let mut args = Vec::with_capacity(num_params);
+        for _ in 0..num_params {
+            args.push(self.next()?.text);
+        }
+
+        let m = &self.macros[&token.text];
+        let expanded: Vec<Token> = m
+            .body
+            .iter()
+            .map(|body| {
+                let text = match m.params.iter().position(|param| *param == body.text) {
+                    Some(n) => args[n].clone(),
+                    None => body.text.clone(),
+                };
+                Token {
+                    text,
+                    depth: token.depth + 1,
+                    ..body.clone()
+                }
+            })
+            .collect();
+        for body in expanded.into_iter().rev() {
+            self.tokens.push_front(body);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+// Color palettes: the color of every combination of lit XO-CHIP planes
+//
+// A palette has 2, 4 or 16 colors: background and foreground for plain CHIP-8, one for each
+// combination of the first two planes, or one for each combination of all four planes.
+// Palette files have one palette per line, with a name and its 0xRRGGBB hex colors:
+//   # name = background plane1 plane2 both
+//   sunset = 1A1C2C F4B41A E06040 FFF0D0
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::constants::*;
+
+// Pixel values a framebuffer can hold, one bit per plane
+pub const NUM_COLORS: usize = 1 << NUM_PLANES;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub name: String,
+    pub colors: [u32; NUM_COLORS], // 0xRRGGBB, indexed by the pixel's plane bitmask
+}
+
+// Names accepted by `Palette::from_name`, in the order the cycle hotkey goes through them
+pub const PALETTE_NAMES: [&str; 5] = ["classic", "amber", "lcd", "bw", "high-contrast"];
+
+impl Palette {
+    // A palette from 2, 4 or 16 colors. With fewer than 16, the missing plane combinations
+    // reuse the colors, but never the background so lit pixels always show.
+    pub fn new(name: &str, colors: &[u32]) -> Option<Self> {
+        if ![2, 4, NUM_COLORS].contains(&colors.len()) {
+            return None;
+        }
+        let mut palette = [0; NUM_COLORS];
+        for (pixel, color) in palette.iter_mut().enumerate() {
+            *color = match pixel {
+                0 => colors[0],
+                _ => colors[(pixel % colors.len()).max(1)],
+            };
+        }
+        Some(Self {
+            name: name.to_string(),
+            colors: palette,
+        })
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (name, colors): (&str, &[u32]) = match name.to_ascii_lowercase().as_str() {
+            "classic" | "green" => ("classic", &[BACK_COLOR, FORE_COLOR, 0x1B6B17, 0xA6F59F]),
+            "amber" => ("amber", &[0x1A0F00, 0xFFB000, 0x8A5A00, 0xFFE0A0]),
+            // The original Game Boy's greens
+            "lcd" => ("lcd", &[0x9BBC0F, 0x0F380F, 0x8BAC0F, 0x306230]),
+            "bw" | "black-and-white" => ("bw", &[0x000000, 0xFFFFFF, 0x555555, 0xAAAAAA]),
+            "high-contrast" | "contrast" => {
+                ("high-contrast", &[0x000000, 0xFFFF00, 0x00FFFF, 0xFF00FF])
+            }
+            _ => return None,
+        };
+        Self::new(name, colors)
+    }
+
+    // Every built in palette, in `PALETTE_NAMES` order
+    pub fn builtin() -> Vec<Self> {
+        PALETTE_NAMES
+            .iter()
+            .filter_map(|name| Self::from_name(name))
+            .collect()
+    }
+
+    // A palette name, or 2, 4 or 16 hex colors separated by commas: "000000,FFFFFF"
+    pub fn parse(text: &str) -> Option<Self> {
+        if let Some(palette) = Self::from_name(text) {
+            return Some(palette);
+        }
+        let colors = text
+            .split(',')
+            .map(parse_color)
+            .collect::<Option<Vec<_>>>()?;
+        Self::new("custom", &colors)
+    }
+
+    // The color of a pixel with these planes lit
+    pub fn color(&self, pixel: u8) -> u32 {
+        self.colors[pixel as usize % NUM_COLORS]
+    }
+
+    // Change the background and foreground colors, keeping the others
+    pub fn with_colors(mut self, back: Option<u32>, fore: Option<u32>) -> Self {
+        if let Some(back) = back {
+            self.colors[0] = back;
+        }
+        if let Some(fore) = fore {
+            self.colors[1] = fore;
+        }
+        self
+    }
+
+    // Read a palette file, the error names the line that's wrong
+    pub fn parse_file(text: &str) -> Result<Vec<Self>, String> {
+        let mut palettes = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let palette = line
+                .split_once('=')
+                .and_then(|(name, colors)| {
+                    let colors = colors
+                        .split_whitespace()
+                        .map(parse_color)
+                        .collect::<Option<Vec<_>>>()?;
+                    Self::new(name.trim(), &colors)
+                })
+                .ok_or_else(|| {
+                    format!("line {}: expected NAME = 2, 4 or 16 hex colors", number + 1)
+                })?;
+            palettes.push(palette);
+        }
+        Ok(palettes)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<Self>> {
+        let text = fs::read_to_string(path)?;
+        Self::parse_file(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::from_name("classic").unwrap()
+    }
+}
+
+// A RRGGBB hex color, with or without a leading '#'
+pub fn parse_color(hex: &str) -> Option<u32> {
+    let hex = hex.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    // from_str_radix would take a sign too
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
@@ -0,0 +1,62 @@
+// Traits the emulator's frontends implement, so the core never depends on a platform library
+use crate::constants::AUDIO_PATTERN_SIZE;
+use crate::framebuffer::FrameBuffer;
+
+// Something that can show the emulator's screen
+pub trait Display {
+    fn draw(&mut self, screen: &FrameBuffer);
+}
+
+// Something that can play the emulator's beeper
+pub trait Audio {
+    // Called every frame with whether the sound timer is running
+    fn set_playing(&mut self, playing: bool);
+
+    // Called every frame with the XO-CHIP pattern to play instead of the beep, if any,
+    // and its pitch register. Backends that can only beep ignore it.
+    fn set_pattern(&mut self, _pattern: Option<&[u8; AUDIO_PATTERN_SIZE]>, _pitch: u8) {}
+}
+
+// Events an input device reports to the frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    KeyDown(u8),   // CHIP-8 key 0x0 - 0xF pressed
+    KeyUp(u8),     // CHIP-8 key 0x0 - 0xF released
+    TogglePause,   // Pause or resume emulation
+    Step,          // Run a single instruction while paused
+    StepOver,      // Step, running subroutine calls to completion
+    StepOut,       // Run until the current subroutine returns
+    SaveState(u8), // Save a snapshot to a numbered slot
+    LoadState(u8), // Load the snapshot in a numbered slot
+    Rewind(bool),  // Start or stop running the game backwards
+    NextPalette,   // Switch to the next color palette
+    ToggleFullscreen,
+    ChangeScale(i32), // Make the window this many scale steps larger or smaller
+    Redraw,           // The window was resized or uncovered and needs drawing again
+    Quit,             // The user asked to close the emulator
+}
+
+// Something that can read the CHIP-8 keypad
+pub trait Input {
+    // Return every event since the last poll
+    fn poll(&mut self) -> Vec<InputEvent>;
+}
+
+// Drivers that do nothing, for running without a display, sound or input device
+pub struct NullDisplay;
+pub struct NullAudio;
+pub struct NullInput;
+
+impl Display for NullDisplay {
+    fn draw(&mut self, _screen: &FrameBuffer) {}
+}
+
+impl Audio for NullAudio {
+    fn set_playing(&mut self, _playing: bool) {}
+}
+
+impl Input for NullInput {
+    fn poll(&mut self) -> Vec<InputEvent> {
+        Vec::new()
+    }
+}
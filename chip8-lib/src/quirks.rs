@@ -0,0 +1,124 @@
+// Quirks are the behaviors that differ between the CHIP-8 interpreters ROMs were written for.
+// Each flag selects one interpretation of an ambiguous instruction.
+// The default is the behavior this emulator has always had: shifts ignore Vy, I is left
+// untouched, BNNN uses V0, VF is preserved and sprites wrap around the screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Quirks {
+    // 8XY6 / 8XYE: shift Vy into Vx instead of Vx in place
+    pub shift_uses_vy: bool,
+    // FX55 / FX65: leave I pointing past the last register
+    pub memory_increments_i: bool,
+    // FX55 / FX65: with `memory_increments_i`, leave I on the last register, X short of it.
+    // Has no effect on its own, I is left untouched without `memory_increments_i`
+    pub memory_increments_i_by_x: bool,
+    // BNNN: jump to XNN + Vx instead of NNN + V0
+    pub jump_uses_vx: bool,
+    // 8XY1 / 8XY2 / 8XY3: reset VF to 0
+    pub vf_reset: bool,
+    // DXYN: clip sprites at the screen edge instead of wrapping
+    pub clip_sprites: bool,
+    // DXYN: wait for the vertical blank before drawing
+    pub display_wait: bool,
+    // Enable the XO-CHIP extensions: 64K memory, bitplanes, audio patterns and F000 NNNN
+    pub xo_chip: bool,
+}
+
+// Names accepted by `Quirks::preset`
+pub const PRESET_NAMES: [&str; 5] = ["default", "vip", "chip48", "schip", "xochip"];
+
+impl Quirks {
+    // The COSMAC VIP's original CHIP-8 interpreter
+    pub fn cosmac_vip() -> Self {
+        Self {
+            shift_uses_vy: true,
+            memory_increments_i: true,
+            memory_increments_i_by_x: false,
+            jump_uses_vx: false,
+            vf_reset: true,
+            clip_sprites: true,
+            display_wait: true,
+            xo_chip: false,
+        }
+    }
+
+    // CHIP-48 for the HP-48 calculators, SUPER-CHIP's predecessor: the same except that
+    // load and store leave I incremented by X
+    pub fn chip48() -> Self {
+        Self {
+            memory_increments_i: true,
+            memory_increments_i_by_x: true,
+            ..Self::superchip()
+        }
+    }
+
+    // SUPER-CHIP 1.1
+    pub fn superchip() -> Self {
+        Self {
+            shift_uses_vy: false,
+            memory_increments_i: false,
+            memory_increments_i_by_x: false,
+            jump_uses_vx: true,
+            vf_reset: false,
+            clip_sprites: true,
+            display_wait: false,
+            xo_chip: false,
+        }
+    }
+
+    // XO-CHIP as implemented by Octo
+    pub fn xo_chip() -> Self {
+        Self {
+            shift_uses_vy: true,
+            memory_increments_i: true,
+            memory_increments_i_by_x: false,
+            jump_uses_vx: false,
+            vf_reset: false,
+            clip_sprites: false,
+            display_wait: false,
+            xo_chip: true,
+        }
+    }
+
+    // Look up a preset by name, see `PRESET_NAMES`
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "default" => Some(Self::default()),
+            "vip" | "cosmac" | "chip8" => Some(Self::cosmac_vip()),
+            "chip48" => Some(Self::chip48()),
+            "schip" | "superchip" => Some(Self::superchip()),
+            "xochip" | "xo-chip" | "octo" => Some(Self::xo_chip()),
+            _ => None,
+        }
+    }
+
+    // Pack the quirks into one bit each, for save states and movies
+    pub fn to_bits(&self) -> u8 {
+        [
+            self.shift_uses_vy,
+            self.memory_increments_i,
+            self.jump_uses_vx,
+            self.vf_reset,
+            self.clip_sprites,
+            self.display_wait,
+            self.xo_chip,
+            self.memory_increments_i_by_x,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (n, &flag)| bits | ((flag as u8) << n))
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        let flag = |n: u8| bits & (1 << n) != 0;
+        Self {
+            shift_uses_vy: flag(0),
+            memory_increments_i: flag(1),
+            jump_uses_vx: flag(2),
+            vf_reset: flag(3),
+            clip_sprites: flag(4),
+            display_wait: flag(5),
+            xo_chip: flag(6),
+            memory_increments_i_by_x: flag(7),
+        }
+    }
+}
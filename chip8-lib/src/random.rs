@@ -0,0 +1,144 @@
+// Random number generators for CXNN, part of the emulator's state so runs can be reproduced
+//
+// A generator's state goes into save states, so loading one replays the same numbers.
+
+// Something that produces the random bytes of CXNN
+pub trait RandomSource {
+    fn next_byte(&mut self) -> u8;
+
+    // Called on every 60 Hz timer tick, for generators that depend on timing
+    fn frame(&mut self) {}
+
+    // The generator's state for save states
+    fn save(&self) -> Vec<u8>;
+
+    // Restore a state from `save`, false if it doesn't belong to this kind of generator
+    fn load(&mut self, data: &[u8]) -> bool;
+}
+
+// The built in generators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomKind {
+    Xorshift,
+    Vip,
+}
+
+// Names accepted by `RandomKind::from_name`
+pub const RANDOM_NAMES: [&str; 2] = ["xorshift", "vip"];
+
+impl RandomKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "xorshift" | "default" => Some(RandomKind::Xorshift),
+            "vip" | "cosmac" => Some(RandomKind::Vip),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RandomKind::Xorshift => "xorshift",
+            RandomKind::Vip => "vip",
+        }
+    }
+
+    pub fn create(&self, seed: u64) -> Box<dyn RandomSource> {
+        match self {
+            RandomKind::Xorshift => Box::new(Xorshift::new(seed)),
+            RandomKind::Vip => Box::new(VipRandom::new(seed)),
+        }
+    }
+}
+
+// A seed that's different on every run, for when reproducing the run doesn't matter
+pub fn entropy_seed() -> u64 {
+    rand::random()
+}
+
+// The default generator, xorshift64*
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xorshift {
+    state: u64, // Never 0
+}
+
+impl Xorshift {
+    pub fn new(seed: u64) -> Self {
+        // SplitMix64 spreads similar seeds apart and never gives 0 for the state
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Self {
+            state: (z ^ (z >> 31)).max(1),
+        }
+    }
+}
+
+impl RandomSource for Xorshift {
+    fn next_byte(&mut self) -> u8 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.state.to_le_bytes().to_vec()
+    }
+
+    fn load(&mut self, data: &[u8]) -> bool {
+        match data.try_into().map(u64::from_le_bytes) {
+            Ok(state) if state != 0 => {
+                self.state = state;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Works like the COSMAC VIP interpreter's generator: a counter that the 60 Hz interrupt
+// advances is mixed into an accumulator on every CXNN, so the numbers a ROM gets depend on
+// when it asks for them. The VIP mixes in bytes of its interpreter code, which isn't part of
+// this emulator, so the sequence has the VIP's timing dependence but not its exact values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VipRandom {
+    counter: u8,     // Advanced by the interrupt and by every CXNN
+    accumulator: u8, // The last number generated
+}
+
+impl VipRandom {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            counter: seed as u8,
+            accumulator: (seed >> 8) as u8,
+        }
+    }
+}
+
+impl RandomSource for VipRandom {
+    fn next_byte(&mut self) -> u8 {
+        self.counter = self.counter.wrapping_add(1);
+        let mixed = self.accumulator.wrapping_add(self.counter);
+        self.accumulator = mixed.rotate_right(1) ^ (self.counter << 3);
+        self.accumulator
+    }
+
+    fn frame(&mut self) {
+        self.counter = self.counter.wrapping_add(1);
+    }
+
+    fn save(&self) -> Vec<u8> {
+        vec![self.counter, self.accumulator]
+    }
+
+    fn load(&mut self, data: &[u8]) -> bool {
+        match *data {
+            [counter, accumulator] => {
+                self.counter = counter;
+                self.accumulator = accumulator;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,159 @@
+// Rewind: a bounded history of save states, to run the game backwards
+//
+// Only the newest snapshot is kept whole. Every older one is stored as a delta that turns
+// the snapshot after it back into it, which is small because most memory doesn't change
+// between frames.
+use std::collections::VecDeque;
+
+use crate::cpu::Emulator;
+
+// Runs of changed bytes closer than this are merged into one
+const MERGE_GAP: usize = 8;
+
+// Turns a snapshot into the one taken before it
+enum Delta {
+    // The snapshots have different sizes (the resolution changed), keep the old one whole
+    Full(Vec<u8>),
+    // The old bytes of every run that changed: (offset, bytes)
+    Sparse(Vec<(usize, Vec<u8>)>),
+}
+
+impl Delta {
+    // Compute the delta that turns `new` back into `old`
+    fn between(new: &[u8], old: &[u8]) -> Self {
+        if new.len() != old.len() {
+            return Delta::Full(old.to_vec());
+        }
+
+        let mut runs: Vec<(usize, Vec<u8>)> = Vec::new();
+        let mut pos = 0;
+        while pos < old.len() {
+            if old[pos] == new[pos] {
+                pos += 1;
+                continue;
+            }
+            // Find the end of this run of changes
+            let start = pos;
+            while pos < old.len() && old[pos] != new[pos] {
+                pos += 1;
+            }
+            match runs.last_mut() {
+                // Close to the previous run, extend it over the unchanged gap
+                Some((offset, bytes)) if start - (*offset + bytes.len()) < MERGE_GAP => {
+                    bytes.extend_from_slice(&old[*offset + bytes.len()..pos]);
+                }
+                _ => runs.push((start, old[start..pos].to_vec())),
+            }
+        }
+        Delta::Sparse(runs)
+    }
+
+    // Turn the newer snapshot into the older one
+    fn apply(&self, new: &[u8]) -> Vec<u8> {
+        match self {
+            Delta::Full(old) => old.clone(),
+            Delta::Sparse(runs) => {
+                let mut old = new.to_vec();
+                for (offset, bytes) in runs {
+                    old[*offset..*offset + bytes.len()].copy_from_slice(bytes);
+                }
+                old
+            }
+        }
+    }
+
+    // Bytes used by the delta
+    fn size(&self) -> usize {
+        match self {
+            Delta::Full(old) => old.len(),
+            Delta::Sparse(runs) => runs.iter().map(|(_, bytes)| bytes.len() + 16).sum(),
+        }
+    }
+}
+
+pub struct Rewind {
+    pub depth: usize,     // Snapshots kept, older ones are dropped
+    pub granularity: u32, // Frames between two snapshots
+    frames: u32,          // Frames recorded since the last snapshot
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Delta>, // Oldest first
+}
+
+impl Rewind {
+    pub fn new(depth: usize, granularity: u32) -> Self {
+        Self {
+            depth,
+            granularity: granularity.max(1),
+            frames: 0,
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    // Call after running `frames` frames, takes a snapshot every `granularity` frames
+    pub fn record(&mut self, emulator: &Emulator, frames: u64) {
+        if frames == 0 {
+            return;
+        }
+        self.frames = self
+            .frames
+            .saturating_add(frames.min(u32::MAX as u64) as u32);
+        if self.frames < self.granularity && self.latest.is_some() {
+            return;
+        }
+        self.frames = 0;
+
+        let state = emulator.save_state();
+        if let Some(latest) = self.latest.take() {
+            self.deltas.push_back(Delta::between(&state, &latest));
+            while self.deltas.len() >= self.depth.max(1) {
+                self.deltas.pop_front();
+            }
+        }
+        self.latest = Some(state);
+    }
+
+    // Restore the snapshot before the latest one, returns false when the history is used up
+    // The keys held right now stay held, only the game goes back in time
+    pub fn step_back(&mut self, emulator: &mut Emulator) -> bool {
+        let (latest, delta) = match (&self.latest, self.deltas.pop_back()) {
+            (Some(latest), Some(delta)) => (latest, delta),
+            _ => return false,
+        };
+        let state = delta.apply(latest);
+
+        let keypad = emulator.keypad;
+        if emulator.load_state(&state).is_err() {
+            self.clear();
+            return false;
+        }
+        emulator.keypad = keypad;
+        emulator.draw_flag = true;
+
+        self.latest = Some(state);
+        self.frames = 0;
+        true
+    }
+
+    // Number of snapshots that can be stepped back to
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    // Bytes used by the history
+    pub fn memory_usage(&self) -> usize {
+        let latest = self.latest.as_ref().map_or(0, |state| state.len());
+        latest + self.deltas.iter().map(Delta::size).sum::<usize>()
+    }
+
+    // Forget the history, e.g. after loading a save state
+    pub fn clear(&mut self) {
+        self.frames = 0;
+        self.latest = None;
+        self.deltas.clear();
+    }
+}
@@ -0,0 +1,253 @@
+// Save states: a snapshot of the whole emulator in a small versioned binary format
+//
+// Layout, all integers little endian:
+//   "C8ST" magic, u16 format version, u64 hash of the loaded ROM,
+//   then every field of `Emulator` in the order written by `Emulator::save_state`.
+use std::time::Duration;
+
+use crate::constants::*;
+use crate::cpu::{Emulator, OutOfBounds};
+use crate::errors::Chip8Error;
+use crate::framebuffer::FrameBuffer;
+use crate::quirks::Quirks;
+
+pub const SAVE_STATE_MAGIC: [u8; 4] = *b"C8ST";
+pub const SAVE_STATE_VERSION: u16 = 3;
+
+// Appends fields to a save state buffer
+struct StateWriter {
+    buf: Vec<u8>,
+}
+
+impl StateWriter {
+    fn u8(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    fn bool(&mut self, val: bool) {
+        self.buf.push(val as u8);
+    }
+
+    fn u16(&mut self, val: u16) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn u32(&mut self, val: u32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn u64(&mut self, val: u64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    // A length prefixed byte string
+    fn bytes(&mut self, val: &[u8]) {
+        self.u32(val.len() as u32);
+        self.buf.extend_from_slice(val);
+    }
+}
+
+// Reads fields back out of a save state buffer
+struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Chip8Error> {
+        if self.data.len() - self.pos < len {
+            return Err(Chip8Error::SaveStateError(
+                "unexpected end of data".to_string(),
+            ));
+        }
+        let slice = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Chip8Error> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, Chip8Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, Chip8Error> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u16(&mut self) -> Result<u16, Chip8Error> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Chip8Error> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, Chip8Error> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], Chip8Error> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+impl Emulator {
+    // Snapshot the full emulator state
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter { buf: Vec::new() };
+        w.buf.extend_from_slice(&SAVE_STATE_MAGIC);
+        w.u16(SAVE_STATE_VERSION);
+        w.u64(self.rom_hash);
+
+        w.u8(self.quirks.to_bits());
+        w.bytes(&self.memory);
+        w.buf.extend_from_slice(&self.v);
+        w.u16(self.i);
+        w.u16(self.pc);
+        self.stack.iter().for_each(|&addr| w.u16(addr));
+        w.u8(self.sp);
+        w.u8(self.dt);
+        w.u8(self.st);
+        w.bool(self.draw_flag);
+        w.u16(self.screen.width as u16);
+        w.u16(self.screen.height as u16);
+        w.bytes(&self.screen.pixels);
+        self.keypad.iter().for_each(|&key| w.bool(key));
+        w.buf.extend_from_slice(&self.rpl);
+        w.bool(self.halted);
+        w.u8(self.planes);
+        w.buf.extend_from_slice(&self.audio_pattern);
+        w.u8(self.pitch);
+        w.u32(self.instructions_per_frame);
+        w.u8(match self.out_of_bounds {
+            OutOfBounds::Error => 0,
+            OutOfBounds::Wrap => 1,
+        });
+        w.u64(self.cycles);
+        w.u64(self.frames);
+        w.u64(self.frame_time.as_nanos() as u64);
+        w.bool(self.vblank);
+        w.bytes(&self.rng.save());
+
+        w.buf
+    }
+
+    // Restore a snapshot taken by `save_state`, the same ROM has to be loaded.
+    // The state's quirks, speed and memory wrapping replace the emulator's, so the game
+    // carries on the way it was saved whatever the emulator was set up with.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        let mut r = StateReader { data, pos: 0 };
+        if r.array::<4>()? != SAVE_STATE_MAGIC {
+            return Err(Chip8Error::SaveStateError("not a save state".to_string()));
+        }
+        let version = r.u16()?;
+        if version != SAVE_STATE_VERSION {
+            return Err(Chip8Error::SaveStateError(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+        let rom_hash = r.u64()?;
+        if rom_hash != self.rom_hash {
+            return Err(Chip8Error::RomMismatch(rom_hash, self.rom_hash));
+        }
+
+        // Read everything before touching the emulator, so a bad state leaves it untouched
+        let quirks = Quirks::from_bits(r.u8()?);
+        let memory = r.bytes()?.to_vec();
+        let v = r.array()?;
+        let i = r.u16()?;
+        let pc = r.u16()?;
+        let mut stack = [0; STACK_SIZE];
+        for addr in stack.iter_mut() {
+            *addr = r.u16()?;
+        }
+        let sp = r.u8()?;
+        let dt = r.u8()?;
+        let st = r.u8()?;
+        let draw_flag = r.bool()?;
+        let width = r.u16()? as usize;
+        let height = r.u16()? as usize;
+        let pixels = r.bytes()?.to_vec();
+        let mut keypad = [false; NUM_KEYS];
+        for key in keypad.iter_mut() {
+            *key = r.bool()?;
+        }
+        let rpl = r.array()?;
+        let halted = r.bool()?;
+        let planes = r.u8()?;
+        let audio_pattern = r.array()?;
+        let pitch = r.u8()?;
+        let instructions_per_frame = r.u32()?;
+        let out_of_bounds = match r.u8()? {
+            0 => OutOfBounds::Error,
+            1 => OutOfBounds::Wrap,
+            _ => return Err(Chip8Error::SaveStateError("corrupt data".to_string())),
+        };
+        let cycles = r.u64()?;
+        let frames = r.u64()?;
+        let frame_time = Duration::from_nanos(r.u64()?);
+        let vblank = r.bool()?;
+        let rng = r.bytes()?;
+
+        // Only the low and high resolution screens exist, and only XO-CHIP has more planes
+        let resolution = (width as u32, height as u32);
+        let max_planes = match quirks.xo_chip {
+            true => (1 << NUM_PLANES) - 1,
+            false => 1,
+        };
+        if memory.len() != Self::memory_size(&quirks)
+            || ![
+                (SCREEN_WIDTH, SCREEN_HEIGHT),
+                (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT),
+            ]
+            .contains(&resolution)
+            || pixels.len() != width * height
+            || pixels.iter().any(|&pixel| pixel > max_planes)
+            || planes > max_planes
+            || sp as usize > STACK_SIZE
+            || !(1..=MAX_INSTRUCTIONS_PER_FRAME).contains(&instructions_per_frame)
+        {
+            return Err(Chip8Error::SaveStateError("corrupt data".to_string()));
+        }
+        // Last check, restoring the generator is the first change to the emulator
+        if !self.rng.load(rng) {
+            return Err(Chip8Error::SaveStateError(
+                "made with another random generator".to_string(),
+            ));
+        }
+
+        self.quirks = quirks;
+        self.memory = memory;
+        self.v = v;
+        self.i = i;
+        self.pc = pc;
+        self.stack = stack;
+        self.sp = sp;
+        self.dt = dt;
+        self.st = st;
+        self.draw_flag = draw_flag;
+        self.screen = FrameBuffer {
+            width,
+            height,
+            pixels,
+        };
+        self.keypad = keypad;
+        self.rpl = rpl;
+        self.halted = halted;
+        self.planes = planes;
+        self.audio_pattern = audio_pattern;
+        self.pitch = pitch;
+        self.instructions_per_frame = instructions_per_frame;
+        self.out_of_bounds = out_of_bounds;
+        self.cycles = cycles;
+        self.frames = frames;
+        self.frame_time = frame_time;
+        self.vblank = vblank;
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+// Symbol maps: names for the addresses of a ROM, so the debugger can show labels
+//
+// Text format, one symbol per line with a hex address:
+//   label 0202 main
+//   breakpoint 0230 check-collision
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::cpu::Address;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolMap {
+    pub labels: BTreeMap<Address, String>, // The first label defined at each address
+    pub breakpoints: BTreeMap<Address, String>, // Breakpoints the program asked for
+}
+
+impl SymbolMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Name an address, an address keeps the first name it gets
+    pub fn add_label(&mut self, addr: Address, name: &str) {
+        self.labels.entry(addr).or_insert_with(|| name.to_string());
+    }
+
+    pub fn add_breakpoint(&mut self, addr: Address, name: &str) {
+        self.breakpoints.insert(addr, name.to_string());
+    }
+
+    // The label at exactly this address
+    pub fn label(&self, addr: Address) -> Option<&str> {
+        self.labels.get(&addr).map(String::as_str)
+    }
+
+    // The address of a label
+    pub fn address(&self, name: &str) -> Option<Address> {
+        self.labels
+            .iter()
+            .find(|(_, label)| label.as_str() == name)
+            .map(|(&addr, _)| addr)
+    }
+
+    // The address relative to the closest label before it, e.g. "main+4"
+    pub fn describe(&self, addr: Address) -> String {
+        match self.labels.range(..=addr).next_back() {
+            Some((&start, name)) if start == addr => name.clone(),
+            Some((&start, name)) => format!("{}+{}", name, addr - start),
+            None => format!("{:#06X}", addr),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        // Writing to a String can't fail
+        for (addr, name) in &self.labels {
+            let _ = writeln!(text, "label {:04X} {}", addr, name);
+        }
+        for (addr, name) in &self.breakpoints {
+            let _ = writeln!(text, "breakpoint {:04X} {}", addr, name);
+        }
+        text
+    }
+
+    // Read the text format back, blank lines are skipped
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut map = SymbolMap::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut words = line.split_whitespace();
+            let kind = words.next()?;
+            let addr = Address::from_str_radix(words.next()?, 16).ok()?;
+            let name = words.next()?;
+            match kind {
+                "label" => map.add_label(addr, name),
+                "breakpoint" => map.add_breakpoint(addr, name),
+                _ => return None,
+            }
+        }
+        Some(map)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a symbol map"))
+    }
+}
@@ -0,0 +1,142 @@
+// Assembler tests: disassembled ROMs assemble back to the same bytes, and the syntax and errors
+use std::fs;
+use std::path::Path;
+
+use chip8_lib::{
+    assembler::{assemble, assemble_file},
+    disassembler::{disassemble_rom, Syntax},
+    drivers::rom_driver::ROM,
+    errors::AsmError,
+};
+
+fn bytes(source: &str) -> Vec<u8> {
+    assemble("test", source)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .data
+}
+
+fn error(source: &str) -> AsmError {
+    match assemble("test", source) {
+        Ok(rom) => panic!("assembled to {:02X?}", rom.data),
+        Err(e) => e,
+    }
+}
+
+// The disassembler's mnemonics for a ROM, data as DB and DW
+fn source(rom: &ROM) -> String {
+    let mut source = String::new();
+    for line in disassemble_rom(rom) {
+        let text = match (&line.instruction, line.bytes.as_slice()) {
+            (Some(instruction), _) => Syntax::Cowgod.format(instruction),
+            (None, &[byte]) => format!("DB {:#04X}", byte),
+            (None, bytes) => format!("DW 0x{:02X}{:02X}", bytes[0], bytes[1]),
+        };
+        source.push_str(&text);
+        source.push('\n');
+    }
+    source
+}
+
+#[test]
+fn disassembled_roms_assemble_to_the_same_bytes() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../roms");
+    let mut roms = 0;
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "ch8") {
+            continue;
+        }
+        let rom = ROM::from_file(&path).unwrap();
+        let assembled = assemble("round trip", &source(&rom))
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert_eq!(assembled.data, rom.data, "{}", path.display());
+        roms += 1;
+    }
+    assert!(roms > 0, "no ROMs in {}", dir.display());
+}
+
+#[test]
+fn labels_resolve_forward_and_backward() {
+    let source = "
+start:  JP end      ; forward
+        LD I, sprite
+loop:   CALL loop   ; on its own line's address
+end:    JP start
+sprite: DB 0xF0
+";
+    assert_eq!(
+        bytes(source),
+        [0x12, 0x06, 0xA2, 0x08, 0x22, 0x04, 0x12, 0x00, 0xF0]
+    );
+}
+
+#[test]
+fn constants_and_expressions() {
+    let source = "
+SPEED EQU 3
+TOP   EQU $10 - 1
+      LD V1, SPEED + 0b10
+      LD V2, TOP
+      LD V3, -1
+      LD I, LONG data + 2
+data: DW SPEED
+";
+    assert_eq!(
+        bytes(source),
+        [0x61, 0x05, 0x62, 0x0F, 0x63, 0xFF, 0xF0, 0x00, 0x02, 0x0C, 0x00, 0x03]
+    );
+}
+
+#[test]
+fn data_directives() {
+    assert_eq!(
+        bytes("db 1, 0x02, \"A;B\" ; strings can hold ; and ,\ndw 0x1234, 5"),
+        [0x01, 0x02, b'A', b';', b'B', 0x12, 0x34, 0x00, 0x05]
+    );
+}
+
+#[test]
+fn include_is_relative_to_the_including_file() {
+    let dir = std::env::temp_dir().join(format!("chip8-asm-test-{}", std::process::id()));
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::write(
+        dir.join("main.asm"),
+        "INCLUDE \"lib/sprites.asm\"\nLD I, sprite\n",
+    )
+    .unwrap();
+    fs::write(dir.join("lib/sprites.asm"), "sprite: DB 0x80\n").unwrap();
+
+    let rom = assemble_file(dir.join("main.asm"));
+    fs::remove_dir_all(&dir).unwrap();
+    let rom = rom.unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!(rom.name, "main");
+    assert_eq!(rom.data, [0x80, 0xA2, 0x00]);
+}
+
+#[test]
+fn errors_point_at_the_line_and_column() {
+    let e = error("CLS\n  FOO V1");
+    assert_eq!((e.line, e.column), (2, 3));
+    assert_eq!(e.message, "unknown instruction `FOO`");
+
+    let e = error("JP nowhere");
+    assert_eq!((e.line, e.column), (1, 4));
+    assert_eq!(e.message, "unknown symbol `nowhere`");
+
+    let e = error("LD V0, 0x100");
+    assert_eq!((e.line, e.column), (1, 8));
+    assert_eq!(e.message, "byte out of range: 256");
+
+    let e = error("DRW V0, V1");
+    assert_eq!(e.message, "invalid operands for `DRW`");
+}
+
+#[test]
+fn symbols_are_checked() {
+    let e = error("here: CLS\nhere: RET");
+    assert_eq!((e.line, e.column), (2, 1));
+    assert_eq!(e.message, "`here` is already defined");
+
+    assert_eq!(error("V1: CLS").message, "invalid symbol name `V1`");
+    assert_eq!(error("DT EQU 1").message, "invalid symbol name `DT`");
+}
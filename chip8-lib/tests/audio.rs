@@ -0,0 +1,148 @@
+// Audio tests: the beeper's envelope and the WAV backend's file format
+use chip8_lib::{
+    constants::{BEEP_RAMP_SECONDS, FRAME_DURATION},
+    cpu::Emulator,
+    drivers::{
+        audio_driver::{Beeper, BeeperConfig, WavAudio, Waveform},
+        rom_driver::ROM,
+    },
+    platform::Audio,
+    quirks::Quirks,
+};
+
+// A small sample rate keeps the numbers readable: 100 samples a frame, a 30 sample ramp
+const SAMPLE_RATE: u32 = 6000;
+const RAMP: usize = (SAMPLE_RATE as f32 * BEEP_RAMP_SECONDS) as usize;
+
+fn config(waveform: Waveform) -> BeeperConfig {
+    BeeperConfig {
+        waveform,
+        frequency: 100.0,
+        volume: 0.5,
+        sample_rate: SAMPLE_RATE,
+    }
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn wav_header_describes_the_samples() {
+    let mut wav = WavAudio::new("unused.wav", config(Waveform::Square));
+    wav.set_playing(true);
+    wav.set_playing(false);
+    assert_eq!(wav.samples.len(), 200);
+
+    let mut bytes = Vec::new();
+    wav.write_wav(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 44 + 400);
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(u32_at(&bytes, 4), 36 + 400);
+    assert_eq!(&bytes[8..12], b"WAVE");
+    assert_eq!(&bytes[12..16], b"fmt ");
+    assert_eq!(u32_at(&bytes, 16), 16); // Format chunk size
+    assert_eq!(u16_at(&bytes, 20), 1); // PCM
+    assert_eq!(u16_at(&bytes, 22), 1); // Mono
+    assert_eq!(u32_at(&bytes, 24), SAMPLE_RATE);
+    assert_eq!(u32_at(&bytes, 28), SAMPLE_RATE * 2); // Bytes per second
+    assert_eq!(u16_at(&bytes, 32), 2); // Bytes per sample
+    assert_eq!(u16_at(&bytes, 34), 16); // Bits per sample
+    assert_eq!(&bytes[36..40], b"data");
+    assert_eq!(u32_at(&bytes, 40), 400);
+    for (n, &sample) in wav.samples.iter().enumerate() {
+        assert_eq!(u16_at(&bytes, 44 + n * 2) as i16, sample);
+    }
+}
+
+#[test]
+fn tone_fades_in_without_a_click() {
+    let mut beeper = Beeper::new(config(Waveform::Square));
+    beeper.playing = true;
+    let samples: Vec<f32> = (0..RAMP * 2).map(|_| beeper.next_sample()).collect();
+
+    // The envelope rises by one step a sample, reaching full volume after the ramp
+    let step = 0.5 / RAMP as f32;
+    for (n, &sample) in samples.iter().enumerate().take(RAMP) {
+        assert!(
+            sample.abs() <= step * (n + 1) as f32 + 1e-6,
+            "{}: {}",
+            n,
+            sample
+        );
+    }
+    for &sample in &samples[RAMP + 1..] {
+        assert!((sample.abs() - 0.5).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn tone_fades_out_without_a_click() {
+    let mut beeper = Beeper::new(config(Waveform::Sine));
+    beeper.playing = true;
+    let mut last = 0.0;
+    for _ in 0..RAMP * 4 {
+        last = beeper.next_sample();
+    }
+
+    beeper.playing = false;
+    let samples: Vec<f32> = (0..RAMP * 2).map(|_| beeper.next_sample()).collect();
+    // Neighbouring samples stay as close as the sine wave itself keeps them
+    let max_jump =
+        0.5 * 2.0 * std::f32::consts::PI * 100.0 / SAMPLE_RATE as f32 + 0.5 / RAMP as f32;
+    for &sample in &samples {
+        assert!((sample - last).abs() <= max_jump + 1e-6);
+        last = sample;
+    }
+    // And silence once the ramp is over
+    assert!(samples[RAMP + 1..].iter().all(|&sample| sample == 0.0));
+}
+
+#[test]
+fn silent_while_the_sound_timer_is_zero() {
+    // V0 = 3, ST = V0, loop
+    let program = [0x60, 0x03, 0xF0, 0x18, 0x12, 0x04];
+    let mut emulator = Emulator::new(Quirks::default());
+    emulator
+        .load_rom(ROM::from_bytes(&program, "beep").unwrap())
+        .unwrap();
+    let mut wav = WavAudio::new("unused.wav", config(Waveform::Square));
+
+    // One frame of silence before the ROM starts the sound
+    wav.set_playing(emulator.st > 0);
+    for _ in 0..6 {
+        emulator.run_for(FRAME_DURATION).unwrap();
+        wav.set_playing(emulator.st > 0);
+    }
+
+    let frames: Vec<&[i16]> = wav.samples.chunks(100).collect();
+    assert_eq!(frames.len(), 7);
+    assert!(frames[0].iter().all(|&sample| sample == 0));
+    // ST is 2 and 1 after the first two frames
+    for frame in &frames[1..3] {
+        assert!(frame.iter().any(|&sample| sample != 0));
+    }
+    // It reaches 0 after the third, which fades out and then stays silent
+    assert!(frames[3][..RAMP].iter().any(|&sample| sample != 0));
+    assert!(frames[3][RAMP + 1..].iter().all(|&sample| sample == 0));
+    assert!(frames[4..]
+        .iter()
+        .all(|frame| frame.iter().all(|&s| s == 0)));
+}
+
+#[test]
+fn pattern_plays_instead_of_the_tone() {
+    let mut beeper = Beeper::new(config(Waveform::Sine));
+    beeper.playing = true;
+    // All ones is a constant full-volume sample once the envelope is up
+    beeper.pattern = Some([0xFF; 16]);
+    let samples: Vec<f32> = (0..RAMP * 2).map(|_| beeper.next_sample()).collect();
+    assert!(samples[RAMP..].iter().all(|&sample| sample == 0.5));
+
+    beeper.pattern = Some([0x00; 16]);
+    assert_eq!(beeper.next_sample(), -0.5);
+}
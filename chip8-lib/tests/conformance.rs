@@ -0,0 +1,253 @@
+// Conformance tests: the test programs in tests/roms run headlessly under every quirks preset,
+// and their final screens are checked against results worked out by hand.
+//
+// tests/roms/ibm-logo.ch8 is the public domain IBM logo ROM, checked against a reference
+// screen of the logo it is known to draw. The opcode, flags, quirks and keypad test ROMs
+// aren't checked in; Octo programs that check the same things stand in for them:
+//   logo      compared to reference screens drawn from its sprites, clipped or wrapped
+//   opcodes   every check must draw a tick, never a cross
+//   flags     likewise
+//   quirks    the digit for each quirk must be the one the preset's quirks call for
+//   keypad    the digits must name the keys the input script presses
+// The expected results are written here and in tests/golden by hand, never by this suite.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chip8_lib::{
+    constants::FONT_SET,
+    cpu::Emulator,
+    drivers::rom_driver::ROM,
+    headless::{InputScript, Limit, Outcome, Runner, ScreenFormat},
+    octo,
+    platform::InputEvent,
+    quirks::{Quirks, PRESET_NAMES},
+};
+
+const FRAMES: u64 = 60; // Every test program finishes well within a second
+
+// The opcode and flags programs' marks, drawn on an 8x6 grid
+const MARK_OK: [u8; 5] = [0x02, 0x04, 0x88, 0x50, 0x20];
+const MARK_FAIL: [u8; 5] = [0x88, 0x50, 0x20, 0x50, 0x88];
+
+fn test_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+// Run a test program to its final screen, in the ASCII screen format
+fn run(program: &str, preset: &str, script: &InputScript) -> String {
+    let path = test_dir().join("roms").join(format!("{}.8o", program));
+    let compiled = octo::compile_file(&path).unwrap_or_else(|e| panic!("{}", e));
+    run_rom(compiled.rom, program, preset, script)
+}
+
+fn run_rom(rom: ROM, program: &str, preset: &str, script: &InputScript) -> String {
+    let mut emulator = Emulator::new(Quirks::preset(preset).unwrap());
+    emulator.load_rom(rom).unwrap();
+    let mut runner = Runner::new(emulator, script.clone());
+    match runner.run(Limit::Frames(FRAMES)) {
+        Outcome::Finished => {}
+        outcome => panic!("{} ({}) stopped early: {:?}", program, preset, outcome),
+    }
+
+    let screen = ScreenFormat::Ascii.encode(&runner.emulator.screen);
+    String::from_utf8(screen).unwrap()
+}
+
+// The 5 rows of 8 pixels at (x, y) of an ASCII screen, as sprite bytes
+fn sprite_at(screen: &str, x: usize, y: usize) -> [u8; 5] {
+    let rows: Vec<&[u8]> = screen.lines().map(str::as_bytes).collect();
+    let mut sprite = [0; 5];
+    for (row, byte) in sprite.iter_mut().enumerate() {
+        for bit in 0..8 {
+            if rows[y + row]
+                .get(x + bit)
+                .is_some_and(|&pixel| pixel != b'.')
+            {
+                *byte |= 0x80 >> bit;
+            }
+        }
+    }
+    sprite
+}
+
+// The opcode and flags programs' results, cell by cell until the first empty one:
+// 'v' for a tick, 'x' for a cross, '?' for anything else
+fn marks(screen: &str) -> String {
+    let mut marks = String::new();
+    for cell in 0..8 * 5 {
+        let sprite = sprite_at(screen, cell % 8 * 8, cell / 8 * 6);
+        marks.push(match sprite {
+            MARK_OK => 'v',
+            MARK_FAIL => 'x',
+            [0, 0, 0, 0, 0] => break,
+            _ => '?',
+        });
+    }
+    marks
+}
+
+// The `count` digits the quirks and keypad programs show from (4, 4), 6 pixels apart,
+// '?' for anything that isn't a font digit
+fn digits(screen: &str, count: usize) -> String {
+    (0..count)
+        .map(|n| {
+            let glyph = sprite_at(screen, 4 + n * 6, 4).map(|byte| byte & 0xF0);
+            FONT_SET
+                .chunks(5)
+                .position(|digit| digit == glyph)
+                .map_or('?', |digit| char::from_digit(digit as u32, 16).unwrap())
+        })
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+// Overlay two ASCII screens: '#' lit in both, '+' only in the actual screen, '-' only in
+// the reference one
+fn visual_diff(reference: &str, actual: &str) -> String {
+    let mut diff = String::new();
+    for (reference_row, actual_row) in reference.lines().zip(actual.lines()) {
+        for (expected, got) in reference_row.chars().zip(actual_row.chars()) {
+            diff.push(match (expected != '.', got != '.') {
+                (true, true) => '#',
+                (false, true) => '+',
+                (true, false) => '-',
+                (false, false) => '.',
+            });
+        }
+        diff.push('\n');
+    }
+    diff
+}
+
+// Check a program's screen under every preset against the result `expected` gives for the
+// preset's quirks
+fn check<F, E>(program: &str, script: InputScript, result: F, expected: E)
+where
+    F: Fn(&str) -> String,
+    E: Fn(&Quirks) -> String,
+{
+    let mut failures = Vec::new();
+    for preset in PRESET_NAMES {
+        let screen = run(program, preset, &script);
+        let (actual, expected) = (result(&screen), expected(&Quirks::preset(preset).unwrap()));
+        if actual != expected {
+            failures.push(format!(
+                "{} with the {} preset shows {}, expected {}\n{}",
+                program, preset, actual, expected, screen
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+// Compare a screen with a reference screen in tests/golden
+fn check_reference(program: &str, preset: &str, actual: &str, reference: &str) {
+    let reference_path = test_dir().join("golden").join(reference);
+    let reference = fs::read_to_string(&reference_path).unwrap();
+    assert!(
+        actual == reference,
+        "{} with the {} preset doesn't match {}\n\
+         ('+' lit but blank in the reference, '-' blank but lit in the reference)\n{}",
+        program,
+        preset,
+        reference_path.display(),
+        visual_diff(&reference, actual)
+    );
+}
+
+// The real ROM draws the same logo with every preset
+#[test]
+fn ibm_logo_rom() {
+    let rom_path = test_dir().join("roms").join("ibm-logo.ch8");
+    for preset in PRESET_NAMES {
+        let rom = ROM::from_file(&rom_path).unwrap();
+        let actual = run_rom(rom, "ibm-logo", preset, &InputScript::new());
+        check_reference("ibm-logo", preset, &actual, "ibm-logo-reference.txt");
+    }
+}
+
+// The banner, and a bar along row 20 from column 4 to the right edge. Wrapped, the bar's
+// last sprite carries on over columns 0 - 3.
+#[test]
+fn logo() {
+    for preset in PRESET_NAMES {
+        let actual = run("logo", preset, &InputScript::new());
+        let reference = match Quirks::preset(preset).unwrap().clip_sprites {
+            true => "logo-clipped-reference.txt",
+            false => "logo-wrapped-reference.txt",
+        };
+        check_reference("logo", preset, &actual, reference);
+    }
+}
+
+// 24 checks, all of which hold under every preset
+#[test]
+fn opcodes() {
+    check("opcodes", InputScript::new(), marks, |_| "v".repeat(24));
+}
+
+// 17 checks, all of which hold under every preset
+#[test]
+fn flags() {
+    check("flags", InputScript::new(), marks, |_| "v".repeat(17));
+}
+
+// The digits for vF reset, shift, memory, jump, clipping and display wait, as listed at
+// the top of quirks.8o
+#[test]
+fn quirks() {
+    let expected = |quirks: &Quirks| {
+        [
+            if quirks.vf_reset { '0' } else { '5' },
+            if quirks.shift_uses_vy { '2' } else { '0' },
+            match (quirks.memory_increments_i, quirks.memory_increments_i_by_x) {
+                (true, false) => '9',
+                (true, true) => '8',
+                (false, _) => '7',
+            },
+            if quirks.jump_uses_vx { '2' } else { '0' },
+            if quirks.clip_sprites { '0' } else { '1' },
+            if quirks.display_wait { '1' } else { '0' },
+        ]
+        .iter()
+        .collect()
+    };
+    check(
+        "quirks",
+        InputScript::new(),
+        |screen| digits(screen, 6),
+        expected,
+    );
+
+    // The presets, spelled out
+    for (preset, digits) in [
+        ("default", "507010"),
+        ("vip", "029001"),
+        ("chip48", "508200"),
+        ("schip", "507200"),
+        ("xochip", "529010"),
+    ] {
+        assert_eq!(
+            expected(&Quirks::preset(preset).unwrap()),
+            digits,
+            "{}",
+            preset
+        );
+    }
+}
+
+// 7 is the first key pressed, then A is held and released
+#[test]
+fn keypad() {
+    let mut script = InputScript::new();
+    script.add(10, InputEvent::KeyDown(0x7));
+    script.add(14, InputEvent::KeyUp(0x7));
+    script.add(30, InputEvent::KeyDown(0xA));
+    script.add(40, InputEvent::KeyUp(0xA));
+    check(
+        "keypad",
+        script,
+        |screen| digits(screen, 3),
+        |_| "7AF".to_string(),
+    );
+}
@@ -0,0 +1,307 @@
+// Debugger tests: breakpoints, stepping, frames stopped partway and crash reports, on small
+// hand-assembled programs
+use std::time::Duration;
+
+use chip8_lib::{
+    constants::FRAME_DURATION,
+    cpu::Emulator,
+    debugger::{Debugger, StopReason},
+    drivers::rom_driver::ROM,
+    errors::{Chip8Error, FaultKind},
+    quirks::Quirks,
+    symbols::SymbolMap,
+};
+
+fn emulator(program: &[u8], instructions_per_frame: u32) -> Emulator {
+    let mut emulator = Emulator::new(Quirks::superchip());
+    emulator.instructions_per_frame = instructions_per_frame;
+    emulator
+        .load_rom(ROM::from_bytes(program, "debug").unwrap())
+        .unwrap();
+    emulator
+}
+
+// Counts up in V0 forever
+const COUNTER: [u8; 4] = [
+    0x70, 0x01, // 200: V0 += 1
+    0x12, 0x00, // 202: Jump 200
+];
+
+// Calls a subroutine, which calls another one
+const CALLS: [u8; 18] = [
+    0x22, 0x06, // 200: Call 206
+    0x60, 0x01, // 202: V0 = 1
+    0x12, 0x04, // 204: Jump 204
+    0x61, 0x01, // 206: V1 = 1
+    0x22, 0x0E, // 208: Call 20E
+    0x62, 0x01, // 20A: V2 = 1
+    0x00, 0xEE, // 20C: Return
+    0x63, 0x01, // 20E: V3 = 1
+    0x00, 0xEE, // 210: Return
+];
+
+#[test]
+fn breakpoint_stops_before_its_instruction() {
+    let mut emulator = emulator(&COUNTER, 10);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.add_breakpoint(0x202);
+
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::Breakpoint(0x202))
+    );
+    assert!(debugger.is_paused());
+    assert_eq!((debugger.emulator.pc, debugger.emulator.v[0]), (0x202, 1));
+
+    // Paused, nothing runs
+    assert_eq!(debugger.run_frame().unwrap(), None);
+    assert_eq!(debugger.run_for(FRAME_DURATION * 5).unwrap(), None);
+    assert_eq!(debugger.emulator.cycles, 1);
+}
+
+#[test]
+fn resume_skips_the_breakpoint_it_stopped_at_once() {
+    let mut emulator = emulator(&COUNTER, 10);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.add_breakpoint(0x202);
+    debugger.run_frame().unwrap();
+
+    // Runs the jump it stopped on, then stops there again on the next time round
+    debugger.resume();
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::Breakpoint(0x202))
+    );
+    assert_eq!(debugger.emulator.v[0], 2);
+    assert_eq!(debugger.emulator.cycles, 3);
+
+    // Without the breakpoint it runs on
+    debugger.remove_breakpoint(0x202);
+    debugger.resume();
+    assert_eq!(debugger.run_frame().unwrap(), None);
+    assert!(!debugger.is_paused());
+}
+
+#[test]
+fn step_runs_one_instruction() {
+    let mut emulator = emulator(&COUNTER, 10);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.add_breakpoint(0x200);
+
+    // A step runs the instruction under a breakpoint
+    assert_eq!(debugger.step().unwrap(), StopReason::Stepped);
+    assert!(debugger.is_paused());
+    assert_eq!((debugger.emulator.pc, debugger.emulator.v[0]), (0x202, 1));
+    assert_eq!(debugger.step().unwrap(), StopReason::Stepped);
+    assert_eq!(debugger.emulator.pc, 0x200);
+}
+
+#[test]
+fn step_over_runs_a_whole_call() {
+    let mut emulator = emulator(&CALLS, 100);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.pause();
+
+    // A call runs on until the instruction after it
+    assert_eq!(debugger.step_over().unwrap(), None);
+    assert!(!debugger.is_paused());
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::TargetReached)
+    );
+    assert_eq!(debugger.emulator.pc, 0x202);
+    assert_eq!(debugger.emulator.v[1..4], [1, 1, 1]);
+    assert_eq!(debugger.emulator.sp, 0);
+
+    // Anything else is a step
+    assert_eq!(debugger.step_over().unwrap(), Some(StopReason::Stepped));
+    assert_eq!(debugger.emulator.v[0], 1);
+}
+
+#[test]
+fn step_over_stops_at_a_breakpoint_inside_the_call() {
+    let mut emulator = emulator(&CALLS, 100);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.add_breakpoint(0x20A);
+    debugger.pause();
+    debugger.step_over().unwrap();
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::Breakpoint(0x20A))
+    );
+    // The step over is cancelled by the stop
+    debugger.resume();
+    assert_eq!(debugger.run_frame().unwrap(), None);
+    assert_eq!(debugger.emulator.pc, 0x204);
+}
+
+#[test]
+fn step_out_stops_when_the_stack_drops_below_its_depth() {
+    let mut emulator = emulator(&CALLS, 100);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.add_breakpoint(0x20E);
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::Breakpoint(0x20E))
+    );
+    assert_eq!(debugger.emulator.sp, 2);
+
+    // Out of the inner subroutine, back in the outer one
+    assert_eq!(debugger.step_out().unwrap(), None);
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::TargetReached)
+    );
+    assert_eq!((debugger.emulator.pc, debugger.emulator.sp), (0x20A, 1));
+
+    // Out of the outer one
+    debugger.step_out().unwrap();
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::TargetReached)
+    );
+    assert_eq!((debugger.emulator.pc, debugger.emulator.sp), (0x202, 0));
+
+    // Outside of any subroutine it is a step
+    assert_eq!(debugger.step_out().unwrap(), Some(StopReason::Stepped));
+    assert_eq!(debugger.emulator.pc, 0x204);
+}
+
+#[test]
+fn run_to_stops_at_the_address() {
+    let mut emulator = emulator(&CALLS, 100);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.pause();
+    debugger.run_to(0x20C);
+    assert!(!debugger.is_paused());
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::TargetReached)
+    );
+    assert_eq!(debugger.emulator.pc, 0x20C);
+    assert_eq!(debugger.emulator.v[1..4], [1, 1, 1]);
+}
+
+#[test]
+fn frame_stopped_partway_is_finished_later() {
+    // 4 instructions a frame, stopping after the first one
+    let mut emulator = emulator(&COUNTER, 4);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.add_breakpoint(0x202);
+    debugger.run_frame().unwrap();
+    assert_eq!((debugger.emulator.cycles, debugger.emulator.frames), (1, 0));
+
+    // Resuming runs the frame's other 3 instructions, then the timers
+    debugger.remove_breakpoint(0x202);
+    debugger.resume();
+    assert_eq!(debugger.run_frame().unwrap(), None);
+    assert_eq!((debugger.emulator.cycles, debugger.emulator.frames), (4, 1));
+    debugger.run_frame().unwrap();
+    assert_eq!((debugger.emulator.cycles, debugger.emulator.frames), (8, 2));
+
+    // Steps count towards the frame too
+    debugger.pause();
+    for _ in 0..3 {
+        debugger.step().unwrap();
+    }
+    assert_eq!(debugger.emulator.frames, 2);
+    debugger.step().unwrap();
+    assert_eq!(
+        (debugger.emulator.cycles, debugger.emulator.frames),
+        (12, 3)
+    );
+}
+
+#[test]
+fn stop_throws_away_the_rest_of_the_time() {
+    let mut emulator = emulator(&COUNTER, 4);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.add_breakpoint(0x202);
+    assert_eq!(
+        debugger.run_for(FRAME_DURATION * 10).unwrap(),
+        Some(StopReason::Breakpoint(0x202))
+    );
+    debugger.remove_breakpoint(0x202);
+    debugger.resume();
+    // Only the new time runs: the stopped frame's rest, then one more frame
+    debugger.run_for(FRAME_DURATION * 2).unwrap();
+    assert_eq!((debugger.emulator.cycles, debugger.emulator.frames), (8, 2));
+    debugger.run_for(Duration::ZERO).unwrap();
+    assert_eq!(debugger.emulator.frames, 2);
+}
+
+#[test]
+fn exit_stops_as_halted() {
+    let mut emulator = emulator(&[0x60, 0x01, 0x00, 0xFD], 10);
+    let mut debugger = Debugger::new(&mut emulator);
+    assert_eq!(debugger.run_frame().unwrap(), Some(StopReason::Halted));
+    assert!(debugger.is_paused());
+}
+
+#[test]
+fn symbol_map_breakpoints_are_added() {
+    let mut symbols = SymbolMap::new();
+    symbols.add_label(0x202, "loop");
+    symbols.add_breakpoint(0x202, "loop");
+    let mut emulator = emulator(&COUNTER, 10);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.set_symbols(symbols);
+    assert_eq!(debugger.breakpoints().collect::<Vec<_>>(), [&0x202]);
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::Breakpoint(0x202))
+    );
+}
+
+#[test]
+fn fault_pauses_on_the_failed_instruction() {
+    let program = [0x6A, 0x42, 0x22, 0x06, 0x12, 0x04, 0xFF, 0xFF];
+    let mut emulator = emulator(&program, 10);
+    let mut debugger = Debugger::new(&mut emulator);
+    let fault = match debugger.run_frame() {
+        Err(Chip8Error::Fault(fault)) => fault,
+        result => panic!("{:?}", result),
+    };
+    assert_eq!(fault.kind, FaultKind::InvalidInstruction);
+    assert!(debugger.is_paused());
+    assert_eq!(debugger.emulator.pc, 0x206);
+}
+
+#[test]
+fn crash_report_shows_registers_stack_and_code() {
+    // VA = 42, call a subroutine holding an invalid opcode
+    let program = [0x6A, 0x42, 0x22, 0x06, 0x12, 0x04, 0xFF, 0xFF, 0x00, 0xEE];
+    let mut emulator = emulator(&program, 10);
+    let mut debugger = Debugger::new(&mut emulator);
+    let mut symbols = SymbolMap::new();
+    symbols.add_label(0x200, "main");
+    symbols.add_label(0x206, "broken");
+    debugger.set_symbols(symbols);
+    let fault = match debugger.run_frame() {
+        Err(Chip8Error::Fault(fault)) => fault,
+        result => panic!("{:?}", result),
+    };
+
+    let expected = "\
+Crash: Invalid Instruction @ PC: 0x0206 [FFFF] after 2 cycles
+  in broken
+Registers:
+  V0-V7: 00 00 00 00 00 00 00 00
+  V8-VF: 00 00 42 00 00 00 00 00
+  I=0000 DT=00 ST=00
+Stack (1): main+4
+Code:
+     01FE: 0000      DW 0x0000
+     main:
+     0200: 6A42      LD VA, 0x42
+     0202: 2206      CALL 0x206
+     0204: 1204      JP 0x204
+     broken:
+  => 0206: FFFF      DW 0xFFFF
+     0208: 00EE      RET
+     020A: 0000      DW 0x0000
+     020C: 0000      DW 0x0000
+     020E: 0000      DW 0x0000
+";
+    assert_eq!(debugger.crash_report(&fault), expected);
+}
@@ -0,0 +1,106 @@
+// Disassembler tests: listings of a small ROM mixing code and data, in both syntaxes
+use chip8_lib::{
+    disassembler::{disassemble, disassemble_rom, listing, Line, Syntax},
+    drivers::rom_driver::ROM,
+};
+
+// Code, then a sprite and an odd byte of data
+const PROGRAM: [u8; 23] = [
+    0x00, 0xE0, // 200: Clear
+    0x6A, 0x12, // 202: VA = 0x12
+    0xF0, 0x00, 0x12, 0x34, // 204: I = 1234, long
+    0xDA, 0xB5, // 208: Draw at (VA, VB)
+    0x22, 0x0E, // 20A: Call 20E
+    0x12, 0x0C, // 20C: Jump 20C
+    0x00, 0xEE, // 20E: Return
+    0xFF, 0xFF, 0x3C, 0x7E, // 210: Sprite
+    0xF0, 0x00, // 214: The first half of a long load, cut short by the data
+    0x81, // 216: Odd byte
+];
+
+fn rom_listing(syntax: Syntax) -> String {
+    let rom = ROM::from_bytes(&PROGRAM, "listing").unwrap();
+    listing(&disassemble_rom(&rom), syntax)
+}
+
+#[test]
+fn cowgod_listing() {
+    // A linear sweep can't tell data from code: 3C7E in the sprite is a skip
+    let expected = "\
+0200: 00E0      CLS
+0202: 6A12      LD VA, 0x12
+0204: F0001234  LD I, LONG 0x1234
+0208: DAB5      DRW VA, VB, 5
+020A: 220E      CALL 0x20E
+020C: 120C      JP 0x20C
+020E: 00EE      RET
+0210: FFFF      DW 0xFFFF
+0212: 3C7E      SE VC, 0x7E
+0214: F000      DW 0xF000
+0216: 81        DB 0x81
+";
+    assert_eq!(rom_listing(Syntax::Cowgod), expected);
+}
+
+#[test]
+fn octo_listing() {
+    let expected = "\
+0200: 00E0      clear
+0202: 6A12      va := 0x12
+0204: F0001234  i := long 0x1234
+0208: DAB5      sprite va vb 5
+020A: 220E      :call 0x20E
+020C: 120C      jump 0x20C
+020E: 00EE      return
+0210: FFFF      0xFF 0xFF
+0212: 3C7E      if vc != 0x7E then
+0214: F000      0xF0 0x00
+0216: 81        0x81
+";
+    assert_eq!(rom_listing(Syntax::Octo), expected);
+}
+
+#[test]
+fn long_load_takes_four_bytes() {
+    let lines = disassemble(&[0xF0, 0x00, 0x12, 0x34, 0x00, 0xE0], 0x300);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].bytes, [0xF0, 0x00, 0x12, 0x34]);
+    // The address word isn't decoded on its own
+    assert_eq!(lines[1].addr, 0x304);
+    assert_eq!(lines[1].to_string(), "0304: 00E0      CLS");
+}
+
+#[test]
+fn data_keeps_the_sweep_aligned() {
+    // A long load without its address word, then an odd byte: both are data
+    let lines = disassemble(&[0xF0, 0x00, 0x12], 0x300);
+    assert_eq!(
+        lines,
+        [
+            Line {
+                addr: 0x300,
+                bytes: vec![0xF0, 0x00],
+                instruction: None,
+            },
+            Line {
+                addr: 0x302,
+                bytes: vec![0x12],
+                instruction: None,
+            },
+        ]
+    );
+    // Listing part of a ROM from an odd address decodes other words
+    let rom = ROM::from_bytes(&PROGRAM, "listing").unwrap();
+    let lines = disassemble(&rom.data[1..5], 0x201);
+    assert_eq!(
+        listing(&lines, Syntax::Cowgod),
+        "0201: E06A      DW 0xE06A\n0203: 12F0      JP 0x2F0\n"
+    );
+}
+
+#[test]
+fn syntax_names() {
+    assert_eq!(Syntax::from_name("cowgod"), Some(Syntax::Cowgod));
+    assert_eq!(Syntax::from_name("Octo"), Some(Syntax::Octo));
+    assert_eq!(Syntax::from_name("intel"), None);
+}
@@ -0,0 +1,158 @@
+// Fault tests: a failed instruction is reported with its address, opcode and cycle, and the
+// emulator is left on it
+use chip8_lib::{
+    cpu::{Emulator, Instruction},
+    debugger::Debugger,
+    drivers::rom_driver::ROM,
+    errors::{Chip8Error, Fault, FaultKind},
+    quirks::Quirks,
+};
+
+fn emulator(program: &[u8], quirks: Quirks) -> Emulator {
+    let mut emulator = Emulator::new(quirks);
+    emulator
+        .load_rom(ROM::from_bytes(program, "fault").unwrap())
+        .unwrap();
+    emulator
+}
+
+// Tick until the program faults
+fn run_to_fault(emulator: &mut Emulator) -> Fault {
+    for _ in 0..1000 {
+        match emulator.tick() {
+            Ok(()) => {}
+            Err(Chip8Error::Fault(fault)) => return fault,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    panic!("no fault");
+}
+
+#[test]
+fn invalid_instruction() {
+    let program = [
+        0x60, 0x01, // 200: V0 = 1
+        0x61, 0x02, // 202: V1 = 2
+        0xFF, 0xFF, // 204: Not an instruction
+    ];
+    let mut emulator = emulator(&program, Quirks::superchip());
+    let fault = run_to_fault(&mut emulator);
+    assert_eq!(
+        fault,
+        Fault {
+            kind: FaultKind::InvalidInstruction,
+            pc: 0x204,
+            opcode: 0xFFFF,
+            instruction: None,
+            cycle: 2,
+        }
+    );
+    assert_eq!(
+        fault.to_string(),
+        "Invalid Instruction @ PC: 0x0204 [FFFF] after 2 cycles"
+    );
+
+    // PC stays on the failed instruction, which isn't counted, and fails again
+    assert_eq!((emulator.pc, emulator.cycles), (0x204, 2));
+    assert_eq!(run_to_fault(&mut emulator), fault);
+}
+
+#[test]
+fn xo_chip_instruction_outside_xo_chip() {
+    let mut emulator = emulator(&[0xF0, 0x00, 0x12, 0x34], Quirks::superchip());
+    let fault = run_to_fault(&mut emulator);
+    assert_eq!(fault.kind, FaultKind::InvalidInstruction);
+    assert_eq!((fault.opcode, fault.instruction), (0xF000, None));
+    assert_eq!(
+        fault.to_string(),
+        "Invalid Instruction @ PC: 0x0200 [F000] after 0 cycles"
+    );
+}
+
+#[test]
+fn stack_underflow() {
+    let mut emulator = emulator(&[0x00, 0xEE], Quirks::superchip());
+    let fault = run_to_fault(&mut emulator);
+    assert_eq!(fault.kind, FaultKind::StackUnderflow);
+    assert_eq!(fault.instruction, Some(Instruction::Return));
+    assert_eq!(
+        fault.to_string(),
+        "Stack Underflow @ PC: 0x0200 [00EE RET] after 0 cycles"
+    );
+}
+
+#[test]
+fn stack_overflow() {
+    // A subroutine calling itself fills the 16 entries of the stack
+    let mut emulator = emulator(&[0x22, 0x00], Quirks::superchip());
+    let fault = run_to_fault(&mut emulator);
+    assert_eq!(fault.kind, FaultKind::StackOverflow);
+    assert_eq!((fault.pc, fault.cycle), (0x200, 16));
+    assert_eq!(fault.instruction, Some(Instruction::Call(0x200)));
+    assert_eq!(emulator.sp, 16);
+    assert_eq!(
+        fault.to_string(),
+        "Stack Overflow @ PC: 0x0200 [2200 CALL 0x200] after 16 cycles"
+    );
+}
+
+#[test]
+fn invalid_register() {
+    let mut emulator = emulator(&[0x60, 0x01, 0xF8, 0x75], Quirks::superchip());
+    let fault = run_to_fault(&mut emulator);
+    assert_eq!(fault.kind, FaultKind::InvalidRegister(8));
+    assert_eq!(
+        fault.to_string(),
+        "Invalid Register: V8 @ PC: 0x0202 [F875 LD R, V8] after 1 cycles"
+    );
+}
+
+#[test]
+fn cycle_counts_across_frames() {
+    // Counts to 25 in V0, then returns from nowhere: 24 rounds of 3 instructions, then the
+    // add and the skip
+    let program = [
+        0x70, 0x01, // 200: V0 += 1
+        0x30, 0x19, // 202: Skip if V0 == 25
+        0x12, 0x00, // 204: Jump 200
+        0x00, 0xEE, // 206: Return
+    ];
+    let mut emulator = emulator(&program, Quirks::superchip());
+    emulator.instructions_per_frame = 10;
+    let fault = loop {
+        match emulator.run_frame() {
+            Ok(_) => {}
+            Err(Chip8Error::Fault(fault)) => break fault,
+            Err(e) => panic!("{}", e),
+        }
+    };
+    assert_eq!((fault.kind, fault.pc), (FaultKind::StackUnderflow, 0x206));
+    assert_eq!(fault.cycle, 74);
+    assert_eq!((emulator.cycles, emulator.frames), (74, 7));
+}
+
+#[test]
+fn crash_report_at_the_end_of_memory() {
+    // Code at the top of 4K returning from nowhere, the listing stops at the end of memory
+    let mut emulator = emulator(&[0x1F, 0xFA], Quirks::superchip());
+    emulator.memory[0xFFA..].copy_from_slice(&[0x60, 0x05, 0x61, 0x06, 0x00, 0xEE]);
+    let fault = run_to_fault(&mut emulator);
+    assert_eq!((fault.pc, fault.opcode, fault.cycle), (0xFFE, 0x00EE, 3));
+
+    // Without symbols there is no label line
+    let expected = "\
+Crash: Stack Underflow @ PC: 0x0FFE [00EE RET] after 3 cycles
+Registers:
+  V0-V7: 05 06 00 00 00 00 00 00
+  V8-VF: 00 00 00 00 00 00 00 00
+  I=0000 DT=00 ST=00
+Stack (0):
+Code:
+     0FF6: 0000      DW 0x0000
+     0FF8: 0000      DW 0x0000
+     0FFA: 6005      LD V0, 0x05
+     0FFC: 6106      LD V1, 0x06
+  => 0FFE: 00EE      RET
+";
+    assert_eq!(Debugger::new(&mut emulator).crash_report(&fault), expected);
+}
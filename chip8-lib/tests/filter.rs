@@ -0,0 +1,73 @@
+// Display filter tests: phosphor fade and drawing only on vblank, on a plain framebuffer
+use chip8_lib::{
+    cpu::Emulator, filter::DisplayFilter, framebuffer::FrameBuffer, palette::Palette,
+    quirks::Quirks,
+};
+
+// Black and white, so a channel's value is the pixel's brightness
+fn palette() -> Palette {
+    Palette::new("test", &[0x000000, 0xFFFFFF]).unwrap()
+}
+
+fn brightness(color: u32) -> u32 {
+    color & 0xFF
+}
+
+#[test]
+fn off_shows_the_screen_as_is() {
+    let mut screen = FrameBuffer::default();
+    let mut filter = DisplayFilter::new(0.0, false);
+    screen.toggle(3, 2, 1);
+    filter.fade(&screen, &palette(), 1);
+
+    screen.toggle(3, 2, 1);
+    let colors = filter.fade(&screen, &palette(), 0);
+    assert!(colors.iter().all(|&color| color == 0x000000));
+    assert!(!filter.is_fading());
+}
+
+#[test]
+fn dark_pixels_fade_out() {
+    let mut screen = FrameBuffer::default();
+    let mut filter = DisplayFilter::new(0.5, false);
+    let idx = 3 + screen.width * 2;
+    screen.toggle(3, 2, 1);
+    assert_eq!(brightness(filter.fade(&screen, &palette(), 1)[idx]), 255);
+
+    // Erased: half as bright every frame, until it's dark
+    screen.toggle(3, 2, 1);
+    assert_eq!(brightness(filter.fade(&screen, &palette(), 1)[idx]), 128);
+    assert_eq!(brightness(filter.fade(&screen, &palette(), 0)[idx]), 128);
+    assert_eq!(brightness(filter.fade(&screen, &palette(), 2)[idx]), 32);
+    assert!(filter.is_fading());
+    assert_eq!(brightness(filter.fade(&screen, &palette(), 10)[idx]), 0);
+    assert!(!filter.is_fading());
+}
+
+#[test]
+fn lit_pixels_light_up_at_once() {
+    let mut screen = FrameBuffer::default();
+    let mut filter = DisplayFilter::new(0.9, false);
+    filter.fade(&screen, &palette(), 1);
+
+    screen.toggle(0, 0, 1);
+    assert_eq!(filter.fade(&screen, &palette(), 1)[0], 0xFFFFFF);
+}
+
+#[test]
+fn vblank_only_waits_for_the_frame() {
+    let mut emulator = Emulator::new(Quirks::default());
+    let mut filter = DisplayFilter::new(0.0, true);
+    filter.apply(&emulator, &palette());
+
+    // Drawn halfway through a frame
+    emulator.screen.toggle(0, 0, 1);
+    emulator.draw_flag = true;
+    assert!(!filter.should_draw(&emulator));
+
+    emulator.timer_tick();
+    assert!(filter.should_draw(&emulator));
+    assert_eq!(filter.apply(&emulator, &palette())[0], 0xFFFFFF);
+    emulator.draw_flag = false;
+    assert!(!filter.should_draw(&emulator));
+}
@@ -0,0 +1,150 @@
+// Keymap file tests, on a keymap with letter keys and a few named buttons instead of SDL2's
+use chip8_lib::keymap::{KeyBindings, KeymapConfig};
+
+const BUTTONS: [&str; 3] = ["a", "b", "start"];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TestKeymap {
+    keys: Vec<(char, u8)>,
+    buttons: Vec<(usize, u8)>,
+}
+
+impl KeyBindings for TestKeymap {
+    type Key = char;
+    type Button = usize;
+
+    const LAYOUT_NAMES: &'static [&'static str] = &["abc"];
+
+    // Keys a - p press 0 - F
+    fn layout(name: &str) -> Option<Self> {
+        (name == "abc").then(|| Self {
+            keys: ('a'..='p').zip(0..).collect(),
+            buttons: Vec::new(),
+        })
+    }
+
+    fn key_from_name(name: &str) -> Option<char> {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(key), None) if key.is_ascii_lowercase() => Some(key),
+            _ => None,
+        }
+    }
+
+    fn button_from_name(name: &str) -> Option<usize> {
+        BUTTONS.iter().position(|&button| button == name)
+    }
+
+    fn use_layout(&mut self, layout: &Self) {
+        self.keys = layout.keys.clone();
+    }
+
+    fn bind_key(&mut self, key: char, chip8_key: u8) {
+        self.keys.retain(|&(k, _)| k != key);
+        self.keys.push((key, chip8_key));
+    }
+
+    fn bind_button(&mut self, button: usize, chip8_key: u8) {
+        self.buttons.retain(|&(b, _)| b != button);
+        self.buttons.push((button, chip8_key));
+    }
+}
+
+impl TestKeymap {
+    fn key(&self, key: char) -> Option<u8> {
+        self.keys.iter().find(|&&(k, _)| k == key).map(|&(_, c)| c)
+    }
+
+    fn button(&self, name: &str) -> Option<u8> {
+        let button = TestKeymap::button_from_name(name)?;
+        self.buttons
+            .iter()
+            .find(|&&(b, _)| b == button)
+            .map(|&(_, c)| c)
+    }
+}
+
+fn parse_error(text: &str) -> String {
+    KeymapConfig::<TestKeymap>::parse(text).unwrap_err()
+}
+
+// Apply a keymap file to an empty keymap
+fn apply(text: &str, rom_name: &str, rom_hash: u64) -> TestKeymap {
+    let config = KeymapConfig::parse(text).unwrap();
+    let mut keymap = TestKeymap::default();
+    config.apply(&mut keymap, rom_name, rom_hash);
+    keymap
+}
+
+const CONFIG: &str = "
+# Every ROM
+layout = abc
+key z = 5
+pad a = 5
+
+[pong]
+key z = 1
+pad start = F
+
+[00000000DEADBEEF]
+key a = C
+";
+
+#[test]
+fn global_bindings_apply_to_every_rom() {
+    let keymap = apply(CONFIG, "tetris", 0x1234);
+    assert_eq!(keymap.key('a'), Some(0x0));
+    assert_eq!(keymap.key('z'), Some(0x5));
+    assert_eq!(keymap.button("a"), Some(0x5));
+    assert_eq!(keymap.button("start"), None);
+}
+
+#[test]
+fn rom_section_overrides_global_bindings() {
+    let keymap = apply(CONFIG, "PONG", 0x1234);
+    assert_eq!(keymap.key('z'), Some(0x1));
+    assert_eq!(keymap.button("a"), Some(0x5));
+    assert_eq!(keymap.button("start"), Some(0xF));
+    // Layout keys the section doesn't touch stay
+    assert_eq!(keymap.key('b'), Some(0x1));
+}
+
+#[test]
+fn section_matches_rom_hash() {
+    let keymap = apply(CONFIG, "renamed", 0xDEADBEEF);
+    assert_eq!(keymap.key('a'), Some(0xC));
+    assert_eq!(keymap.key('z'), Some(0x5));
+    assert_eq!(apply(CONFIG, "renamed", 0xDEADBEE0).key('a'), Some(0x0));
+}
+
+#[test]
+fn parse_errors_name_the_line() {
+    assert_eq!(
+        parse_error("layout = abc\n\n# comment\nkey z 5"),
+        "line 4: expected INPUT = VALUE"
+    );
+    assert_eq!(
+        parse_error("[pong]\nkey Up = 2"),
+        "line 2: unknown keyboard key Up"
+    );
+    assert_eq!(
+        parse_error("pad x = 2"),
+        "line 1: unknown controller button x"
+    );
+    assert_eq!(
+        parse_error("key z = 10"),
+        "line 1: 10 isn't a CHIP-8 key 0 - F"
+    );
+    assert_eq!(
+        parse_error("key z = G"),
+        "line 1: G isn't a CHIP-8 key 0 - F"
+    );
+    assert_eq!(
+        parse_error("layout = qwerty"),
+        "line 1: expected one of abc"
+    );
+    assert_eq!(
+        parse_error("mouse left = 1"),
+        "line 1: expected layout, key NAME or pad BUTTON"
+    );
+}
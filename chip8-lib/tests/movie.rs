@@ -0,0 +1,186 @@
+// Movie tests: a recording plays back to the same screens, the text format round trips, and
+// playback with other input desyncs
+use chip8_lib::{
+    constants::{FRAME_DURATION, MAX_INSTRUCTIONS_PER_FRAME, MOVIE_CHECK_INTERVAL},
+    cpu::Emulator,
+    debugger::Debugger,
+    drivers::rom_driver::ROM,
+    errors::{Chip8Error, FaultKind},
+    movie::{Movie, MovieMode, MovieSession},
+    platform::InputEvent,
+    quirks::Quirks,
+    random::RandomKind,
+};
+
+const FRAMES: u64 = 120;
+
+// Draws a digit at a random position every loop, 0 until key 5 is first held, then 5
+const PROGRAM: [u8; 14] = [
+    0xC0, 0x3F, // 200: V0 = random & 63
+    0xC1, 0x1F, // 202: V1 = random & 31
+    0x62, 0x05, // 204: V2 = 5
+    0xE2, 0xA1, // 206: Skip if key V2 isn't pressed
+    0xF2, 0x29, // 208: I = font digit V2
+    0xD0, 0x15, // 20A: Draw it at (V0, V1)
+    0x12, 0x00, // 20C: Loop
+];
+
+fn rom() -> ROM {
+    ROM::from_bytes(&PROGRAM, "movie").unwrap()
+}
+
+// Record FRAMES frames, holding key 5 down over frames 40 - 50
+fn record() -> (Movie, Vec<u64>) {
+    let mut emulator = Emulator::new(Quirks::superchip());
+    emulator.rng = RandomKind::Xorshift.create(42);
+    emulator.load_rom(rom()).unwrap();
+    let mut session = MovieSession::new(
+        Movie::new(&emulator, RandomKind::Xorshift, 42),
+        MovieMode::Record,
+    );
+    let mut debugger = Debugger::new(&mut emulator);
+
+    let mut screens = Vec::new();
+    for frame in 0..FRAMES {
+        match frame {
+            40 => session.key(InputEvent::KeyDown(5)),
+            50 => session.key(InputEvent::KeyUp(5)),
+            _ => {}
+        }
+        session.run_for(&mut debugger, FRAME_DURATION).unwrap();
+        screens.push(debugger.emulator.screen.checksum());
+    }
+    (session.movie, screens)
+}
+
+// Play a movie through, returning the screen checksum after each frame
+fn play(movie: Movie) -> Result<Vec<u64>, Chip8Error> {
+    let mut emulator = movie.emulator();
+    emulator.load_rom(rom()).unwrap();
+    movie.check_rom(&emulator)?;
+    let mut session = MovieSession::new(movie, MovieMode::Play);
+    let mut debugger = Debugger::new(&mut emulator);
+
+    let mut screens = Vec::new();
+    while !session.finished(debugger.emulator) {
+        session.run_for(&mut debugger, FRAME_DURATION)?;
+        screens.push(debugger.emulator.screen.checksum());
+    }
+    Ok(screens)
+}
+
+#[test]
+fn recording_plays_back_the_same() {
+    let (movie, screens) = record();
+    assert_eq!(movie.length, FRAMES);
+    assert_eq!(
+        movie.inputs,
+        [(40, InputEvent::KeyDown(5)), (50, InputEvent::KeyUp(5))]
+    );
+    // A checksum every MOVIE_CHECK_INTERVAL frames
+    let checked: Vec<u64> = movie.checksums.keys().copied().collect();
+    assert_eq!(checked, [30, 60, 90, 120]);
+    assert_eq!(movie.checksums[&60], screens[60 - 1]);
+
+    // Through the text format, as the emulator saves and loads it
+    let movie = Movie::parse(&movie.to_text()).unwrap();
+    assert_eq!(play(movie).unwrap(), screens);
+}
+
+#[test]
+fn other_input_desyncs() {
+    // Key 5 pressed later: the screens match until the press makes them differ
+    let (mut movie, _) = record();
+    movie.inputs[0].0 = 45;
+    match play(movie) {
+        Err(Chip8Error::Desync { frame, .. }) => assert_eq!(frame, 2 * MOVIE_CHECK_INTERVAL),
+        result => panic!("{:?}", result),
+    }
+
+    // Without the key, the digit is never 5
+    let (mut movie, _) = record();
+    movie.inputs.clear();
+    assert!(matches!(play(movie), Err(Chip8Error::Desync { .. })));
+}
+
+#[test]
+fn other_seed_desyncs() {
+    let (mut movie, _) = record();
+    movie.seed = 43;
+    match play(movie) {
+        Err(Chip8Error::Desync { frame, .. }) => assert_eq!(frame, MOVIE_CHECK_INTERVAL),
+        result => panic!("{:?}", result),
+    }
+}
+
+#[test]
+fn other_rom_is_refused() {
+    let (movie, _) = record();
+    let mut emulator = movie.emulator();
+    emulator
+        .load_rom(ROM::from_bytes(&[0x12, 0x00], "other").unwrap())
+        .unwrap();
+    assert!(matches!(
+        movie.check_rom(&emulator),
+        Err(Chip8Error::MovieError(_))
+    ));
+}
+
+#[test]
+fn speed_is_checked() {
+    let (movie, _) = record();
+    let text = movie.to_text();
+    let with_speed = |speed: u32| text.replace("speed 11\n", &format!("speed {}\n", speed));
+    assert!(text.contains("speed 11\n"));
+    assert_eq!(Movie::parse(&with_speed(0)), None);
+    assert_eq!(
+        Movie::parse(&with_speed(MAX_INSTRUCTIONS_PER_FRAME + 1)),
+        None
+    );
+    assert_eq!(
+        Movie::parse(&with_speed(MAX_INSTRUCTIONS_PER_FRAME))
+            .unwrap()
+            .instructions_per_frame,
+        MAX_INSTRUCTIONS_PER_FRAME
+    );
+}
+
+#[test]
+fn recording_keeps_the_frame_it_faulted_in() {
+    // Returns from nowhere once key 1 is pressed
+    let program = [0x61, 0x01, 0xE1, 0xA1, 0x00, 0xEE, 0x12, 0x00];
+    let rom = || ROM::from_bytes(&program, "crash").unwrap();
+    let mut emulator = Emulator::new(Quirks::superchip());
+    emulator.load_rom(rom()).unwrap();
+    let mut session = MovieSession::new(
+        Movie::new(&emulator, RandomKind::Xorshift, 1),
+        MovieMode::Record,
+    );
+    let mut debugger = Debugger::new(&mut emulator);
+    for _ in 0..5 {
+        session.run_for(&mut debugger, FRAME_DURATION).unwrap();
+    }
+    session.key(InputEvent::KeyDown(1));
+    let fault = match session.run_for(&mut debugger, FRAME_DURATION) {
+        Err(Chip8Error::Fault(fault)) => fault,
+        result => panic!("{:?}", result),
+    };
+    assert_eq!(fault.kind, FaultKind::StackUnderflow);
+    assert_eq!(session.movie.length, 6);
+
+    // Playing it back ends in the same fault
+    let movie = Movie::parse(&session.movie.to_text()).unwrap();
+    let mut emulator = movie.emulator();
+    emulator.load_rom(rom()).unwrap();
+    let mut session = MovieSession::new(movie, MovieMode::Play);
+    let mut debugger = Debugger::new(&mut emulator);
+    let played = loop {
+        match session.run_for(&mut debugger, FRAME_DURATION) {
+            Ok(_) if session.finished(debugger.emulator) => panic!("no fault"),
+            Ok(_) => {}
+            Err(Chip8Error::Fault(fault)) => break fault,
+            Err(e) => panic!("{}", e),
+        }
+    };
+    assert_eq!(played, fault);
+}
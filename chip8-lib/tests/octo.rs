@@ -0,0 +1,32 @@
+// Octo compiler tests: forward references and the limits of the 12-bit jumps
+use chip8_lib::octo::compile;
+
+fn bytes(source: &str) -> Vec<u8> {
+    compile("test", source)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .rom
+        .data
+}
+
+#[test]
+fn unpack_long_of_a_later_label() {
+    // Patching the label in later only fills in the operands, not the 6X opcodes
+    let rom = bytes(": main :unpack long data ;\n: data 0xAB");
+    assert_eq!(rom, [0x12, 0x02, 0x60, 0x02, 0x61, 0x08, 0x00, 0xEE, 0xAB]);
+}
+
+#[test]
+fn unpack_of_a_later_label() {
+    let rom = bytes(": main :unpack 0xA data ;\n: data 0xAB");
+    assert_eq!(rom, [0x12, 0x02, 0x60, 0xA2, 0x61, 0x08, 0x00, 0xEE, 0xAB]);
+}
+
+#[test]
+fn again_past_the_jump_range_is_an_error() {
+    let e = match compile("test", ": main ;\n:org 0x1000 loop again") {
+        Ok(program) => panic!("compiled to {:02X?}", program.rom.data),
+        Err(e) => e,
+    };
+    assert_eq!((e.line, e.column), (2, 18));
+    assert_eq!(e.message, "address out of range: 0x1000");
+}
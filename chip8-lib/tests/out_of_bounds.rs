@@ -0,0 +1,217 @@
+// Out of bounds tests: instructions reaching past the end of memory or using keys above 0xF
+// fail with `OutOfBounds::Error`, and wrap around with `OutOfBounds::Wrap`
+use chip8_lib::{
+    cpu::{Emulator, OutOfBounds},
+    drivers::rom_driver::ROM,
+    errors::{Chip8Error, Fault, FaultKind},
+    quirks::Quirks,
+};
+
+fn emulator(program: &[u8], quirks: Quirks, out_of_bounds: OutOfBounds) -> Emulator {
+    let mut emulator = Emulator::new(quirks);
+    emulator.out_of_bounds = out_of_bounds;
+    emulator
+        .load_rom(ROM::from_bytes(program, "bounds").unwrap())
+        .unwrap();
+    emulator
+}
+
+// Run a program that ends in a `Jump` to itself, `instructions` instructions long
+fn run(emulator: &mut Emulator, instructions: usize) {
+    for _ in 0..instructions {
+        emulator.tick().unwrap();
+    }
+}
+
+// Run a program until it faults
+fn run_to_fault(emulator: &mut Emulator) -> Fault {
+    for _ in 0..100 {
+        match emulator.tick() {
+            Ok(_) => {}
+            Err(Chip8Error::Fault(fault)) => return fault,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    panic!("no fault");
+}
+
+// The first 8 pixels of a row of the screen, as a sprite byte, for one plane
+fn row(emulator: &Emulator, y: usize, plane: u8) -> u8 {
+    (0..8).fold(0, |byte, x| {
+        byte << 1 | (emulator.screen.get(x, y) & plane != 0) as u8
+    })
+}
+
+// Draws 5 rows from 0xFFE, of which 3 are past the end of 4K
+const DRAW: [u8; 6] = [
+    0xAF, 0xFE, // 200: I = FFE
+    0xD0, 0x05, // 202: Draw 5 rows at (V0, V0)
+    0x12, 0x04, // 204: End
+];
+
+#[test]
+fn sprite_past_the_end_of_memory() {
+    let mut error = emulator(&DRAW, Quirks::superchip(), OutOfBounds::Error);
+    error.memory[0xFFE..].copy_from_slice(&[0xFF, 0x81]);
+    error.v[0xF] = 7;
+    let fault = run_to_fault(&mut error);
+    assert_eq!(fault.kind, FaultKind::MemoryOutOfBounds(0x1000));
+    assert_eq!((fault.pc, fault.opcode), (0x202, 0xD005));
+    // Nothing is drawn, not even the rows that were in memory
+    assert!(error.screen.pixels.iter().all(|&pixel| pixel == 0));
+    assert_eq!(error.v[0xF], 7);
+
+    // Wrapped, the last 3 rows are the top of the font's 0
+    let mut wrap = emulator(&DRAW, Quirks::superchip(), OutOfBounds::Wrap);
+    wrap.memory[0xFFE..].copy_from_slice(&[0xFF, 0x81]);
+    run(&mut wrap, 3);
+    let rows: Vec<u8> = (0..5).map(|y| row(&wrap, y, 1)).collect();
+    assert_eq!(rows, [0xFF, 0x81, 0xF0, 0x90, 0x90]);
+}
+
+#[test]
+fn sprite_with_a_plane_past_the_end_of_memory() {
+    // Plane 1's 2 rows are the last bytes of 64K, plane 2's are past it
+    let program = [
+        0xF0, 0x00, 0xFF, 0xFE, // 200: I = FFFE, long
+        0xF3, 0x01, // 204: Both planes
+        0xD0, 0x02, // 206: Draw 2 rows for each plane
+        0x12, 0x08, // 208: End
+    ];
+    let mut error = emulator(&program, Quirks::xo_chip(), OutOfBounds::Error);
+    error.memory[0xFFFE..].copy_from_slice(&[0xFF, 0x81]);
+    let fault = run_to_fault(&mut error);
+    assert_eq!(fault.kind, FaultKind::MemoryOutOfBounds(0x10000));
+    // Plane 1 isn't left drawn on its own
+    assert!(error.screen.pixels.iter().all(|&pixel| pixel == 0));
+
+    let mut wrap = emulator(&program, Quirks::xo_chip(), OutOfBounds::Wrap);
+    wrap.memory[0xFFFE..].copy_from_slice(&[0xFF, 0x81]);
+    run(&mut wrap, 4);
+    assert_eq!((row(&wrap, 0, 1), row(&wrap, 1, 1)), (0xFF, 0x81));
+    assert_eq!((row(&wrap, 0, 2), row(&wrap, 1, 2)), (0xF0, 0x90));
+}
+
+#[test]
+fn bcd_past_the_end_of_memory() {
+    let program = [
+        0xAF, 0xFE, // 200: I = FFE
+        0x60, 0x7B, // 202: V0 = 123
+        0xF0, 0x33, // 204: BCD of V0
+        0x12, 0x06, // 206: End
+    ];
+    let mut error = emulator(&program, Quirks::superchip(), OutOfBounds::Error);
+    let fault = run_to_fault(&mut error);
+    assert_eq!(fault.kind, FaultKind::MemoryOutOfBounds(0x1000));
+    assert_eq!(fault.pc, 0x204);
+
+    let mut wrap = emulator(&program, Quirks::superchip(), OutOfBounds::Wrap);
+    run(&mut wrap, 4);
+    assert_eq!(wrap.memory[0xFFE..], [1, 2]);
+    // The ones over the top of the font
+    assert_eq!(wrap.memory[0], 3);
+}
+
+#[test]
+fn registers_past_the_end_of_memory() {
+    // Stores V0 - V3 from FFE
+    let program = [
+        0x60, 0x0A, // 200: V0 = 0A
+        0x61, 0x0B, // 202: V1 = 0B
+        0x62, 0x0C, // 204: V2 = 0C
+        0x63, 0x0D, // 206: V3 = 0D
+        0xAF, 0xFE, // 208: I = FFE
+        0xF3, 0x55, // 20A: Store V0 - V3
+        0x12, 0x0C, // 20C: End
+    ];
+    let mut error = emulator(&program, Quirks::cosmac_vip(), OutOfBounds::Error);
+    let fault = run_to_fault(&mut error);
+    assert_eq!(fault.kind, FaultKind::MemoryOutOfBounds(0x1000));
+    assert_eq!((fault.pc, fault.opcode), (0x20A, 0xF355));
+
+    // Wrapped, V2 and V3 land on 0 and 1, and I moves past the end like it always does
+    let mut wrap = emulator(&program, Quirks::cosmac_vip(), OutOfBounds::Wrap);
+    run(&mut wrap, 7);
+    assert_eq!(wrap.memory[0xFFE..], [0x0A, 0x0B]);
+    assert_eq!(wrap.memory[..2], [0x0C, 0x0D]);
+    assert_eq!(wrap.i, 0x1002);
+
+    // Loading them back reads the same wrapped addresses
+    let load = [0xAF, 0xFE, 0xF3, 0x65, 0x12, 0x04];
+    for (out_of_bounds, expected) in [
+        (OutOfBounds::Error, None),
+        (OutOfBounds::Wrap, Some([0x0A, 0x0B, 0x0C, 0x0D])),
+    ] {
+        let mut emulator = emulator(&load, Quirks::superchip(), out_of_bounds);
+        emulator.memory[0xFFE..].copy_from_slice(&[0x0A, 0x0B]);
+        emulator.memory[..2].copy_from_slice(&[0x0C, 0x0D]);
+        match expected {
+            None => assert_eq!(
+                run_to_fault(&mut emulator).kind,
+                FaultKind::MemoryOutOfBounds(0x1000)
+            ),
+            Some(registers) => {
+                run(&mut emulator, 2);
+                assert_eq!(emulator.v[..4], registers);
+            }
+        }
+    }
+}
+
+#[test]
+fn fetch_at_the_last_byte_of_memory() {
+    // Jump to FFF, where the opcode's second byte is past the end
+    let program = [0x1F, 0xFF];
+    let mut error = emulator(&program, Quirks::superchip(), OutOfBounds::Error);
+    error.memory[0xFFF] = 0x12;
+    let fault = run_to_fault(&mut error);
+    assert_eq!(fault.kind, FaultKind::MemoryOutOfBounds(0x1000));
+    assert_eq!(fault.pc, 0xFFF);
+    assert_eq!(fault.cycle, 1);
+
+    // Wrapped, the second byte is memory[0], F0 from the font, and PC carries on at 1
+    let mut wrap = emulator(&program, Quirks::superchip(), OutOfBounds::Wrap);
+    wrap.memory[0xFFF] = 0x62;
+    run(&mut wrap, 2);
+    assert_eq!(wrap.v[2], 0xF0);
+    assert_eq!(wrap.pc, 0x001);
+}
+
+#[test]
+fn jump_with_offset_past_the_end_of_memory() {
+    let program = [
+        0x60, 0x02, // 200: V0 = 2
+        0xBF, 0xFF, // 202: Jump to FFF + V0
+    ];
+    let mut error = emulator(&program, Quirks::cosmac_vip(), OutOfBounds::Error);
+    let fault = run_to_fault(&mut error);
+    assert_eq!(fault.kind, FaultKind::MemoryOutOfBounds(0x1001));
+    assert_eq!(fault.pc, 0x202);
+
+    let mut wrap = emulator(&program, Quirks::cosmac_vip(), OutOfBounds::Wrap);
+    run(&mut wrap, 2);
+    assert_eq!(wrap.pc, 0x001);
+}
+
+#[test]
+fn keys_above_f() {
+    // Skip the V1 = 1 if key V0 = 12 is pressed
+    let program = [
+        0x60, 0x12, // 200: V0 = 12
+        0xE0, 0x9E, // 202: Skip if key V0 is pressed
+        0x61, 0x01, // 204: V1 = 1
+        0x12, 0x06, // 206: End
+    ];
+    let mut error = emulator(&program, Quirks::superchip(), OutOfBounds::Error);
+    // The frontend's key 12 is ignored
+    error.key_down(0x12);
+    assert!(error.keypad.iter().all(|&pressed| !pressed));
+    assert_eq!(run_to_fault(&mut error).kind, FaultKind::InvalidKey(0x12));
+
+    // Wrapped, 12 is key 2, whether it comes from the frontend or V0
+    let mut wrap = emulator(&program, Quirks::superchip(), OutOfBounds::Wrap);
+    wrap.key_down(0x12);
+    assert!(wrap.keypad[2]);
+    run(&mut wrap, 3);
+    assert_eq!((wrap.pc, wrap.v[1]), (0x206, 0));
+}
@@ -0,0 +1,87 @@
+// Palette tests: hex colors, palettes given on the command line and palette files
+use chip8_lib::palette::{parse_color, Palette, NUM_COLORS, PALETTE_NAMES};
+
+#[test]
+fn colors() {
+    assert_eq!(parse_color("1A1C2C"), Some(0x1A1C2C));
+    assert_eq!(parse_color("#ffb000"), Some(0xFFB000));
+    assert_eq!(parse_color(" 000000 "), Some(0x000000));
+
+    for bad in [
+        "", "#", "FFF", "1234567", "GGGGGG", "12 456", "+12345", "-12345", "0x1234", "##123456",
+    ] {
+        assert_eq!(parse_color(bad), None, "{:?}", bad);
+    }
+}
+
+#[test]
+fn two_and_four_colors_fill_the_plane_combinations() {
+    // Every lit pixel gets the foreground
+    let palette = Palette::parse("000000,FFFFFF").unwrap();
+    assert_eq!(palette.name, "custom");
+    assert_eq!(palette.color(0), 0x000000);
+    assert!((1..NUM_COLORS as u8).all(|pixel| palette.color(pixel) == 0xFFFFFF));
+
+    // Plane combinations past the first two reuse them, never the background
+    let palette = Palette::parse("#000000,#FF0000,#00FF00,#0000FF").unwrap();
+    assert_eq!(
+        palette.colors[..4],
+        [0x000000, 0xFF0000, 0x00FF00, 0x0000FF]
+    );
+    assert_eq!(palette.colors[4], 0xFF0000);
+    assert_eq!(palette.colors[5], 0xFF0000);
+    assert_eq!(palette.colors[7], 0x0000FF);
+
+    let sixteen: Vec<String> = (0..16).map(|n| format!("{:06X}", n)).collect();
+    let palette = Palette::parse(&sixteen.join(",")).unwrap();
+    assert!((0..16).all(|n| palette.colors[n] == n as u32));
+}
+
+#[test]
+fn wrong_color_counts_are_refused() {
+    for text in [
+        "000000",
+        "000000,FFFFFF,AAAAAA",
+        "000000,FFFFFF,AAAAAA,555555,123456",
+    ] {
+        assert_eq!(Palette::parse(text), None, "{}", text);
+    }
+    assert_eq!(Palette::new("empty", &[]), None);
+    assert_eq!(Palette::parse("000000,nothex"), None);
+}
+
+#[test]
+fn names() {
+    for name in PALETTE_NAMES {
+        assert_eq!(Palette::from_name(name).unwrap().name, name);
+        assert_eq!(Palette::parse(name), Palette::from_name(name));
+    }
+    assert_eq!(Palette::from_name("GREEN").unwrap().name, "classic");
+    assert_eq!(Palette::from_name("sepia"), None);
+    let builtin: Vec<String> = Palette::builtin().into_iter().map(|p| p.name).collect();
+    assert_eq!(builtin, PALETTE_NAMES);
+}
+
+#[test]
+fn palette_files() {
+    let text = "\
+# name = background plane1 plane2 both
+sunset = 1A1C2C F4B41A E06040 FFF0D0
+
+mono=000000 #FFFFFF
+";
+    let palettes = Palette::parse_file(text).unwrap();
+    assert_eq!(palettes.len(), 2);
+    assert_eq!(palettes[0].name, "sunset");
+    assert_eq!(palettes[0].colors[3], 0xFFF0D0);
+    assert_eq!(palettes[1].name, "mono");
+    assert_eq!(palettes[1].colors[1], 0xFFFFFF);
+
+    // The error names the line
+    assert_eq!(
+        Palette::parse_file("a = 000000 FFFFFF\nb = 000000 FFFFF\n"),
+        Err("line 2: expected NAME = 2, 4 or 16 hex colors".to_string())
+    );
+    assert!(Palette::parse_file("c 000000 FFFFFF").is_err());
+    assert!(Palette::parse_file("d = 000000 FFFFFF 123456").is_err());
+}
@@ -0,0 +1,286 @@
+// Quirks tests: each quirk changes exactly the instruction it is for, and each preset picks
+// the interpretations of the interpreter it is named after
+use chip8_lib::{
+    cpu::Emulator,
+    drivers::rom_driver::ROM,
+    errors::{Chip8Error, FaultKind},
+    quirks::Quirks,
+};
+
+// Load a program and run its first `cycles` instructions
+fn run(quirks: Quirks, program: &[u8], cycles: usize) -> Result<Emulator, Chip8Error> {
+    let mut emulator = Emulator::new(quirks);
+    emulator
+        .load_rom(ROM::from_bytes(program, "test").unwrap())
+        .unwrap();
+    for _ in 0..cycles {
+        emulator.tick()?;
+    }
+    Ok(emulator)
+}
+
+// What a quirks profile does with every ambiguous instruction
+#[derive(Debug, PartialEq)]
+struct Behavior {
+    shifted: (u8, u8),       // V1 and VF after V1 = 5, V2 = 6, 8126
+    i_after_store: u16,      // I after I = 0x300, F155
+    jump_target: u16,        // PC after V0 = 8, V2 = 4, B210
+    vf_after_or: u8,         // VF after VF = 5, 8011
+    wrapped: bool,           // Whether a sprite drawn at X = 60 wraps to the left edge
+    second_draw_waits: bool, // Whether a second sprite in the same frame waits for vblank
+    long_load: bool,         // Whether F000 NNNN decodes
+}
+
+fn behavior(quirks: Quirks) -> Behavior {
+    let shift = run(quirks, &[0x61, 0x05, 0x62, 0x06, 0x81, 0x26], 3).unwrap();
+    let store = run(quirks, &[0xA3, 0x00, 0xF1, 0x55], 2).unwrap();
+    let jump = run(quirks, &[0x60, 0x08, 0x62, 0x04, 0xB2, 0x10], 3).unwrap();
+    let or = run(quirks, &[0x6F, 0x05, 0x80, 0x11], 2).unwrap();
+    // V0 = 60, V1 = 0, I = sprite, draw an 8 pixel wide row at (60, 0), then at (0, 28)
+    let draw = [
+        0x60, 0x3C, 0x61, 0x00, 0xA2, 0x0A, 0xD0, 0x11, 0xD1, 0x01, 0xFF,
+    ];
+    let draw = run(quirks, &draw, 5).unwrap();
+    let long_load = match run(quirks, &[0xF0, 0x00, 0x12, 0x34], 1) {
+        Ok(emulator) => emulator.i == 0x1234,
+        Err(Chip8Error::Fault(fault)) => {
+            assert_eq!(fault.kind, FaultKind::InvalidInstruction);
+            false
+        }
+        Err(e) => panic!("{}", e),
+    };
+    Behavior {
+        shifted: (shift.v[1], shift.v[0xF]),
+        i_after_store: store.i,
+        jump_target: jump.pc,
+        vf_after_or: or.v[0xF],
+        wrapped: draw.screen.get(0, 0) != 0,
+        second_draw_waits: draw.pc == 0x208,
+        long_load,
+    }
+}
+
+// The default profile, every other test changes one thing about it
+fn default_behavior() -> Behavior {
+    Behavior {
+        shifted: (2, 1),
+        i_after_store: 0x300,
+        jump_target: 0x218,
+        vf_after_or: 5,
+        wrapped: true,
+        second_draw_waits: false,
+        long_load: false,
+    }
+}
+
+#[test]
+fn default_quirks() {
+    assert_eq!(behavior(Quirks::default()), default_behavior());
+}
+
+#[test]
+fn shift_uses_vy() {
+    let quirks = Quirks {
+        shift_uses_vy: true,
+        ..Quirks::default()
+    };
+    assert_eq!(
+        behavior(quirks),
+        Behavior {
+            shifted: (3, 0),
+            ..default_behavior()
+        }
+    );
+}
+
+#[test]
+fn memory_increments_i() {
+    let quirks = Quirks {
+        memory_increments_i: true,
+        ..Quirks::default()
+    };
+    assert_eq!(
+        behavior(quirks),
+        Behavior {
+            i_after_store: 0x302,
+            ..default_behavior()
+        }
+    );
+}
+
+#[test]
+fn memory_increments_i_by_x() {
+    let quirks = Quirks {
+        memory_increments_i: true,
+        memory_increments_i_by_x: true,
+        ..Quirks::default()
+    };
+    assert_eq!(
+        behavior(quirks),
+        Behavior {
+            i_after_store: 0x301,
+            ..default_behavior()
+        }
+    );
+
+    // Only changes how far `memory_increments_i` moves I
+    let quirks = Quirks {
+        memory_increments_i_by_x: true,
+        ..Quirks::default()
+    };
+    assert_eq!(behavior(quirks), default_behavior());
+}
+
+#[test]
+fn jump_uses_vx() {
+    let quirks = Quirks {
+        jump_uses_vx: true,
+        ..Quirks::default()
+    };
+    assert_eq!(
+        behavior(quirks),
+        Behavior {
+            jump_target: 0x214,
+            ..default_behavior()
+        }
+    );
+}
+
+#[test]
+fn vf_reset() {
+    let quirks = Quirks {
+        vf_reset: true,
+        ..Quirks::default()
+    };
+    assert_eq!(
+        behavior(quirks),
+        Behavior {
+            vf_after_or: 0,
+            ..default_behavior()
+        }
+    );
+    // AND and XOR reset it too
+    for op in [0x12, 0x13] {
+        let emulator = run(quirks, &[0x6F, 0x05, 0x80, op], 2).unwrap();
+        assert_eq!(emulator.v[0xF], 0, "80{:02X}", op);
+    }
+}
+
+#[test]
+fn clip_sprites() {
+    let quirks = Quirks {
+        clip_sprites: true,
+        ..Quirks::default()
+    };
+    assert_eq!(
+        behavior(quirks),
+        Behavior {
+            wrapped: false,
+            ..default_behavior()
+        }
+    );
+}
+
+#[test]
+fn display_wait() {
+    let quirks = Quirks {
+        display_wait: true,
+        ..Quirks::default()
+    };
+    assert_eq!(
+        behavior(quirks),
+        Behavior {
+            second_draw_waits: true,
+            ..default_behavior()
+        }
+    );
+}
+
+#[test]
+fn xo_chip() {
+    let quirks = Quirks {
+        xo_chip: true,
+        ..Quirks::default()
+    };
+    assert_eq!(
+        behavior(quirks),
+        Behavior {
+            long_load: true,
+            ..default_behavior()
+        }
+    );
+    assert_eq!(Emulator::new(quirks).memory.len(), 0x10000);
+    assert_eq!(Emulator::new(Quirks::default()).memory.len(), 0x1000);
+}
+
+#[test]
+fn vip_preset() {
+    assert_eq!(
+        behavior(Quirks::preset("vip").unwrap()),
+        Behavior {
+            shifted: (3, 0),
+            i_after_store: 0x302,
+            jump_target: 0x218,
+            vf_after_or: 0,
+            wrapped: false,
+            second_draw_waits: true,
+            long_load: false,
+        }
+    );
+}
+
+#[test]
+fn chip48_preset() {
+    assert_eq!(
+        behavior(Quirks::preset("chip48").unwrap()),
+        Behavior {
+            shifted: (2, 1),
+            i_after_store: 0x301,
+            jump_target: 0x214,
+            vf_after_or: 5,
+            wrapped: false,
+            second_draw_waits: false,
+            long_load: false,
+        }
+    );
+}
+
+#[test]
+fn schip_preset() {
+    assert_eq!(
+        behavior(Quirks::preset("schip").unwrap()),
+        Behavior {
+            shifted: (2, 1),
+            i_after_store: 0x300,
+            jump_target: 0x214,
+            vf_after_or: 5,
+            wrapped: false,
+            second_draw_waits: false,
+            long_load: false,
+        }
+    );
+}
+
+#[test]
+fn xochip_preset() {
+    assert_eq!(
+        behavior(Quirks::preset("xochip").unwrap()),
+        Behavior {
+            shifted: (3, 0),
+            i_after_store: 0x302,
+            jump_target: 0x218,
+            vf_after_or: 5,
+            wrapped: true,
+            second_draw_waits: false,
+            long_load: true,
+        }
+    );
+}
+
+#[test]
+fn quirks_round_trip_through_bits() {
+    for name in ["default", "vip", "chip48", "schip", "xochip"] {
+        let quirks = Quirks::preset(name).unwrap();
+        assert_eq!(Quirks::from_bits(quirks.to_bits()), quirks, "{}", name);
+    }
+}
@@ -0,0 +1,159 @@
+// Rewind tests: stepping back restores every recorded snapshot exactly, the deltas stay small
+// and the history stays within its depth
+use chip8_lib::{
+    constants::FRAME_DURATION, cpu::Emulator, drivers::rom_driver::ROM, quirks::Quirks,
+    random::RandomKind, rewind::Rewind,
+};
+
+// Counts frames in V0 and draws V0's digit, so every frame changes memory, registers and
+// the screen
+const PROGRAM: [u8; 12] = [
+    0x00, 0xE0, // Clear
+    0xF0, 0x29, // I = font digit V0
+    0xD1, 0x15, // Draw it at (V1, V1)
+    0x70, 0x01, // V0 += 1
+    0xF0, 0x33, // BCD of V0 at I, wherever the font digit is
+    0x12, 0x00, // Loop
+];
+
+fn emulator(program: &[u8]) -> Emulator {
+    let mut emulator = Emulator::new(Quirks::superchip());
+    emulator.rng = RandomKind::Xorshift.create(1);
+    emulator.instructions_per_frame = 6;
+    emulator
+        .load_rom(ROM::from_bytes(program, "rewind").unwrap())
+        .unwrap();
+    emulator
+}
+
+// Run frames one at a time, recording each, and keep the state after each
+fn record_frames(emulator: &mut Emulator, rewind: &mut Rewind, frames: usize) -> Vec<Vec<u8>> {
+    let mut states = Vec::new();
+    for _ in 0..frames {
+        let ran = emulator.run_for(FRAME_DURATION).unwrap();
+        rewind.record(emulator, ran as u64);
+        states.push(emulator.save_state());
+    }
+    states
+}
+
+// The bytes the history needs besides the newest snapshot
+fn delta_usage(rewind: &Rewind, emulator: &Emulator) -> usize {
+    rewind.memory_usage() - emulator.save_state().len()
+}
+
+#[test]
+fn step_back_restores_each_snapshot() {
+    let mut emulator = emulator(&PROGRAM);
+    let mut rewind = Rewind::new(100, 1);
+    let states = record_frames(&mut emulator, &mut rewind, 20);
+    assert_eq!(rewind.len(), 19);
+
+    for state in states.iter().rev().skip(1) {
+        assert!(rewind.step_back(&mut emulator));
+        assert_eq!(&emulator.save_state(), state);
+        assert!(emulator.draw_flag);
+    }
+    assert!(rewind.is_empty());
+    assert!(!rewind.step_back(&mut emulator));
+    assert_eq!(&emulator.save_state(), &states[0]);
+}
+
+#[test]
+fn resolution_change_stores_the_whole_snapshot() {
+    // Switch to hi-res after the first frame's instructions
+    let program = [
+        &[0x00, 0xE0, 0x00, 0xE0, 0x00, 0xE0, 0x00, 0xFF],
+        &PROGRAM[..],
+    ]
+    .concat();
+    let mut emulator = emulator(&program);
+    emulator.instructions_per_frame = 3;
+    let mut rewind = Rewind::new(100, 1);
+    let states = record_frames(&mut emulator, &mut rewind, 2);
+    assert!(emulator.screen.is_hires());
+    // The delta is the low-res snapshot, whole
+    assert_eq!(delta_usage(&rewind, &emulator), states[0].len());
+
+    assert!(rewind.step_back(&mut emulator));
+    assert!(!emulator.screen.is_hires());
+    assert_eq!(emulator.save_state(), states[0]);
+}
+
+#[test]
+fn nearby_changes_merge_into_one_run() {
+    // Two changed bytes `distance` apart, in memory that is otherwise left alone
+    let usage = |distance: usize| {
+        let mut emulator = emulator(&PROGRAM);
+        let mut rewind = Rewind::new(10, 1);
+        rewind.record(&emulator, 1);
+        emulator.memory[0x300] = 1;
+        emulator.memory[0x300 + distance] = 1;
+        rewind.record(&emulator, 1);
+        delta_usage(&rewind, &emulator)
+    };
+    // Each run costs its bytes plus 16 for its offset and length
+    assert_eq!(usage(1), 2 + 16);
+    // A gap of up to 7 unchanged bytes is stored with them
+    assert_eq!(usage(8), 9 + 16);
+    // 8 unchanged bytes make two runs
+    assert_eq!(usage(9), 2 * (1 + 16));
+}
+
+#[test]
+fn unchanged_snapshot_costs_nothing() {
+    let emulator = emulator(&PROGRAM);
+    let mut rewind = Rewind::new(10, 1);
+    rewind.record(&emulator, 1);
+    rewind.record(&emulator, 1);
+    assert_eq!(rewind.len(), 1);
+    assert_eq!(delta_usage(&rewind, &emulator), 0);
+}
+
+#[test]
+fn depth_drops_the_oldest_snapshots() {
+    let mut emulator = emulator(&PROGRAM);
+    let mut rewind = Rewind::new(5, 1);
+    let states = record_frames(&mut emulator, &mut rewind, 20);
+    // The newest snapshot and 4 to step back to
+    assert_eq!(rewind.len(), 4);
+
+    for state in states[15..19].iter().rev() {
+        assert!(rewind.step_back(&mut emulator));
+        assert_eq!(&emulator.save_state(), state);
+    }
+    assert!(!rewind.step_back(&mut emulator));
+}
+
+#[test]
+fn granularity_skips_frames() {
+    let mut emulator = emulator(&PROGRAM);
+    let mut rewind = Rewind::new(100, 3);
+    let states = record_frames(&mut emulator, &mut rewind, 10);
+    // Snapshots after frames 1, 4, 7 and 10
+    assert_eq!(rewind.len(), 3);
+    for state in [&states[6], &states[3], &states[0]] {
+        assert!(rewind.step_back(&mut emulator));
+        assert_eq!(&emulator.save_state(), state);
+    }
+}
+
+#[test]
+fn held_keys_stay_held() {
+    let mut emulator = emulator(&PROGRAM);
+    let mut rewind = Rewind::new(100, 1);
+    record_frames(&mut emulator, &mut rewind, 3);
+    emulator.key_down(5);
+    assert!(rewind.step_back(&mut emulator));
+    assert!(emulator.keypad[5]);
+}
+
+#[test]
+fn clear_forgets_the_history() {
+    let mut emulator = emulator(&PROGRAM);
+    let mut rewind = Rewind::new(100, 1);
+    record_frames(&mut emulator, &mut rewind, 3);
+    rewind.clear();
+    assert_eq!(rewind.memory_usage(), 0);
+    assert!(!rewind.step_back(&mut emulator));
+}
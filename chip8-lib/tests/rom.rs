@@ -0,0 +1,127 @@
+// ROM tests: empty and oversized ROMs are refused, by size alone and by the emulator
+// loading them
+use std::io::{self, Read};
+
+use chip8_lib::{
+    constants::{MEMORY_SIZE, ROM_START, XO_MEMORY_SIZE},
+    cpu::Emulator,
+    drivers::rom_driver::{MAX_ROM_SIZE, ROM},
+    errors::RomError,
+    quirks::Quirks,
+};
+
+// The most a 4K emulator can load
+const MAX_4K: usize = MEMORY_SIZE - ROM_START as usize;
+
+fn too_large(result: Result<ROM, RomError>) -> (usize, usize) {
+    match result {
+        Err(RomError::TooLarge { size, max }) => (size, max),
+        Err(e) => panic!("{}", e),
+        Ok(rom) => panic!("loaded {} bytes", rom.data.len()),
+    }
+}
+
+// An endless stream of zeros, counting how much of it was read
+struct Endless {
+    read: usize,
+}
+
+impl Read for Endless {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        buf.fill(0);
+        self.read += buf.len();
+        Ok(buf.len())
+    }
+}
+
+#[test]
+fn validate_checks_the_size() {
+    let rom = ROM::new(vec![0; 100], "rom".to_string());
+    assert!(rom.validate(100).is_ok());
+    assert!(matches!(
+        rom.validate(99),
+        Err(RomError::TooLarge { size: 100, max: 99 })
+    ));
+    let empty = ROM::new(Vec::new(), "empty".to_string());
+    assert!(matches!(empty.validate(100), Err(RomError::Empty)));
+}
+
+#[test]
+fn from_bytes_refuses_empty_and_oversized_roms() {
+    assert_eq!(MAX_ROM_SIZE, XO_MEMORY_SIZE - 0x200);
+    assert!(matches!(
+        ROM::from_bytes(&[], "empty"),
+        Err(RomError::Empty)
+    ));
+    assert_eq!(
+        ROM::from_bytes(&[0; MAX_ROM_SIZE], "max")
+            .unwrap()
+            .data
+            .len(),
+        MAX_ROM_SIZE
+    );
+    assert_eq!(
+        too_large(ROM::from_bytes(&[0; MAX_ROM_SIZE + 1], "big")),
+        (MAX_ROM_SIZE + 1, MAX_ROM_SIZE)
+    );
+}
+
+#[test]
+fn from_reader_stops_reading_past_the_limit() {
+    assert!(matches!(
+        ROM::from_reader(io::empty(), "empty"),
+        Err(RomError::Empty)
+    ));
+    let rom = ROM::from_reader(&[0x12, 0x00][..], "jump").unwrap();
+    assert_eq!(rom.data, [0x12, 0x00]);
+
+    // Reading stops one byte past the most any platform loads
+    let mut endless = Endless { read: 0 };
+    assert_eq!(
+        too_large(ROM::from_reader(&mut endless, "endless")),
+        (MAX_ROM_SIZE + 1, MAX_ROM_SIZE)
+    );
+    assert!(endless.read < MAX_ROM_SIZE * 2);
+}
+
+#[test]
+fn load_rom_checks_the_platform_memory() {
+    // Too big for 4K, but fine with XO-CHIP's 64K
+    let data = vec![0xAA; MAX_4K + 1];
+    let mut emulator = Emulator::new(Quirks::superchip());
+    let e = emulator
+        .load_rom(ROM::from_bytes(&data, "big").unwrap())
+        .unwrap_err();
+    assert!(matches!(
+        e,
+        RomError::TooLarge { size, max: MAX_4K } if size == MAX_4K + 1
+    ));
+    assert_eq!(
+        e.to_string(),
+        "ROM is 3585 bytes, only 3584 bytes fit in memory"
+    );
+    // Nothing was loaded
+    assert!(emulator.memory[ROM_START as usize..]
+        .iter()
+        .all(|&b| b == 0));
+
+    let mut emulator = Emulator::new(Quirks::xo_chip());
+    emulator
+        .load_rom(ROM::from_bytes(&data, "big").unwrap())
+        .unwrap();
+    assert_eq!(emulator.memory[ROM_START as usize + MAX_4K], 0xAA);
+
+    // The largest 4K ROM still fits
+    let mut emulator = Emulator::new(Quirks::superchip());
+    emulator
+        .load_rom(ROM::from_bytes(&data[..MAX_4K], "max").unwrap())
+        .unwrap();
+    assert_eq!(emulator.memory[MEMORY_SIZE - 1], 0xAA);
+}
+
+#[test]
+fn load_rom_refuses_an_empty_rom() {
+    let mut emulator = Emulator::new(Quirks::default());
+    let empty = ROM::new(Vec::new(), "empty".to_string());
+    assert!(matches!(emulator.load_rom(empty), Err(RomError::Empty)));
+}
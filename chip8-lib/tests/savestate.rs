@@ -0,0 +1,194 @@
+// Save state tests: a restored state carries on exactly like the original, and every kind of
+// damaged state is refused without touching the emulator
+use chip8_lib::{
+    constants::{FRAME_DURATION, MAX_INSTRUCTIONS_PER_FRAME, MEMORY_SIZE, STACK_SIZE},
+    cpu::{Emulator, OutOfBounds},
+    drivers::rom_driver::ROM,
+    quirks::Quirks,
+    random::RandomKind,
+};
+
+// Draws random digits all over the screen
+const PROGRAM: [u8; 12] = [
+    0xC0, 0x3F, // V0 = random & 63
+    0xC1, 0x1F, // V1 = random & 31
+    0xC2, 0x0F, // V2 = random & 15
+    0xF2, 0x29, // I = font digit V2
+    0xD0, 0x15, // Draw it at (V0, V1)
+    0x12, 0x00, // Loop
+];
+
+// Offsets into a state of a 4K emulator, following the layout `save_state` writes
+const QUIRKS: usize = 4 + 2 + 8;
+const MEMORY: usize = QUIRKS + 1;
+const SP: usize = MEMORY + 4 + MEMORY_SIZE + 16 + 2 + 2 + STACK_SIZE * 2;
+const WIDTH: usize = SP + 4;
+const PIXELS: usize = WIDTH + 4;
+const PLANES: usize = PIXELS + 4 + 64 * 32 + 16 + 16 + 1;
+const INSTRUCTIONS_PER_FRAME: usize = PLANES + 1 + 16 + 1;
+const OUT_OF_BOUNDS: usize = INSTRUCTIONS_PER_FRAME + 4;
+const RANDOM: usize = OUT_OF_BOUNDS + 1 + 8 + 8 + 8 + 1;
+
+fn emulator() -> Emulator {
+    let mut emulator = Emulator::new(Quirks::default());
+    emulator.rng = RandomKind::Xorshift.create(7);
+    emulator
+        .load_rom(ROM::from_bytes(&PROGRAM, "random").unwrap())
+        .unwrap();
+    emulator
+}
+
+// Everything a state holds that can be compared
+fn snapshot(emulator: &Emulator) -> impl PartialEq + std::fmt::Debug {
+    (
+        emulator.memory.clone(),
+        emulator.v,
+        (emulator.i, emulator.pc, emulator.stack, emulator.sp),
+        (emulator.dt, emulator.st, emulator.cycles, emulator.frames),
+        emulator.screen.pixels.clone(),
+        emulator.quirks,
+        emulator.instructions_per_frame,
+    )
+}
+
+fn load_error(state: &[u8]) -> String {
+    let mut emulator = emulator();
+    let before = snapshot(&emulator);
+    let e = emulator.load_state(state).unwrap_err();
+    assert_eq!(
+        snapshot(&emulator),
+        before,
+        "a failed load changed the emulator"
+    );
+    e.to_string()
+}
+
+// A state with one byte changed
+fn corrupt(offset: usize, value: u8) -> Vec<u8> {
+    let mut state = emulator().save_state();
+    state[offset] = value;
+    state
+}
+
+#[test]
+fn restored_state_carries_on_the_same() {
+    let mut original = emulator();
+    original.run_for(FRAME_DURATION * 10).unwrap();
+    let state = original.save_state();
+    original
+        .run_for(FRAME_DURATION * 10 + FRAME_DURATION / 2)
+        .unwrap();
+
+    // A fresh emulator with another seed, restored, draws the same random sprites
+    let mut restored = emulator();
+    restored.rng = RandomKind::Xorshift.create(8);
+    restored.load_state(&state).unwrap();
+    restored
+        .run_for(FRAME_DURATION * 10 + FRAME_DURATION / 2)
+        .unwrap();
+    assert_eq!(snapshot(&restored), snapshot(&original));
+    assert_eq!(restored.save_state(), original.save_state());
+}
+
+#[test]
+fn state_replaces_quirks_speed_and_memory_wrapping() {
+    let mut saved = emulator();
+    saved.quirks = Quirks::cosmac_vip();
+    saved.instructions_per_frame = 30;
+    saved.out_of_bounds = OutOfBounds::Wrap;
+    let state = saved.save_state();
+
+    let mut emulator = emulator();
+    emulator.load_state(&state).unwrap();
+    assert_eq!(emulator.quirks, Quirks::cosmac_vip());
+    assert_eq!(emulator.instructions_per_frame, 30);
+    assert_eq!(emulator.out_of_bounds, OutOfBounds::Wrap);
+}
+
+#[test]
+fn header_is_checked() {
+    assert_eq!(
+        load_error(&corrupt(0, b'X')),
+        "Save State Error: not a save state"
+    );
+    assert_eq!(
+        load_error(&corrupt(4, 99)),
+        "Save State Error: unsupported version 99"
+    );
+
+    let mut other = Emulator::new(Quirks::default());
+    other
+        .load_rom(ROM::from_bytes(&[0x12, 0x00], "other").unwrap())
+        .unwrap();
+    let e = load_error(&other.save_state());
+    assert!(e.starts_with("Save State is for another ROM"), "{}", e);
+}
+
+#[test]
+fn truncated_state_is_refused() {
+    let state = emulator().save_state();
+    for len in [0, 3, QUIRKS, SP, state.len() - 1] {
+        assert_eq!(
+            load_error(&state[..len]),
+            "Save State Error: unexpected end of data"
+        );
+    }
+}
+
+#[test]
+fn impossible_values_are_refused() {
+    let corrupt_data = "Save State Error: corrupt data";
+    // 4K of memory with the XO-CHIP quirk, which has 64K
+    let xo_chip = Quirks::xo_chip().to_bits();
+    assert_eq!(load_error(&corrupt(QUIRKS, xo_chip)), corrupt_data);
+    // A stack pointer past the stack
+    assert_eq!(load_error(&corrupt(SP, 17)), corrupt_data);
+    // A 65 pixel wide screen
+    assert_eq!(load_error(&corrupt(WIDTH, 65)), corrupt_data);
+    // A pixel lit in plane 2 outside XO-CHIP
+    assert_eq!(load_error(&corrupt(PIXELS + 4, 2)), corrupt_data);
+    // Planes 1 and 2 selected outside XO-CHIP
+    assert_eq!(load_error(&corrupt(PLANES, 3)), corrupt_data);
+    // No memory wrapping mode 2
+    assert_eq!(load_error(&corrupt(OUT_OF_BOUNDS, 2)), corrupt_data);
+}
+
+#[test]
+fn instructions_per_frame_is_checked() {
+    let mut state = emulator().save_state();
+    let mut set = |value: u32| {
+        state[INSTRUCTIONS_PER_FRAME..INSTRUCTIONS_PER_FRAME + 4]
+            .copy_from_slice(&value.to_le_bytes());
+        state.clone()
+    };
+    let corrupt_data = "Save State Error: corrupt data";
+    assert_eq!(load_error(&set(0)), corrupt_data);
+    assert_eq!(
+        load_error(&set(MAX_INSTRUCTIONS_PER_FRAME + 1)),
+        corrupt_data
+    );
+
+    let mut emulator = emulator();
+    emulator
+        .load_state(&set(MAX_INSTRUCTIONS_PER_FRAME))
+        .unwrap();
+    assert_eq!(emulator.instructions_per_frame, MAX_INSTRUCTIONS_PER_FRAME);
+}
+
+#[test]
+fn state_of_another_random_generator_is_refused() {
+    let mut vip = emulator();
+    vip.rng = RandomKind::Vip.create(7);
+    assert_eq!(
+        load_error(&vip.save_state()),
+        "Save State Error: made with another random generator"
+    );
+
+    // An all zero xorshift state would only ever give 0
+    let mut state = emulator().save_state();
+    state[RANDOM + 4..].fill(0);
+    assert_eq!(
+        load_error(&state),
+        "Save State Error: made with another random generator"
+    );
+}
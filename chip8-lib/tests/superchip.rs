@@ -0,0 +1,174 @@
+// SUPER-CHIP tests: hi-res mode, scrolling, 16x16 sprites, the big font and the RPL flags
+use chip8_lib::{
+    constants::BIG_FONT_SET_START,
+    cpu::Emulator,
+    drivers::rom_driver::ROM,
+    errors::{Chip8Error, FaultKind},
+    framebuffer::FrameBuffer,
+    quirks::Quirks,
+};
+
+// Load a program and run its first `cycles` instructions
+fn run(quirks: Quirks, program: &[u8], cycles: usize) -> Result<Emulator, Chip8Error> {
+    let mut emulator = Emulator::new(quirks);
+    emulator
+        .load_rom(ROM::from_bytes(program, "test").unwrap())
+        .unwrap();
+    for _ in 0..cycles {
+        emulator.tick()?;
+    }
+    Ok(emulator)
+}
+
+fn schip(program: &[u8], cycles: usize) -> Emulator {
+    run(Quirks::superchip(), program, cycles).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// The lit pixels of a screen, in row-major order
+fn lit(screen: &FrameBuffer) -> Vec<(usize, usize)> {
+    let mut pixels = Vec::new();
+    for y in 0..screen.height {
+        for x in 0..screen.width {
+            if screen.get(x, y) != 0 {
+                pixels.push((x, y));
+            }
+        }
+    }
+    pixels
+}
+
+// A screen with one lit pixel at (x, y)
+fn one_pixel(width: usize, height: usize, x: usize, y: usize) -> FrameBuffer {
+    let mut screen = FrameBuffer::new(width, height);
+    screen.toggle(x, y, 1);
+    screen
+}
+
+#[test]
+fn hires_and_lowres_change_resolution_and_clear() {
+    // Draw the font's 0 in low res, then switch to hi-res and back
+    let program = [0xA0, 0x00, 0xD0, 0x05, 0x00, 0xFF, 0x00, 0xFE];
+    let emulator = schip(&program, 2);
+    assert!(!emulator.screen.is_hires());
+    assert!(!lit(&emulator.screen).is_empty());
+
+    let emulator = schip(&program, 3);
+    assert!(emulator.screen.is_hires());
+    assert_eq!((emulator.screen.width, emulator.screen.height), (128, 64));
+    assert!(lit(&emulator.screen).is_empty());
+
+    let emulator = schip(&program, 4);
+    assert_eq!((emulator.screen.width, emulator.screen.height), (64, 32));
+    assert!(lit(&emulator.screen).is_empty());
+}
+
+#[test]
+fn scroll_instructions_move_the_screen() {
+    // Draw one pixel at (8, 4): V0 = 8, V1 = 4, I = sprite, DXY1, then scroll
+    let draw = [0x60, 0x08, 0x61, 0x04, 0xA2, 0x0A, 0xD0, 0x11];
+    for (scroll, expected) in [
+        ([0x00, 0xC3], (8, 7)),  // Down 3 rows
+        ([0x00, 0xFB], (12, 4)), // Right 4 pixels
+        ([0x00, 0xFC], (4, 4)),  // Left 4 pixels
+    ] {
+        let program = [&draw[..], &scroll, &[0x80]].concat();
+        let emulator = schip(&program, 5);
+        assert_eq!(lit(&emulator.screen), [expected], "{:02X?}", scroll);
+        assert!(emulator.draw_flag);
+    }
+}
+
+#[test]
+fn scrolled_off_pixels_are_gone() {
+    // Pixel at (1, 0), scroll left 4 then right 4
+    let program = [
+        0x60, 0x01, 0x61, 0x00, 0xA2, 0x0E, 0xD0, 0x11, 0x00, 0xFC, 0x00, 0xFB, 0x12, 0x0C, 0x80,
+    ];
+    let emulator = schip(&program, 6);
+    assert!(lit(&emulator.screen).is_empty());
+}
+
+#[test]
+fn dxy0_draws_a_16x16_sprite() {
+    // Hi-res, I = a 16x16 sprite of 32 0xFF bytes, draw it at (120, 0) twice
+    let mut program = vec![0x00, 0xFF, 0x60, 0x78, 0x61, 0x00, 0xA2, 0x0E];
+    program.extend([0xD0, 0x10, 0xD0, 0x10, 0x12, 0x0C]);
+    program.extend([0xFF; 32]);
+
+    let emulator = schip(&program, 5);
+    let pixels = lit(&emulator.screen);
+    assert_eq!(pixels.len(), 8 * 16); // Clipped at the right edge
+    assert_eq!(pixels[0], (120, 0));
+    assert_eq!(pixels[pixels.len() - 1], (127, 15));
+    assert_eq!(emulator.v[0xF], 0);
+
+    // Drawing it again erases it and reports the collision
+    let emulator = schip(&program, 6);
+    assert!(lit(&emulator.screen).is_empty());
+    assert_eq!(emulator.v[0xF], 1);
+}
+
+#[test]
+fn big_font_points_at_the_10_byte_digits() {
+    let emulator = schip(&[0x60, 0x07, 0xF0, 0x30], 2);
+    assert_eq!(emulator.i, BIG_FONT_SET_START + 70);
+}
+
+#[test]
+fn rpl_flags_store_and_load() {
+    // V0 - V7 = 1 - 8, store them, clear V0 - V7, load them back
+    let mut program: Vec<u8> = (0..8).flat_map(|x| [0x60 + x, x + 1]).collect();
+    program.extend([0xF7, 0x75]);
+    program.extend((0..8).flat_map(|x| [0x60 + x, 0]));
+    program.extend([0xF7, 0x85]);
+
+    let emulator = schip(&program, 9);
+    assert_eq!(emulator.rpl[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+    let emulator = schip(&program, 18);
+    assert_eq!(emulator.v[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn rpl_flags_stop_at_v7_outside_xo_chip() {
+    for opcode in [0x75, 0x85] {
+        let e = run(Quirks::superchip(), &[0xF8, opcode], 1).err();
+        match e {
+            Some(Chip8Error::Fault(fault)) => {
+                assert_eq!(fault.kind, FaultKind::InvalidRegister(8));
+                assert_eq!(fault.pc, 0x200);
+            }
+            e => panic!("F8{:02X}: {:?}", opcode, e),
+        }
+        // XO-CHIP has all 16
+        let emulator = run(Quirks::xo_chip(), &[0xFF, opcode], 1).unwrap();
+        assert_eq!(emulator.pc, 0x202);
+    }
+}
+
+#[test]
+fn exit_halts_the_emulator() {
+    let emulator = schip(&[0x00, 0xFD, 0x60, 0x01], 3);
+    assert!(emulator.halted);
+    assert_eq!(emulator.pc, 0x202);
+    assert_eq!(emulator.v[0], 0);
+}
+
+#[test]
+fn framebuffer_scroll_fills_with_blank_pixels() {
+    let mut screen = one_pixel(64, 32, 0, 0);
+    screen.scroll(3, 2, 1);
+    assert_eq!(lit(&screen), [(3, 2)]);
+    screen.scroll(-3, -2, 1);
+    assert_eq!(lit(&screen), [(0, 0)]);
+    screen.scroll(-1, 0, 1);
+    assert!(lit(&screen).is_empty());
+}
+
+#[test]
+fn framebuffer_resize_clears() {
+    let mut screen = one_pixel(64, 32, 10, 10);
+    screen.resize(128, 64);
+    assert!(screen.is_hires());
+    assert_eq!(screen.pixels.len(), 128 * 64);
+    assert!(lit(&screen).is_empty());
+}
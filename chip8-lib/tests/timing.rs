@@ -0,0 +1,95 @@
+// Frame timing tests: `run_for` turns time into whole frames of instructions and timer ticks
+use std::time::Duration;
+
+use chip8_lib::{
+    constants::FRAME_DURATION, cpu::Emulator, drivers::rom_driver::ROM, quirks::Quirks,
+};
+
+// An emulator counting up in V0 forever, with both timers at 100
+fn counter(instructions_per_frame: u32) -> Emulator {
+    // V1 = 100, DT = V1, ST = V1, loop: V0 += 1, jump loop
+    let program = [0x61, 0x64, 0xF1, 0x15, 0xF1, 0x18, 0x70, 0x01, 0x12, 0x06];
+    let mut emulator = Emulator::new(Quirks::default());
+    emulator
+        .load_rom(ROM::from_bytes(&program, "counter").unwrap())
+        .unwrap();
+    for _ in 0..3 {
+        emulator.tick().unwrap();
+    }
+    emulator.cycles = 0;
+    emulator.instructions_per_frame = instructions_per_frame;
+    emulator
+}
+
+#[test]
+fn less_than_a_frame_runs_nothing() {
+    let mut emulator = counter(10);
+    assert_eq!(emulator.run_for(FRAME_DURATION / 2).unwrap(), 0);
+    assert_eq!(emulator.run_for(Duration::ZERO).unwrap(), 0);
+    assert_eq!((emulator.cycles, emulator.frames), (0, 0));
+    assert_eq!((emulator.dt, emulator.st), (100, 100));
+}
+
+#[test]
+fn leftover_time_carries_over_to_the_next_call() {
+    let mut emulator = counter(10);
+    // Three quarters of a frame twice make one frame, with half a frame left over
+    let three_quarters = FRAME_DURATION * 3 / 4;
+    assert_eq!(emulator.run_for(three_quarters).unwrap(), 0);
+    assert_eq!(emulator.run_for(three_quarters).unwrap(), 1);
+    assert_eq!(emulator.frames, 1);
+    // The leftover half plus three quarters is another frame
+    assert_eq!(emulator.run_for(three_quarters).unwrap(), 1);
+    assert_eq!(emulator.frames, 2);
+
+    // Splitting time into small steps runs the same frames as one big call
+    let mut stepped = counter(10);
+    for _ in 0..100 {
+        stepped.run_for(FRAME_DURATION / 7).unwrap();
+    }
+    let mut once = counter(10);
+    once.run_for(FRAME_DURATION / 7 * 100).unwrap();
+    assert_eq!(stepped.frames, once.frames);
+    assert_eq!(stepped.frames, 14);
+    assert_eq!(stepped.v[0], once.v[0]);
+}
+
+#[test]
+fn timers_drop_once_per_frame() {
+    let mut emulator = counter(10);
+    assert_eq!(emulator.run_for(FRAME_DURATION * 3).unwrap(), 3);
+    assert_eq!((emulator.dt, emulator.st), (97, 97));
+    assert_eq!(emulator.run_for(FRAME_DURATION).unwrap(), 1);
+    assert_eq!((emulator.dt, emulator.st), (96, 96));
+
+    // And stop at 0
+    emulator.run_for(FRAME_DURATION * 200).unwrap();
+    assert_eq!((emulator.dt, emulator.st), (0, 0));
+    assert_eq!(emulator.frames, 204);
+}
+
+#[test]
+fn instructions_per_frame_run_each_frame() {
+    for instructions_per_frame in [1, 11, 30] {
+        let mut emulator = counter(instructions_per_frame);
+        emulator.run_for(FRAME_DURATION * 5).unwrap();
+        let expected = 5 * instructions_per_frame as u64;
+        assert_eq!(emulator.cycles, expected);
+        // Every other instruction is the jump back
+        assert_eq!(emulator.v[0] as u64, expected.div_ceil(2));
+    }
+}
+
+#[test]
+fn halted_emulator_still_ticks_the_timers() {
+    // V0 = 5, DT = V0, exit
+    let program = [0x60, 0x05, 0xF0, 0x15, 0x00, 0xFD];
+    let mut emulator = Emulator::new(Quirks::superchip());
+    emulator
+        .load_rom(ROM::from_bytes(&program, "exit").unwrap())
+        .unwrap();
+    emulator.run_for(FRAME_DURATION * 2).unwrap();
+    assert!(emulator.halted);
+    assert_eq!(emulator.cycles, 3);
+    assert_eq!(emulator.dt, 3);
+}
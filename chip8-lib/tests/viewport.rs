@@ -0,0 +1,50 @@
+// Viewport tests: where the screen goes in windows of different sizes
+use chip8_lib::drivers::viewport::{viewport, ScaleMode, Viewport};
+
+fn rect(x: i32, y: i32, width: u32, height: u32) -> Viewport {
+    Viewport {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+#[test]
+fn integer_scale_uses_whole_pixels() {
+    // 10.9x horizontally and 10.3x vertically fits, so 10x, centered
+    assert_eq!(
+        viewport((700, 330), 64, 32, ScaleMode::Integer),
+        rect(30, 5, 640, 320)
+    );
+    // The hi-res screen in the same window is half the scale
+    assert_eq!(
+        viewport((700, 330), 128, 64, ScaleMode::Integer),
+        rect(30, 5, 640, 320)
+    );
+}
+
+#[test]
+fn aspect_scale_fills_one_side() {
+    assert_eq!(
+        viewport((700, 330), 64, 32, ScaleMode::Aspect),
+        rect(20, 0, 660, 330)
+    );
+    assert_eq!(
+        viewport((640, 640), 64, 32, ScaleMode::Aspect),
+        rect(0, 160, 640, 320)
+    );
+}
+
+#[test]
+fn window_too_small_for_1x() {
+    // Integer scaling can't go below 1x, so it shrinks like aspect scaling
+    for mode in [ScaleMode::Integer, ScaleMode::Aspect] {
+        assert_eq!(viewport((32, 20), 64, 32, mode), rect(0, 2, 32, 16));
+    }
+    // A minimized window still gets a pixel
+    assert_eq!(
+        viewport((0, 0), 64, 32, ScaleMode::Integer),
+        rect(0, 0, 1, 1)
+    );
+}
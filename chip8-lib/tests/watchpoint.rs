@@ -0,0 +1,194 @@
+// Watchpoint tests: parsing, and which instructions read, write and change what is watched
+use chip8_lib::{
+    cpu::Emulator,
+    debugger::{
+        watchpoint::{Compare, Condition, Location, Value, Watch, Watchpoint},
+        Debugger, StopReason,
+    },
+    drivers::rom_driver::ROM,
+    quirks::Quirks,
+};
+
+fn emulator(program: &[u8]) -> Emulator {
+    let mut emulator = Emulator::new(Quirks::superchip());
+    emulator.instructions_per_frame = 100;
+    emulator
+        .load_rom(ROM::from_bytes(program, "watch").unwrap())
+        .unwrap();
+    emulator
+}
+
+// Run a program with one watchpoint, returning the address of every instruction that
+// triggered it, until the program reaches its final `Jump` to itself
+fn hits(program: &[u8], watchpoint: &str) -> Vec<u16> {
+    let mut emulator = emulator(program);
+    let mut debugger = Debugger::new(&mut emulator);
+    let id = debugger.add_watchpoint(Watchpoint::parse(watchpoint).unwrap());
+    let end = 0x200 + program.len() as u16 - 2;
+    debugger.run_to(end);
+
+    let mut hits = Vec::new();
+    loop {
+        match debugger.run_frame().unwrap() {
+            Some(StopReason::Watchpoint(hit, pc)) => {
+                assert_eq!(hit, id);
+                hits.push(pc);
+                // Carry on to the end
+                debugger.run_to(end);
+            }
+            Some(StopReason::TargetReached) => return hits,
+            None => {}
+            reason => panic!("{:?}", reason),
+        }
+    }
+}
+
+// Reads and writes memory at 0x300 in every way there is
+const MEMORY: [u8; 20] = [
+    0xA3, 0x00, // 200: I = 300
+    0x60, 0x05, // 202: V0 = 5
+    0xF0, 0x55, // 204: Store V0 at 300
+    0xF0, 0x55, // 206: Store it again, the same value
+    0xF1, 0x65, // 208: Load V0 - V1 from 300
+    0xD0, 0x01, // 20A: Draw the byte at 300 as a sprite
+    0x60, 0x06, // 20C: V0 = 6
+    0xF0, 0x33, // 20E: BCD of V0 at 300 - 302
+    0xA3, 0x10, // 210: I = 310
+    0x12, 0x12, // 212: End
+];
+
+#[test]
+fn parse_watchpoints() {
+    let parse = |text| Watchpoint::parse(text).map(|watchpoint| watchpoint.watch);
+    assert_eq!(parse("write 3A0+4"), Some(Watch::Write(0x3A0, 4)));
+    assert_eq!(parse("w [3A0+4]"), Some(Watch::Write(0x3A0, 4)));
+    assert_eq!(parse("read 0x300"), Some(Watch::Read(0x300, 1)));
+    assert_eq!(parse("change v3"), Some(Watch::Change(Location::V(3))));
+    assert_eq!(parse("change VA"), Some(Watch::Change(Location::V(0xA))));
+    assert_eq!(parse("c dt"), Some(Watch::Change(Location::Dt)));
+    assert_eq!(
+        parse("change [a+2]"),
+        Some(Watch::Change(Location::Memory(0xA, 2)))
+    );
+
+    // Memory needs brackets, so a register name missing its V isn't an address
+    assert_eq!(parse("change a"), None);
+    assert_eq!(parse("change 3A0"), None);
+    assert_eq!(parse("change vg"), None);
+    assert_eq!(parse("change v10"), None);
+    assert_eq!(parse("read"), None);
+    assert_eq!(parse("read 300 400"), None);
+    assert_eq!(parse("jump 300"), None);
+}
+
+#[test]
+fn parse_conditions() {
+    let condition = |text| Watchpoint::parse(text).unwrap().condition;
+    assert_eq!(
+        condition("change i if v3 == 10"),
+        Some(Condition {
+            value: Value::V(3),
+            compare: Compare::Eq,
+            operand: 0x10,
+        })
+    );
+    assert_eq!(
+        condition("write 300 if [3A0] >= 80"),
+        Some(Condition {
+            value: Value::Memory(0x3A0),
+            compare: Compare::Ge,
+            operand: 0x80,
+        })
+    );
+    assert_eq!(condition("change v0"), None);
+    for text in [
+        "change i if v3",
+        "change i if v3 = 10",
+        "change i if x == 10",
+        "change i if v3 == 10 20",
+    ] {
+        assert_eq!(Watchpoint::parse(text), None, "{}", text);
+    }
+}
+
+#[test]
+fn read_watchpoint() {
+    // FX65 and the sprite read 300; FX55 and FX33 only write it
+    assert_eq!(hits(&MEMORY, "read 300"), [0x208, 0x20A]);
+    // Only the two byte load reaches 301
+    assert_eq!(hits(&MEMORY, "read 301"), [0x208]);
+    assert_eq!(hits(&MEMORY, "read 2F0+10"), []);
+}
+
+#[test]
+fn write_watchpoint() {
+    // Writing the value that is already there counts
+    assert_eq!(hits(&MEMORY, "write 300"), [0x204, 0x206, 0x20E]);
+    assert_eq!(hits(&MEMORY, "write 302"), [0x20E]);
+}
+
+#[test]
+fn change_watchpoint() {
+    // Only writes that change the byte
+    assert_eq!(hits(&MEMORY, "change [300]"), [0x204, 0x20E]);
+    // BCD of 6 writes 0, 0, 6: 301 keeps its 0
+    assert_eq!(hits(&MEMORY, "change [301]"), []);
+    assert_eq!(hits(&MEMORY, "change [301+2]"), [0x20E]);
+    assert_eq!(hits(&MEMORY, "change v0"), [0x202, 0x20C]);
+    assert_eq!(hits(&MEMORY, "change v1"), []);
+    assert_eq!(hits(&MEMORY, "change i"), [0x200, 0x210]);
+}
+
+#[test]
+fn timers_do_not_trigger_change_watchpoints() {
+    // V0 = 3, DT = V0, then loop until the timer has counted down to 0
+    let program = [
+        0x60, 0x03, 0xF0, 0x15, 0xF1, 0x07, 0x31, 0x00, 0x12, 0x04, 0x12, 0x0A,
+    ];
+    assert_eq!(hits(&program, "change dt"), [0x202]);
+}
+
+#[test]
+fn conditions_filter_hits() {
+    // Checked after the instruction: V0 is 5 at the first two writes, 6 at the BCD
+    assert_eq!(hits(&MEMORY, "write 300 if v0 == 5"), [0x204, 0x206]);
+    assert_eq!(hits(&MEMORY, "write 300 if v0 != 5"), [0x20E]);
+    assert_eq!(hits(&MEMORY, "change v0 if v0 > 5"), [0x20C]);
+    assert_eq!(hits(&MEMORY, "change [300] if [300] < 5"), [0x20E]);
+    assert_eq!(hits(&MEMORY, "read 300 if pc == 20A"), [0x208]);
+}
+
+#[test]
+fn registers_past_vf_never_change() {
+    let mut emulator = emulator(&MEMORY);
+    let mut debugger = Debugger::new(&mut emulator);
+    debugger.add_watchpoint(Watchpoint::new(Watch::Change(Location::V(16))));
+    let condition = Condition {
+        value: Value::V(16),
+        compare: Compare::Eq,
+        operand: 0,
+    };
+    debugger.add_watchpoint(Watchpoint::new(Watch::Change(Location::V(0))).when(condition));
+    debugger.run_to(0x212);
+    // Only the second, whose condition reads the missing register as 0
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::Watchpoint(1, 0x202))
+    );
+}
+
+#[test]
+fn removed_watchpoints_stop_triggering() {
+    let mut emulator = emulator(&MEMORY);
+    let mut debugger = Debugger::new(&mut emulator);
+    let id = debugger.add_watchpoint(Watchpoint::parse("write 300").unwrap());
+    assert_eq!(
+        debugger.remove_watchpoint(id).map(|w| w.watch),
+        Some(Watch::Write(0x300, 1))
+    );
+    debugger.run_to(0x212);
+    assert_eq!(
+        debugger.run_frame().unwrap(),
+        Some(StopReason::TargetReached)
+    );
+}
@@ -0,0 +1,164 @@
+// XO-CHIP tests: the long load, bitplanes, register ranges, audio and the 64K address space
+use chip8_lib::{
+    cpu::Emulator,
+    drivers::rom_driver::ROM,
+    errors::{Chip8Error, FaultKind},
+    framebuffer::FrameBuffer,
+    quirks::Quirks,
+};
+
+// Load a program and run its first `cycles` instructions
+fn run(quirks: Quirks, program: &[u8], cycles: usize) -> Result<Emulator, Chip8Error> {
+    let mut emulator = Emulator::new(quirks);
+    emulator
+        .load_rom(ROM::from_bytes(program, "test").unwrap())
+        .unwrap();
+    for _ in 0..cycles {
+        emulator.tick()?;
+    }
+    Ok(emulator)
+}
+
+fn xo(program: &[u8], cycles: usize) -> Emulator {
+    run(Quirks::xo_chip(), program, cycles).unwrap_or_else(|e| panic!("{}", e))
+}
+
+#[test]
+fn long_load_takes_the_next_word() {
+    let emulator = xo(&[0xF0, 0x00, 0xAB, 0xCD, 0x60, 0x01], 2);
+    assert_eq!(emulator.i, 0xABCD);
+    assert_eq!(emulator.pc, 0x206);
+    assert_eq!(emulator.v[0], 1);
+}
+
+#[test]
+fn skip_steps_over_a_whole_long_load() {
+    // V0 == 0, so 3000 skips the 4 byte F000 NNNN
+    let emulator = xo(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x60, 0x01], 1);
+    assert_eq!(emulator.pc, 0x206);
+    let emulator = xo(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x60, 0x01], 2);
+    assert_eq!((emulator.i, emulator.v[0]), (0, 1));
+
+    // Every skip does, not only 3XNN. With V0 = 0 and V1 = 1 each of these is taken.
+    for skip in [[0x40, 0x01], [0x50, 0x20], [0x90, 0x10], [0xE0, 0xA1]] {
+        let program = [&[0x61, 0x01], &skip[..], &[0xF0, 0x00, 0x12, 0x34]].concat();
+        let emulator = xo(&program, 2);
+        assert_eq!(emulator.pc, 0x208, "{:02X?}", skip);
+    }
+}
+
+#[test]
+fn skip_outside_xo_chip_moves_two_bytes() {
+    let emulator = run(Quirks::superchip(), &[0x30, 0x00, 0xF0, 0x00], 1).unwrap();
+    assert_eq!(emulator.pc, 0x204);
+}
+
+#[test]
+fn xo_chip_opcodes_are_invalid_in_other_modes() {
+    for opcode in [
+        [0xF0, 0x00], // F000 NNNN
+        [0xF1, 0x01], // PLANE 1
+        [0xF0, 0x02], // AUDIO
+        [0xF0, 0x3A], // PITCH V0
+        [0x50, 0x12], // SAVE V0 - V1
+        [0x50, 0x13], // LOAD V0 - V1
+        [0x00, 0xD1], // SCROLL-UP 1
+    ] {
+        for quirks in [Quirks::default(), Quirks::superchip()] {
+            match run(quirks, &opcode, 1) {
+                Err(Chip8Error::Fault(fault)) => {
+                    assert_eq!(fault.kind, FaultKind::InvalidInstruction)
+                }
+                result => panic!("{:02X?}: {:?}", opcode, result.map(|e| e.pc)),
+            }
+        }
+        xo(&opcode, 1);
+    }
+}
+
+#[test]
+fn draw_uses_the_selected_planes() {
+    // PLANE 2, draw an 8x1 sprite at (0, 0)
+    let program = [0xF2, 0x01, 0xA2, 0x08, 0xD0, 0x01, 0x12, 0x06, 0xC0];
+    let emulator = xo(&program, 3);
+    assert_eq!(emulator.planes, 2);
+    assert_eq!(emulator.screen.pixels[..3], [2, 2, 0]);
+
+    // PLANE 3 reads one sprite per plane, plane 1's first
+    let program = [0xF3, 0x01, 0xA2, 0x08, 0xD0, 0x01, 0x12, 0x06, 0x80, 0xC0];
+    let emulator = xo(&program, 3);
+    assert_eq!(emulator.screen.pixels[..3], [3, 2, 0]);
+}
+
+#[test]
+fn clear_only_touches_the_selected_planes() {
+    // Draw into planes 1 and 2, then PLANE 1 and clear
+    let program = [
+        0xF3, 0x01, 0xA2, 0x0C, 0xD0, 0x01, 0xF1, 0x01, 0x00, 0xE0, 0x12, 0x0A, 0x80, 0x80,
+    ];
+    let emulator = xo(&program, 5);
+    assert_eq!(emulator.screen.pixels[0], 2);
+}
+
+#[test]
+fn scroll_up_and_plane_masked_scrolls() {
+    // Pixel at (0, 5) in plane 1, scroll up 2
+    let program = [
+        0x61, 0x05, 0xA2, 0x0A, 0xD0, 0x11, 0x00, 0xD2, 0x12, 0x08, 0x80,
+    ];
+    let emulator = xo(&program, 4);
+    assert_eq!(emulator.screen.get(0, 3), 1);
+    assert_eq!(emulator.screen.get(0, 5), 0);
+
+    // Scrolling plane 2 leaves plane 1 where it is
+    let mut screen = FrameBuffer::new(64, 32);
+    screen.toggle(0, 0, 1);
+    screen.toggle(0, 0, 2);
+    screen.scroll(4, 0, 2);
+    assert_eq!(screen.get(0, 0), 1);
+    assert_eq!(screen.get(4, 0), 2);
+}
+
+#[test]
+fn register_ranges_store_and_load_in_either_order() {
+    // V1 = 1, V2 = 2, V3 = 3, I = 0x300, SAVE V1 - V3, SAVE V3 - V1 at 0x310
+    let program = [
+        0x61, 0x01, 0x62, 0x02, 0x63, 0x03, 0xA3, 0x00, 0x51, 0x32, 0xA3, 0x10, 0x53, 0x12,
+    ];
+    let emulator = xo(&program, 7);
+    assert_eq!(emulator.memory[0x300..0x303], [1, 2, 3]);
+    assert_eq!(emulator.memory[0x310..0x313], [3, 2, 1]);
+    // Ranges don't move I
+    assert_eq!(emulator.i, 0x310);
+
+    // I = 0x310, LOAD V4 - V6
+    let program = [&program[..], &[0x54, 0x63]].concat();
+    let emulator = xo(&program, 8);
+    assert_eq!(emulator.v[4..7], [3, 2, 1]);
+}
+
+#[test]
+fn audio_pattern_and_pitch() {
+    // I = pattern, AUDIO, V0 = 100, PITCH V0
+    let mut program = vec![0xA2, 0x0A, 0xF0, 0x02, 0x60, 0x64, 0xF0, 0x3A, 0x12, 0x08];
+    program.extend(0x01..=0x10);
+    let emulator = xo(&program, 1);
+    assert_eq!(emulator.buzzer_pattern(), None);
+
+    let emulator = xo(&program, 4);
+    let pattern: Vec<u8> = (0x01..=0x10).collect();
+    assert_eq!(emulator.buzzer_pattern().unwrap()[..], pattern[..]);
+    assert_eq!(emulator.pitch, 100);
+}
+
+#[test]
+fn xo_chip_has_64k_of_memory() {
+    let rom = ROM::from_bytes(&[0; 0x1000], "big").unwrap();
+    let mut emulator = Emulator::new(Quirks::xo_chip());
+    emulator.load_rom(rom).unwrap();
+    assert_eq!(emulator.memory.len(), 0x10000);
+
+    // I = 0xFFF0 with the long load, store V0 there
+    let emulator = xo(&[0x60, 0x2A, 0xF0, 0x00, 0xFF, 0xF0, 0xF0, 0x55], 3);
+    assert_eq!(emulator.memory[0xFFF0], 0x2A);
+}